@@ -1,15 +1,45 @@
 use color_eyre::eyre::Result;
 use rlok_lib::interpreter::Interpreter;
 use std::env;
+use std::io::{self, Write};
+use std::process;
+use std::thread;
 use tracing::Level;
 use tracing_subscriber::FmtSubscriber;
 
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
 fn main() -> Result<()> {
+    // The debug adapter and language server speak their protocols on stdout,
+    // so logs go to stderr.
+    let protocol = env::args()
+        .nth(1)
+        .is_some_and(|mode| mode == "dap" || mode == "lsp");
     let subscriber = FmtSubscriber::builder()
         .with_max_level(Level::TRACE)
+        .with_writer(move || -> Box<dyn Write> {
+            if protocol {
+                Box::new(io::stderr())
+            } else {
+                Box::new(io::stdout())
+            }
+        })
         .finish();
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
     color_eyre::install()?;
-    Interpreter::build().start(env::args().collect())?;
+    // Each Lox call nests several native calls in the tree-walker, so give it
+    // room to reach the call depth limit instead of overflowing the stack.
+    let had_runtime_error = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(|| -> Result<bool> {
+            let mut interpreter = Interpreter::build();
+            interpreter.start(env::args().collect())?;
+            Ok(interpreter.had_runtime_error())
+        })?
+        .join()
+        .expect("interpreter thread panicked")?;
+    if had_runtime_error {
+        process::exit(70);
+    }
     Ok(())
 }
@@ -0,0 +1,348 @@
+use super::expression::Expr;
+use super::pattern::MatchArm;
+use super::statement::Statement;
+use super::tokens::Token;
+
+/// Prints syntax trees as parenthesized prefix expressions, like the book's
+/// `AstPrinter`, so `1 + 2 * 3;` becomes `(; (+ 1 (* 2 3)))`.
+pub struct AstPrinter;
+
+impl AstPrinter {
+    pub fn statement(stmt: &Statement) -> String {
+        match stmt {
+            Statement::Expression {
+                span: _,
+                expression,
+            } => Self::parenthesize(";", &[Self::expr(expression)]),
+            Statement::Print {
+                span: _,
+                expression,
+            } => Self::parenthesize("print", &[Self::expr(expression)]),
+            Statement::Var {
+                span: _,
+                name,
+                expression,
+            } => {
+                let mut parts = vec![name.lexeme.clone()];
+                parts.extend(expression.iter().map(Self::expr));
+                Self::parenthesize("var", &parts)
+            }
+            Statement::Block {
+                span: _,
+                statements,
+            } => Self::parenthesize("block", &Self::statements(statements)),
+            Statement::If {
+                span: _,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let mut parts = vec![Self::expr(condition), Self::statement(then_branch)];
+                parts.extend(else_branch.iter().map(|els| Self::statement(els)));
+                Self::parenthesize("if", &parts)
+            }
+            Statement::While {
+                span: _,
+                condition,
+                body,
+                label,
+            } => {
+                let mut parts = Self::label(label);
+                parts.push(Self::expr(condition));
+                parts.push(Self::statement(body));
+                Self::parenthesize("while", &parts)
+            }
+            Statement::For {
+                span: _,
+                initializer,
+                condition,
+                increment,
+                body,
+                label,
+            } => {
+                let mut parts = Self::label(label);
+                parts.push(
+                    initializer
+                        .as_ref()
+                        .map_or("_".into(), |init| Self::statement(init)),
+                );
+                parts.push(condition.as_ref().map_or("_".into(), Self::expr));
+                parts.push(increment.as_ref().map_or("_".into(), Self::expr));
+                parts.push(Self::statement(body));
+                Self::parenthesize("for", &parts)
+            }
+            Statement::Break {
+                span: _,
+                keyword: _,
+                label,
+            } => {
+                let parts: Vec<String> = label.iter().map(|label| label.lexeme.clone()).collect();
+                Self::parenthesize("break", &parts)
+            }
+            Statement::Continue {
+                span: _,
+                keyword: _,
+                label,
+            } => {
+                let parts: Vec<String> = label.iter().map(|label| label.lexeme.clone()).collect();
+                Self::parenthesize("continue", &parts)
+            }
+            Statement::Function {
+                span: _,
+                name,
+                params,
+                body,
+            } => {
+                let mut parts = vec![name.lexeme.clone(), Self::names(params)];
+                parts.extend(Self::statements(body));
+                Self::parenthesize("fun", &parts)
+            }
+            Statement::Return {
+                span: _,
+                keyword: _,
+                value,
+            } => {
+                let parts: Vec<String> = value.iter().map(Self::expr).collect();
+                Self::parenthesize("return", &parts)
+            }
+            Statement::Class {
+                span: _,
+                name,
+                superclass,
+                methods,
+            } => {
+                let mut parts = vec![name.lexeme.clone()];
+                if let Some(superclass) = superclass {
+                    parts.push(format!("< {}", Self::expr(superclass)));
+                }
+                parts.extend(methods.iter().map(Self::statement));
+                Self::parenthesize("class", &parts)
+            }
+            Statement::Import {
+                span: _,
+                path,
+                alias,
+                names,
+            } => {
+                let mut parts = vec![path.lexeme.clone()];
+                if !names.is_empty() {
+                    parts.push(Self::names(names));
+                }
+                if let Some(alias) = alias {
+                    parts.push(format!("as {}", alias.lexeme));
+                }
+                Self::parenthesize("import", &parts)
+            }
+            Statement::Throw {
+                span: _,
+                keyword: _,
+                value,
+            } => Self::parenthesize("throw", &[Self::expr(value)]),
+            Statement::Try {
+                span: _,
+                body,
+                catch,
+                finally,
+            } => {
+                let mut parts = vec![Self::statement(body)];
+                if let Some((name, handler)) = catch {
+                    parts.push(Self::parenthesize(
+                        "catch",
+                        &[name.lexeme.clone(), Self::statement(handler)],
+                    ));
+                }
+                if let Some(finally) = finally {
+                    parts.push(Self::parenthesize("finally", &[Self::statement(finally)]));
+                }
+                Self::parenthesize("try", &parts)
+            }
+            Statement::Match {
+                span: _,
+                keyword: _,
+                subject,
+                arms,
+            } => {
+                let mut parts = vec![Self::expr(subject)];
+                parts.extend(arms.iter().map(Self::arm));
+                Self::parenthesize("match", &parts)
+            }
+        }
+    }
+
+    pub fn expr(expr: &Expr) -> String {
+        match expr {
+            Expr::Binary {
+                span: _,
+                left,
+                operator,
+                right,
+            }
+            | Expr::Logcial {
+                span: _,
+                left,
+                operator,
+                right,
+            } => Self::parenthesize(&operator.lexeme, &[Self::expr(left), Self::expr(right)]),
+            Expr::Grouping {
+                span: _,
+                expression,
+            } => Self::parenthesize("group", &[Self::expr(expression)]),
+            Expr::Literal { span: _, value } => {
+                value.as_ref().map_or("nil".into(), |value| value.quoted())
+            }
+            Expr::Unary {
+                span: _,
+                operator,
+                right,
+            } => Self::parenthesize(&operator.lexeme, &[Self::expr(right)]),
+            Expr::Variable {
+                span: _,
+                name,
+                depth: _,
+            } => name.lexeme.clone(),
+            Expr::Assign {
+                span: _,
+                name,
+                value,
+                depth: _,
+            } => Self::parenthesize("=", &[name.lexeme.clone(), Self::expr(value)]),
+            Expr::Increment {
+                span: _,
+                name,
+                operator,
+                prefix,
+                depth: _,
+            } => {
+                let fixity = if *prefix { "prefix" } else { "postfix" };
+                Self::parenthesize(
+                    &format!("{} {}", fixity, operator.lexeme),
+                    std::slice::from_ref(&name.lexeme),
+                )
+            }
+            Expr::Conditional {
+                span: _,
+                condition,
+                then_branch,
+                else_branch,
+            } => Self::parenthesize(
+                "?",
+                &[
+                    Self::expr(condition),
+                    Self::expr(then_branch),
+                    Self::expr(else_branch),
+                ],
+            ),
+            Expr::Call {
+                span: _,
+                callee,
+                paren: _,
+                arguments,
+            } => {
+                let mut parts = vec![Self::expr(callee)];
+                parts.extend(arguments.iter().map(Self::expr));
+                Self::parenthesize("call", &parts)
+            }
+            Expr::Get {
+                span: _,
+                object,
+                name,
+            } => Self::parenthesize(".", &[Self::expr(object), name.lexeme.clone()]),
+            Expr::Set {
+                span: _,
+                object,
+                name,
+                value,
+            } => Self::parenthesize(
+                "=",
+                &[
+                    Self::parenthesize(".", &[Self::expr(object), name.lexeme.clone()]),
+                    Self::expr(value),
+                ],
+            ),
+            Expr::List { span: _, elements } => {
+                let parts: Vec<String> = elements.iter().map(Self::expr).collect();
+                Self::parenthesize("list", &parts)
+            }
+            Expr::Stringify {
+                span: _,
+                expression,
+            } => Self::parenthesize("str", &[Self::expr(expression)]),
+            Expr::Map { span: _, entries } => {
+                let parts: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| format!("({} {})", Self::expr(key), Self::expr(value)))
+                    .collect();
+                Self::parenthesize("map", &parts)
+            }
+            Expr::Index {
+                span: _,
+                object,
+                bracket: _,
+                index,
+            } => Self::parenthesize("[]", &[Self::expr(object), Self::expr(index)]),
+            Expr::SetIndex {
+                span: _,
+                object,
+                bracket: _,
+                index,
+                value,
+            } => Self::parenthesize(
+                "=",
+                &[
+                    Self::parenthesize("[]", &[Self::expr(object), Self::expr(index)]),
+                    Self::expr(value),
+                ],
+            ),
+            Expr::This {
+                span: _,
+                keyword: _,
+                depth: _,
+            } => "this".into(),
+            Expr::Super {
+                span: _,
+                keyword: _,
+                method,
+                depth: _,
+            } => Self::parenthesize("super", std::slice::from_ref(&method.lexeme)),
+            Expr::Lambda {
+                span: _,
+                declaration,
+            } => Self::statement(declaration),
+        }
+    }
+
+    fn arm(arm: &MatchArm) -> String {
+        let mut parts = vec![arm.pattern.to_string()];
+        if let Some(guard) = &arm.guard {
+            parts.push(Self::parenthesize("if", &[Self::expr(guard)]));
+        }
+        parts.push(Self::statement(&arm.body));
+        Self::parenthesize("=>", &parts)
+    }
+
+    fn statements(statements: &[Statement]) -> Vec<String> {
+        statements.iter().map(Self::statement).collect()
+    }
+
+    fn names(names: &[Token]) -> String {
+        let names: Vec<&str> = names.iter().map(|name| name.lexeme.as_str()).collect();
+        format!("({})", names.join(" "))
+    }
+
+    fn label(label: &Option<Token>) -> Vec<String> {
+        label
+            .iter()
+            .map(|label| format!("{}:", label.lexeme))
+            .collect()
+    }
+
+    fn parenthesize(name: &str, parts: &[String]) -> String {
+        let mut out = format!("({}", name);
+        for part in parts {
+            out.push(' ');
+            out.push_str(part);
+        }
+        out.push(')');
+        out
+    }
+}
@@ -0,0 +1,258 @@
+use super::span::Span;
+use super::value::Value;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpCode {
+    Constant,
+    Nil,
+    True,
+    False,
+    Pop,
+    GetLocal,
+    SetLocal,
+    GetGlobal,
+    DefineGlobal,
+    SetGlobal,
+    GetUpvalue,
+    SetUpvalue,
+    GetProperty,
+    SetProperty,
+    GetSuper,
+    Equal,
+    Greater,
+    Less,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    FloorDivide,
+    Power,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    BitNot,
+    Not,
+    Negate,
+    Print,
+    Jump,
+    JumpIfFalse,
+    Loop,
+    Call,
+    Closure,
+    CloseUpvalue,
+    Return,
+    Class,
+    Inherit,
+    Method,
+    BuildList,
+    BuildMap,
+    GetIndex,
+    SetIndex,
+    Stringify,
+    Import,
+    PushHandler,
+    PopHandler,
+    Throw,
+    MatchLiteral,
+    MatchRange,
+}
+
+impl OpCode {
+    const ALL: [OpCode; 55] = [
+        OpCode::Constant,
+        OpCode::Nil,
+        OpCode::True,
+        OpCode::False,
+        OpCode::Pop,
+        OpCode::GetLocal,
+        OpCode::SetLocal,
+        OpCode::GetGlobal,
+        OpCode::DefineGlobal,
+        OpCode::SetGlobal,
+        OpCode::GetUpvalue,
+        OpCode::SetUpvalue,
+        OpCode::GetProperty,
+        OpCode::SetProperty,
+        OpCode::GetSuper,
+        OpCode::Equal,
+        OpCode::Greater,
+        OpCode::Less,
+        OpCode::Add,
+        OpCode::Subtract,
+        OpCode::Multiply,
+        OpCode::Divide,
+        OpCode::Modulo,
+        OpCode::FloorDivide,
+        OpCode::Power,
+        OpCode::BitAnd,
+        OpCode::BitOr,
+        OpCode::BitXor,
+        OpCode::ShiftLeft,
+        OpCode::ShiftRight,
+        OpCode::BitNot,
+        OpCode::Not,
+        OpCode::Negate,
+        OpCode::Print,
+        OpCode::Jump,
+        OpCode::JumpIfFalse,
+        OpCode::Loop,
+        OpCode::Call,
+        OpCode::Closure,
+        OpCode::CloseUpvalue,
+        OpCode::Return,
+        OpCode::Class,
+        OpCode::Inherit,
+        OpCode::Method,
+        OpCode::BuildList,
+        OpCode::BuildMap,
+        OpCode::GetIndex,
+        OpCode::SetIndex,
+        OpCode::Stringify,
+        OpCode::Import,
+        OpCode::PushHandler,
+        OpCode::PopHandler,
+        OpCode::Throw,
+        OpCode::MatchLiteral,
+        OpCode::MatchRange,
+    ];
+
+    pub fn from_byte(byte: u8) -> Option<OpCode> {
+        OpCode::ALL.get(byte as usize).copied()
+    }
+}
+
+impl std::fmt::Display for OpCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<u8>,
+    pub spans: Vec<Span>,
+    pub constants: Vec<Value>,
+}
+
+impl Chunk {
+    pub fn write(&mut self, byte: u8, span: Span) {
+        self.code.push(byte);
+        self.spans.push(span);
+    }
+
+    pub fn write_op(&mut self, op: OpCode, span: Span) {
+        self.write(op as u8, span);
+    }
+
+    pub fn write_u16(&mut self, value: u16, span: Span) {
+        self.write((value >> 8) as u8, span);
+        self.write(value as u8, span);
+    }
+
+    pub fn read_u16(&self, offset: usize) -> u16 {
+        ((self.code[offset] as u16) << 8) | self.code[offset + 1] as u16
+    }
+
+    pub fn add_constant(&mut self, value: Value) -> usize {
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    pub fn disassemble(&self, name: &str) -> String {
+        let mut output = format!("== {} ==\n", name);
+        let mut offset = 0;
+        while offset < self.code.len() {
+            let (line, next) = self.disassemble_instruction(offset);
+            output.push_str(&line);
+            output.push('\n');
+            offset = next;
+        }
+        output
+    }
+
+    fn disassemble_instruction(&self, offset: usize) -> (String, usize) {
+        let prefix = format!("{:04} {:>4} ", offset, self.spans[offset].line());
+        let Some(op) = OpCode::from_byte(self.code[offset]) else {
+            return (
+                format!("{}Unknown {}", prefix, self.code[offset]),
+                offset + 1,
+            );
+        };
+        match op {
+            OpCode::Constant
+            | OpCode::GetGlobal
+            | OpCode::DefineGlobal
+            | OpCode::SetGlobal
+            | OpCode::GetProperty
+            | OpCode::SetProperty
+            | OpCode::GetSuper
+            | OpCode::Class
+            | OpCode::Method
+            | OpCode::Import => {
+                let index = self.read_u16(offset + 1) as usize;
+                (
+                    format!(
+                        "{}{:<16} {:4} '{}'",
+                        prefix, op, index, self.constants[index]
+                    ),
+                    offset + 3,
+                )
+            }
+            OpCode::GetLocal
+            | OpCode::SetLocal
+            | OpCode::GetUpvalue
+            | OpCode::SetUpvalue
+            | OpCode::Call
+            | OpCode::MatchRange => (
+                format!("{}{:<16} {:4}", prefix, op, self.code[offset + 1]),
+                offset + 2,
+            ),
+            OpCode::BuildList | OpCode::BuildMap => (
+                format!("{}{:<16} {:4}", prefix, op, self.read_u16(offset + 1)),
+                offset + 3,
+            ),
+            OpCode::Jump | OpCode::JumpIfFalse | OpCode::PushHandler => {
+                let jump = self.read_u16(offset + 1) as usize;
+                (
+                    format!("{}{:<16} {:4} -> {}", prefix, op, offset, offset + 3 + jump),
+                    offset + 3,
+                )
+            }
+            OpCode::Loop => {
+                let jump = self.read_u16(offset + 1) as usize;
+                (
+                    format!("{}{:<16} {:4} -> {}", prefix, op, offset, offset + 3 - jump),
+                    offset + 3,
+                )
+            }
+            OpCode::Closure => {
+                let index = self.read_u16(offset + 1) as usize;
+                let mut output =
+                    format!("{}{:<16} {:4} {}", prefix, op, index, self.constants[index]);
+                let mut next = offset + 3;
+                if let Value::Function(function) = &self.constants[index] {
+                    for _ in 0..function.upvalue_count {
+                        let kind = if self.code[next] == 1 {
+                            "local"
+                        } else {
+                            "upvalue"
+                        };
+                        output.push_str(&format!(
+                            "\n{:04}    |                     {} {}",
+                            next,
+                            kind,
+                            self.code[next + 1]
+                        ));
+                        next += 2;
+                    }
+                }
+                (output, next)
+            }
+            _ => (format!("{}{}", prefix, op), offset + 1),
+        }
+    }
+}
@@ -0,0 +1,1106 @@
+use super::chunk::OpCode;
+use super::error_handler::CompilerError;
+use super::expression::Expr;
+use super::lit::LitType;
+use super::pattern::{MatchArm, Pattern};
+use super::span::Span;
+use super::statement::Statement;
+use super::tokens::{Token, TokenType};
+use super::value::{Function, Value};
+use color_eyre::eyre::{Report, Result};
+use std::sync::Arc;
+use tracing::{span, trace, Level};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FunctionKind {
+    Script,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Debug)]
+struct Local {
+    name: String,
+    depth: usize,
+    is_captured: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct UpvalueSlot {
+    index: u8,
+    is_local: bool,
+}
+
+#[derive(Debug)]
+struct LoopState {
+    label: Option<String>,
+    scope_depth: usize,
+    handlers: usize,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct FunctionState {
+    function: Function,
+    kind: FunctionKind,
+    locals: Vec<Local>,
+    upvalues: Vec<UpvalueSlot>,
+    scope_depth: usize,
+    loops: Vec<LoopState>,
+    /// The finally block of every try whose error handler is installed here.
+    handlers: Vec<Option<Box<Statement>>>,
+}
+
+impl FunctionState {
+    fn new(kind: FunctionKind, name: String) -> Self {
+        let receiver = match kind {
+            FunctionKind::Method | FunctionKind::Initializer => "this",
+            _ => "",
+        };
+        FunctionState {
+            function: Function {
+                name,
+                ..Default::default()
+            },
+            kind,
+            locals: vec![Local {
+                name: receiver.into(),
+                depth: 0,
+                is_captured: false,
+            }],
+            upvalues: Vec::new(),
+            scope_depth: 0,
+            loops: Vec::new(),
+            handlers: Vec::new(),
+        }
+    }
+}
+
+pub struct Compiler {
+    states: Vec<FunctionState>,
+    span: Span,
+    is_repl: bool,
+}
+
+impl Compiler {
+    pub fn new(is_repl: bool) -> Self {
+        Compiler {
+            states: Vec::new(),
+            span: Span::default(),
+            is_repl,
+        }
+    }
+
+    pub fn compile(&mut self, statements: &[Statement]) -> Result<Vec<Arc<Function>>> {
+        let span = span!(Level::TRACE, "compiling");
+        let _enter = span.enter();
+        let mut scripts = Vec::new();
+        for stmt in statements {
+            self.states
+                .push(FunctionState::new(FunctionKind::Script, "script".into()));
+            let result = self.statement(stmt).and_then(|_| self.emit_return());
+            let state = self.states.pop().unwrap();
+            result?;
+            trace!(chunk = %state.function.chunk.disassemble("script"), "Compiled statement");
+            scripts.push(Arc::new(state.function));
+        }
+        Ok(scripts)
+    }
+
+    fn state(&mut self) -> &mut FunctionState {
+        self.states.last_mut().unwrap()
+    }
+
+    fn see(&mut self, token: &Token) {
+        self.mark(token.into());
+    }
+
+    /// Attributes the code emitted next, and any error it raises, to `span`.
+    fn mark(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    fn emit_byte(&mut self, byte: u8) {
+        let span = self.span;
+        self.state().function.chunk.write(byte, span);
+    }
+
+    fn emit_op(&mut self, op: OpCode) {
+        let span = self.span;
+        self.state().function.chunk.write_op(op, span);
+    }
+
+    fn emit_u16(&mut self, value: u16) {
+        let span = self.span;
+        self.state().function.chunk.write_u16(value, span);
+    }
+
+    fn emit_op_u16(&mut self, op: OpCode, operand: u16) {
+        self.emit_op(op);
+        self.emit_u16(operand);
+    }
+
+    fn emit_op_u8(&mut self, op: OpCode, operand: u8) {
+        self.emit_op(op);
+        self.emit_byte(operand);
+    }
+
+    /// Pushes what a function returns when it doesn't say: nil, or `this` from an initializer.
+    fn emit_implicit_return_value(&mut self) {
+        if self.state().kind == FunctionKind::Initializer {
+            self.emit_op_u8(OpCode::GetLocal, 0);
+        } else {
+            self.emit_op(OpCode::Nil);
+        }
+    }
+
+    fn emit_return(&mut self) -> Result<()> {
+        self.emit_implicit_return_value();
+        self.emit_op(OpCode::Return);
+        Ok(())
+    }
+
+    fn make_constant(&mut self, value: Value) -> Result<u16> {
+        let index = self.state().function.chunk.add_constant(value);
+        if index > u16::MAX as usize {
+            return Err(Report::new(CompilerError::TooManyConstants(
+                self.span.line(),
+            )));
+        }
+        Ok(index as u16)
+    }
+
+    fn identifier_constant(&mut self, name: &str) -> Result<u16> {
+        self.make_constant(Value::Str(name.into()))
+    }
+
+    fn emit_jump(&mut self, op: OpCode) -> usize {
+        self.emit_op_u16(op, u16::MAX);
+        self.state().function.chunk.code.len() - 2
+    }
+
+    fn patch_jump(&mut self, offset: usize) -> Result<()> {
+        let line = self.span.line();
+        let code = &mut self.state().function.chunk.code;
+        let jump = code.len() - offset - 2;
+        if jump > u16::MAX as usize {
+            return Err(Report::new(CompilerError::JumpTooLarge(line)));
+        }
+        code[offset] = (jump >> 8) as u8;
+        code[offset + 1] = jump as u8;
+        Ok(())
+    }
+
+    fn emit_loop(&mut self, loop_start: usize) -> Result<()> {
+        self.emit_op(OpCode::Loop);
+        let offset = self.state().function.chunk.code.len() - loop_start + 2;
+        if offset > u16::MAX as usize {
+            return Err(Report::new(CompilerError::LoopTooLarge(self.span.line())));
+        }
+        self.emit_u16(offset as u16);
+        Ok(())
+    }
+
+    fn begin_scope(&mut self) {
+        self.state().scope_depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        let state = self.state();
+        state.scope_depth -= 1;
+        let depth = state.scope_depth;
+        while let Some(local) = self.state().locals.last() {
+            if local.depth <= depth {
+                break;
+            }
+            if local.is_captured {
+                self.emit_op(OpCode::CloseUpvalue);
+            } else {
+                self.emit_op(OpCode::Pop);
+            }
+            self.state().locals.pop();
+        }
+    }
+
+    fn begin_loop(&mut self, label: &Option<Token>) {
+        let scope_depth = self.state().scope_depth;
+        let handlers = self.state().handlers.len();
+        self.state().loops.push(LoopState {
+            label: label.as_ref().map(|label| label.lexeme.clone()),
+            scope_depth,
+            handlers,
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+    }
+
+    fn loop_body(&mut self, label: &Option<Token>, body: &Statement) -> Result<Vec<usize>> {
+        self.begin_loop(label);
+        let result = self.statement(body);
+        let state = self.state().loops.pop().unwrap();
+        result?;
+        for jump in state.continues {
+            self.patch_jump(jump)?;
+        }
+        Ok(state.breaks)
+    }
+
+    fn jump_statement(&mut self, keyword: &Token, label: &Option<Token>) -> Result<()> {
+        self.see(keyword);
+        let target = label.as_ref().map(|label| label.lexeme.clone());
+        let index = self
+            .state()
+            .loops
+            .iter()
+            .rposition(|l| target.is_none() || l.label == target)
+            .unwrap();
+        let handlers = self.state().loops[index].handlers;
+        self.leave_handlers(handlers)?;
+        // A closure made in an earlier pass of an inner loop may have captured
+        // any of these, so close them all rather than trusting is_captured.
+        let depth = self.state().loops[index].scope_depth;
+        let count = self
+            .state()
+            .locals
+            .iter()
+            .rev()
+            .take_while(|local| local.depth > depth)
+            .count();
+        for _ in 0..count {
+            self.emit_op(OpCode::CloseUpvalue);
+        }
+        let jump = self.emit_jump(OpCode::Jump);
+        let state = &mut self.state().loops[index];
+        if keyword.ty == TokenType::BREAK {
+            state.breaks.push(jump);
+        } else {
+            state.continues.push(jump);
+        }
+        Ok(())
+    }
+
+    /// Removes the handlers of the try blocks a return, break or continue jumps
+    /// out of, running each one's finally block on the way.
+    fn leave_handlers(&mut self, depth: usize) -> Result<()> {
+        let handlers = self.state().handlers.clone();
+        while self.state().handlers.len() > depth {
+            let finally = self.state().handlers.pop().unwrap();
+            self.emit_op(OpCode::PopHandler);
+            if let Some(finally) = finally {
+                self.statement(&finally)?;
+            }
+        }
+        self.state().handlers = handlers;
+        Ok(())
+    }
+
+    /// Runs a finally block for an error that wasn't caught, then throws it on.
+    /// The error sits on top of `hidden` values the handler left on the stack.
+    fn rethrow_after(&mut self, finally: &Statement, hidden: usize) -> Result<()> {
+        self.begin_scope();
+        for _ in 0..hidden {
+            self.add_local("")?;
+        }
+        let error = (self.state().locals.len() - 1) as u8;
+        self.statement(finally)?;
+        self.emit_op_u8(OpCode::GetLocal, error);
+        self.emit_op(OpCode::Throw);
+        self.end_scope();
+        Ok(())
+    }
+
+    /// Keeps the matched value in a hidden local, which a binding pattern names
+    /// for the length of its arm.
+    fn match_statement(&mut self, subject: &Expr, arms: &[MatchArm]) -> Result<()> {
+        self.begin_scope();
+        self.expression(subject)?;
+        self.add_local("")?;
+        let slot = self.state().locals.len() - 1;
+        let mut exits = Vec::new();
+        for arm in arms {
+            self.pattern(&arm.pattern, slot as u8)?;
+            let mut misses = vec![self.emit_jump(OpCode::JumpIfFalse)];
+            self.emit_op(OpCode::Pop);
+            if let Some(name) = arm.pattern.binding() {
+                self.state().locals[slot].name = name.lexeme.clone();
+            }
+            if let Some(guard) = &arm.guard {
+                self.expression(guard)?;
+                misses.push(self.emit_jump(OpCode::JumpIfFalse));
+                self.emit_op(OpCode::Pop);
+            }
+            self.statement(&arm.body)?;
+            self.state().locals[slot].name = String::new();
+            exits.push(self.emit_jump(OpCode::Jump));
+            for miss in misses {
+                self.patch_jump(miss)?;
+            }
+            self.emit_op(OpCode::Pop);
+        }
+        for exit in exits {
+            self.patch_jump(exit)?;
+        }
+        self.end_scope();
+        Ok(())
+    }
+
+    /// Emits a test of the value in `slot` against `pattern`, leaving a bool.
+    fn pattern(&mut self, pattern: &Pattern, slot: u8) -> Result<()> {
+        match pattern {
+            Pattern::Wildcard(_) | Pattern::Binding(_) => self.emit_op(OpCode::True),
+            Pattern::Literal(literal, span) => {
+                self.emit_op_u8(OpCode::GetLocal, slot);
+                self.expression(&Expr::Literal {
+                    span: *span,
+                    value: Some(literal.clone()),
+                })?;
+                self.emit_op(OpCode::MatchLiteral);
+            }
+            Pattern::Range {
+                span: _,
+                start,
+                end,
+                inclusive,
+            } => {
+                self.emit_op_u8(OpCode::GetLocal, slot);
+                let start = self.make_constant((*start).into())?;
+                self.emit_op_u16(OpCode::Constant, start);
+                let end = self.make_constant((*end).into())?;
+                self.emit_op_u16(OpCode::Constant, end);
+                self.emit_op_u8(OpCode::MatchRange, *inclusive as u8);
+            }
+            Pattern::Or(alternatives, _) => {
+                let mut matched = Vec::new();
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i > 0 {
+                        let next = self.emit_jump(OpCode::JumpIfFalse);
+                        matched.push(self.emit_jump(OpCode::Jump));
+                        self.patch_jump(next)?;
+                        self.emit_op(OpCode::Pop);
+                    }
+                    self.pattern(alternative, slot)?;
+                }
+                for jump in matched {
+                    self.patch_jump(jump)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn try_statement(
+        &mut self,
+        body: &Statement,
+        catch: &Option<(Token, Box<Statement>)>,
+        finally: &Option<Box<Statement>>,
+    ) -> Result<()> {
+        let handler = self.emit_jump(OpCode::PushHandler);
+        self.state().handlers.push(finally.clone());
+        let result = self.statement(body);
+        self.state().handlers.pop();
+        result?;
+        self.emit_op(OpCode::PopHandler);
+        let mut exits = vec![self.emit_jump(OpCode::Jump)];
+        // The VM jumps here with the error pushed on top of the try's locals.
+        self.patch_jump(handler)?;
+        match (catch, finally) {
+            (Some((name, handler_body)), finally) => {
+                self.see(name);
+                self.begin_scope();
+                self.add_local(&name.lexeme)?;
+                let rethrow = finally
+                    .as_ref()
+                    .map(|_| self.emit_jump(OpCode::PushHandler));
+                self.state().handlers.push(finally.clone());
+                let result = self.statement(handler_body);
+                self.state().handlers.pop();
+                result?;
+                if let (Some(rethrow), Some(finally)) = (rethrow, finally) {
+                    self.emit_op(OpCode::PopHandler);
+                    self.end_scope();
+                    exits.push(self.emit_jump(OpCode::Jump));
+                    self.patch_jump(rethrow)?;
+                    self.rethrow_after(finally, 2)?;
+                } else {
+                    self.end_scope();
+                }
+            }
+            (None, Some(finally)) => self.rethrow_after(finally, 1)?,
+            (None, None) => (),
+        }
+        for exit in exits {
+            self.patch_jump(exit)?;
+        }
+        if let Some(finally) = finally {
+            self.statement(finally)?;
+        }
+        Ok(())
+    }
+
+    fn add_local(&mut self, name: &str) -> Result<()> {
+        if self.state().locals.len() > u8::MAX as usize {
+            return Err(Report::new(CompilerError::TooManyLocals(self.span.line())));
+        }
+        let depth = self.state().scope_depth;
+        self.state().locals.push(Local {
+            name: name.into(),
+            depth,
+            is_captured: false,
+        });
+        Ok(())
+    }
+
+    fn resolve_local(&self, state: usize, name: &str) -> Option<u8> {
+        self.states[state]
+            .locals
+            .iter()
+            .rposition(|local| local.name == name)
+            .map(|index| index as u8)
+    }
+
+    fn add_upvalue(&mut self, state: usize, index: u8, is_local: bool) -> Result<u8> {
+        let upvalue = UpvalueSlot { index, is_local };
+        let upvalues = &mut self.states[state].upvalues;
+        if let Some(existing) = upvalues.iter().position(|u| *u == upvalue) {
+            return Ok(existing as u8);
+        }
+        if upvalues.len() > u8::MAX as usize {
+            return Err(Report::new(CompilerError::TooManyUpvalues(
+                self.span.line(),
+            )));
+        }
+        upvalues.push(upvalue);
+        let count = upvalues.len();
+        self.states[state].function.upvalue_count = count;
+        Ok((count - 1) as u8)
+    }
+
+    fn resolve_upvalue(&mut self, state: usize, name: &str) -> Result<Option<u8>> {
+        if state == 0 {
+            return Ok(None);
+        }
+        if let Some(local) = self.resolve_local(state - 1, name) {
+            self.states[state - 1].locals[local as usize].is_captured = true;
+            return Ok(Some(self.add_upvalue(state, local, true)?));
+        }
+        if let Some(upvalue) = self.resolve_upvalue(state - 1, name)? {
+            return Ok(Some(self.add_upvalue(state, upvalue, false)?));
+        }
+        Ok(None)
+    }
+
+    fn named_variable(&mut self, name: &str, set: bool) -> Result<()> {
+        let current = self.states.len() - 1;
+        let (op, operand) = if let Some(local) = self.resolve_local(current, name) {
+            let op = if set {
+                OpCode::SetLocal
+            } else {
+                OpCode::GetLocal
+            };
+            (op, local as u16)
+        } else if let Some(upvalue) = self.resolve_upvalue(current, name)? {
+            let op = if set {
+                OpCode::SetUpvalue
+            } else {
+                OpCode::GetUpvalue
+            };
+            (op, upvalue as u16)
+        } else {
+            let op = if set {
+                OpCode::SetGlobal
+            } else {
+                OpCode::GetGlobal
+            };
+            (op, self.identifier_constant(name)?)
+        };
+        match op {
+            OpCode::GetGlobal | OpCode::SetGlobal => self.emit_op_u16(op, operand),
+            _ => self.emit_op_u8(op, operand as u8),
+        }
+        Ok(())
+    }
+
+    fn declare_variable(&mut self, name: &str) -> Result<()> {
+        if self.state().scope_depth > 0 {
+            self.add_local(name)?;
+        }
+        Ok(())
+    }
+
+    fn define_variable(&mut self, name: &str) -> Result<()> {
+        if self.state().scope_depth == 0 {
+            let global = self.identifier_constant(name)?;
+            self.emit_op_u16(OpCode::DefineGlobal, global);
+        }
+        Ok(())
+    }
+
+    fn function(
+        &mut self,
+        kind: FunctionKind,
+        name: &Token,
+        params: &[Token],
+        body: &[Statement],
+    ) -> Result<()> {
+        self.states
+            .push(FunctionState::new(kind, name.lexeme.clone()));
+        self.begin_scope();
+        self.state().function.arity = params.len();
+        let mut result = Ok(());
+        for param in params {
+            result = result.and_then(|_| self.add_local(&param.lexeme));
+        }
+        for stmt in body {
+            result = result.and_then(|_| self.statement(stmt));
+        }
+        result = result.and_then(|_| self.emit_return());
+        let state = self.states.pop().unwrap();
+        result?;
+        trace!(chunk = %state.function.chunk.disassemble(&name.lexeme), "Compiled function");
+        let constant = self.make_constant(Value::Function(Arc::new(state.function)))?;
+        self.emit_op_u16(OpCode::Closure, constant);
+        for upvalue in state.upvalues {
+            self.emit_byte(upvalue.is_local as u8);
+            self.emit_byte(upvalue.index);
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &Statement) -> Result<()> {
+        match stmt {
+            Statement::Expression {
+                span: _,
+                expression,
+            } => {
+                self.expression(expression)?;
+                if self.is_repl && self.states.len() == 1 {
+                    self.emit_op(OpCode::Print);
+                } else {
+                    self.emit_op(OpCode::Pop);
+                }
+            }
+            Statement::Print {
+                span: _,
+                expression,
+            } => {
+                self.expression(expression)?;
+                self.emit_op(OpCode::Print);
+            }
+            Statement::Var {
+                span: _,
+                name,
+                expression,
+            } => {
+                self.see(name);
+                self.declare_variable(&name.lexeme)?;
+                if let Some(expr) = expression {
+                    self.expression(expr)?;
+                } else {
+                    self.emit_op(OpCode::Nil);
+                }
+                self.define_variable(&name.lexeme)?;
+            }
+            Statement::Block {
+                span: _,
+                statements,
+            } => {
+                self.begin_scope();
+                for stmt in statements {
+                    self.statement(stmt)?;
+                }
+                self.end_scope();
+            }
+            Statement::If {
+                span: _,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition)?;
+                let then_jump = self.emit_jump(OpCode::JumpIfFalse);
+                self.emit_op(OpCode::Pop);
+                self.statement(then_branch)?;
+                let else_jump = self.emit_jump(OpCode::Jump);
+                self.patch_jump(then_jump)?;
+                self.emit_op(OpCode::Pop);
+                if let Some(els) = else_branch {
+                    self.statement(els)?;
+                }
+                self.patch_jump(else_jump)?;
+            }
+            Statement::While {
+                span: _,
+                condition,
+                body,
+                label,
+            } => {
+                let loop_start = self.state().function.chunk.code.len();
+                self.expression(condition)?;
+                let exit_jump = self.emit_jump(OpCode::JumpIfFalse);
+                self.emit_op(OpCode::Pop);
+                let breaks = self.loop_body(label, body)?;
+                self.emit_loop(loop_start)?;
+                self.patch_jump(exit_jump)?;
+                self.emit_op(OpCode::Pop);
+                for jump in breaks {
+                    self.patch_jump(jump)?;
+                }
+            }
+            Statement::For {
+                span: _,
+                initializer,
+                condition,
+                increment,
+                body,
+                label,
+            } => {
+                self.begin_scope();
+                if let Some(init) = initializer {
+                    self.statement(init)?;
+                }
+                let loop_start = self.state().function.chunk.code.len();
+                let mut exit_jump = None;
+                if let Some(cond) = condition {
+                    self.expression(cond)?;
+                    exit_jump = Some(self.emit_jump(OpCode::JumpIfFalse));
+                    self.emit_op(OpCode::Pop);
+                }
+                let breaks = self.loop_body(label, body)?;
+                if let Some(inc) = increment {
+                    self.expression(inc)?;
+                    self.emit_op(OpCode::Pop);
+                }
+                self.emit_loop(loop_start)?;
+                if let Some(exit_jump) = exit_jump {
+                    self.patch_jump(exit_jump)?;
+                    self.emit_op(OpCode::Pop);
+                }
+                for jump in breaks {
+                    self.patch_jump(jump)?;
+                }
+                self.end_scope();
+            }
+            Statement::Break {
+                span: _,
+                keyword,
+                label,
+            }
+            | Statement::Continue {
+                span: _,
+                keyword,
+                label,
+            } => self.jump_statement(keyword, label)?,
+            Statement::Import {
+                span,
+                path,
+                alias,
+                names,
+            } => {
+                self.mark(*span);
+                let path = path.literal.clone().unwrap_or_default();
+                let module = self.make_constant(Value::Str(path.into()))?;
+                for name in names {
+                    self.declare_variable(&name.lexeme)?;
+                    self.emit_op_u16(OpCode::Import, module);
+                    let member = self.identifier_constant(&name.lexeme)?;
+                    self.emit_op_u16(OpCode::GetProperty, member);
+                    self.define_variable(&name.lexeme)?;
+                }
+                if let Some(alias) = alias {
+                    self.declare_variable(&alias.lexeme)?;
+                    self.emit_op_u16(OpCode::Import, module);
+                    self.define_variable(&alias.lexeme)?;
+                }
+            }
+            Statement::Function {
+                span: _,
+                name,
+                params,
+                body,
+            } => {
+                self.see(name);
+                self.declare_variable(&name.lexeme)?;
+                self.function(FunctionKind::Function, name, params, body)?;
+                self.define_variable(&name.lexeme)?;
+            }
+            Statement::Return {
+                span: _,
+                keyword,
+                value,
+            } => {
+                self.see(keyword);
+                match value {
+                    Some(value) => self.expression(value)?,
+                    None => self.emit_implicit_return_value(),
+                }
+                if self.state().handlers.iter().any(Option::is_some) {
+                    // Keep the value in a hidden local while the finally blocks run.
+                    self.begin_scope();
+                    self.add_local("")?;
+                    let value = (self.state().locals.len() - 1) as u8;
+                    self.leave_handlers(0)?;
+                    self.emit_op_u8(OpCode::GetLocal, value);
+                    self.emit_op(OpCode::Return);
+                    self.end_scope();
+                } else {
+                    self.emit_op(OpCode::Return);
+                }
+            }
+            Statement::Throw {
+                span,
+                keyword,
+                value,
+            } => {
+                self.see(keyword);
+                self.expression(value)?;
+                self.mark(*span);
+                self.emit_op(OpCode::Throw);
+            }
+            Statement::Try {
+                span: _,
+                body,
+                catch,
+                finally,
+            } => self.try_statement(body, catch, finally)?,
+            Statement::Match {
+                span: _,
+                keyword,
+                subject,
+                arms,
+            } => {
+                self.see(keyword);
+                self.match_statement(subject, arms)?;
+            }
+            Statement::Class {
+                span: _,
+                name,
+                superclass,
+                methods,
+            } => {
+                self.see(name);
+                let name_constant = self.identifier_constant(&name.lexeme)?;
+                self.declare_variable(&name.lexeme)?;
+                self.emit_op_u16(OpCode::Class, name_constant);
+                self.define_variable(&name.lexeme)?;
+
+                if let Some(superclass) = superclass {
+                    self.expression(superclass)?;
+                    self.begin_scope();
+                    self.add_local("super")?;
+                    self.named_variable(&name.lexeme, false)?;
+                    self.emit_op(OpCode::Inherit);
+                }
+
+                self.named_variable(&name.lexeme, false)?;
+                for method in methods {
+                    if let Statement::Function {
+                        span: _,
+                        name: method_name,
+                        params,
+                        body,
+                    } = method
+                    {
+                        self.see(method_name);
+                        let kind = if method_name.lexeme == "init" {
+                            FunctionKind::Initializer
+                        } else {
+                            FunctionKind::Method
+                        };
+                        let constant = self.identifier_constant(&method_name.lexeme)?;
+                        self.function(kind, method_name, params, body)?;
+                        self.emit_op_u16(OpCode::Method, constant);
+                    }
+                }
+                self.emit_op(OpCode::Pop);
+
+                if superclass.is_some() {
+                    self.end_scope();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self, expr: &Expr) -> Result<()> {
+        match expr {
+            Expr::Literal { span: _, value } => match value {
+                Some(LitType::Int(number)) => {
+                    let constant = self.make_constant(Value::Int(*number))?;
+                    self.emit_op_u16(OpCode::Constant, constant);
+                }
+                Some(LitType::Float(number)) => {
+                    let constant = self.make_constant(Value::Float(*number))?;
+                    self.emit_op_u16(OpCode::Constant, constant);
+                }
+                Some(LitType::Str(string)) => {
+                    let constant = self.make_constant(Value::Str(string.as_str().into()))?;
+                    self.emit_op_u16(OpCode::Constant, constant);
+                }
+                Some(LitType::Bool(true)) => self.emit_op(OpCode::True),
+                Some(LitType::Bool(false)) => self.emit_op(OpCode::False),
+                _ => self.emit_op(OpCode::Nil),
+            },
+            Expr::Grouping {
+                span: _,
+                expression,
+            } => self.expression(expression)?,
+            Expr::Unary {
+                span,
+                operator,
+                right,
+            } => {
+                self.expression(right)?;
+                self.mark(*span);
+                match operator.ty {
+                    TokenType::Minus => self.emit_op(OpCode::Negate),
+                    TokenType::Tilde => self.emit_op(OpCode::BitNot),
+                    _ => self.emit_op(OpCode::Not),
+                }
+            }
+            Expr::Binary {
+                span,
+                left,
+                operator,
+                right,
+            } => {
+                self.expression(left)?;
+                self.expression(right)?;
+                self.mark(*span);
+                match operator.ty {
+                    TokenType::Plus => self.emit_op(OpCode::Add),
+                    TokenType::Minus => self.emit_op(OpCode::Subtract),
+                    TokenType::Star => self.emit_op(OpCode::Multiply),
+                    TokenType::Slash => self.emit_op(OpCode::Divide),
+                    TokenType::Percent => self.emit_op(OpCode::Modulo),
+                    TokenType::TildeSlash => self.emit_op(OpCode::FloorDivide),
+                    TokenType::StarStar => self.emit_op(OpCode::Power),
+                    TokenType::Ampersand => self.emit_op(OpCode::BitAnd),
+                    TokenType::Pipe => self.emit_op(OpCode::BitOr),
+                    TokenType::Caret => self.emit_op(OpCode::BitXor),
+                    TokenType::LessLess => self.emit_op(OpCode::ShiftLeft),
+                    TokenType::GreaterGreater => self.emit_op(OpCode::ShiftRight),
+                    TokenType::Greater => self.emit_op(OpCode::Greater),
+                    TokenType::GreaterEqual => {
+                        self.emit_op(OpCode::Less);
+                        self.emit_op(OpCode::Not);
+                    }
+                    TokenType::Less => self.emit_op(OpCode::Less),
+                    TokenType::LessEqual => {
+                        self.emit_op(OpCode::Greater);
+                        self.emit_op(OpCode::Not);
+                    }
+                    TokenType::EqualEqual => self.emit_op(OpCode::Equal),
+                    _ => {
+                        self.emit_op(OpCode::Equal);
+                        self.emit_op(OpCode::Not);
+                    }
+                }
+            }
+            Expr::Logcial {
+                span: _,
+                left,
+                operator,
+                right,
+            } => {
+                self.expression(left)?;
+                self.see(operator);
+                if matches!(operator.ty, TokenType::OR) {
+                    let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+                    let end_jump = self.emit_jump(OpCode::Jump);
+                    self.patch_jump(else_jump)?;
+                    self.emit_op(OpCode::Pop);
+                    self.expression(right)?;
+                    self.patch_jump(end_jump)?;
+                } else {
+                    let end_jump = self.emit_jump(OpCode::JumpIfFalse);
+                    self.emit_op(OpCode::Pop);
+                    self.expression(right)?;
+                    self.patch_jump(end_jump)?;
+                }
+            }
+            Expr::Variable {
+                span: _,
+                name,
+                depth: _,
+            } => {
+                self.see(name);
+                self.named_variable(&name.lexeme, false)?;
+            }
+            Expr::Assign {
+                span: _,
+                name,
+                value,
+                depth: _,
+            } => {
+                self.expression(value)?;
+                self.see(name);
+                self.named_variable(&name.lexeme, true)?;
+            }
+            Expr::Increment {
+                span: _,
+                name,
+                operator,
+                prefix,
+                depth: _,
+            } => {
+                self.see(name);
+                self.named_variable(&name.lexeme, false)?;
+                if !prefix {
+                    self.named_variable(&name.lexeme, false)?;
+                }
+                let one = self.make_constant(Value::Int(1))?;
+                self.emit_op_u16(OpCode::Constant, one);
+                self.see(operator);
+                if operator.ty == TokenType::PlusPlus {
+                    self.emit_op(OpCode::Add);
+                } else {
+                    self.emit_op(OpCode::Subtract);
+                }
+                self.named_variable(&name.lexeme, true)?;
+                if !prefix {
+                    self.emit_op(OpCode::Pop);
+                }
+            }
+            Expr::Conditional {
+                span: _,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition)?;
+                let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+                self.emit_op(OpCode::Pop);
+                self.expression(then_branch)?;
+                let end_jump = self.emit_jump(OpCode::Jump);
+                self.patch_jump(else_jump)?;
+                self.emit_op(OpCode::Pop);
+                self.expression(else_branch)?;
+                self.patch_jump(end_jump)?;
+            }
+            Expr::Call {
+                span,
+                callee,
+                paren: _,
+                arguments,
+            } => {
+                self.expression(callee)?;
+                for arg in arguments {
+                    self.expression(arg)?;
+                }
+                self.mark(*span);
+                self.emit_op_u8(OpCode::Call, arguments.len() as u8);
+            }
+            Expr::Get { span, object, name } => {
+                self.expression(object)?;
+                self.mark(*span);
+                let constant = self.identifier_constant(&name.lexeme)?;
+                self.emit_op_u16(OpCode::GetProperty, constant);
+            }
+            Expr::Set {
+                span,
+                object,
+                name,
+                value,
+            } => {
+                self.expression(object)?;
+                self.expression(value)?;
+                self.mark(*span);
+                let constant = self.identifier_constant(&name.lexeme)?;
+                self.emit_op_u16(OpCode::SetProperty, constant);
+            }
+            Expr::List { span: _, elements } => {
+                for element in elements {
+                    self.expression(element)?;
+                }
+                if elements.len() > u16::MAX as usize {
+                    return Err(Report::new(CompilerError::TooManyElements(
+                        self.span.line(),
+                    )));
+                }
+                self.emit_op_u16(OpCode::BuildList, elements.len() as u16);
+            }
+            Expr::Stringify {
+                span: _,
+                expression,
+            } => {
+                self.expression(expression)?;
+                self.emit_op(OpCode::Stringify);
+            }
+            Expr::Map { span, entries } => {
+                for (key, value) in entries {
+                    self.expression(key)?;
+                    self.expression(value)?;
+                }
+                self.mark(*span);
+                if entries.len() > u16::MAX as usize {
+                    return Err(Report::new(CompilerError::TooManyElements(
+                        self.span.line(),
+                    )));
+                }
+                self.emit_op_u16(OpCode::BuildMap, entries.len() as u16);
+            }
+            Expr::Index {
+                span,
+                object,
+                bracket: _,
+                index,
+            } => {
+                self.expression(object)?;
+                self.expression(index)?;
+                self.mark(*span);
+                self.emit_op(OpCode::GetIndex);
+            }
+            Expr::SetIndex {
+                span,
+                object,
+                bracket: _,
+                index,
+                value,
+            } => {
+                self.expression(object)?;
+                self.expression(index)?;
+                self.expression(value)?;
+                self.mark(*span);
+                self.emit_op(OpCode::SetIndex);
+            }
+            Expr::This {
+                span: _,
+                keyword,
+                depth: _,
+            } => {
+                self.see(keyword);
+                self.named_variable("this", false)?;
+            }
+            Expr::Super {
+                span,
+                keyword,
+                method,
+                depth: _,
+            } => {
+                self.see(keyword);
+                let constant = self.identifier_constant(&method.lexeme)?;
+                self.named_variable("this", false)?;
+                self.named_variable("super", false)?;
+                self.mark(*span);
+                self.emit_op_u16(OpCode::GetSuper, constant);
+            }
+            Expr::Lambda {
+                span: _,
+                declaration,
+            } => {
+                if let Statement::Function {
+                    span: _,
+                    name,
+                    params,
+                    body,
+                } = &**declaration
+                {
+                    self.function(FunctionKind::Function, name, params, body)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
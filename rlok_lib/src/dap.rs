@@ -0,0 +1,441 @@
+use super::debugger::{Debugger, Frame, Frontend, Location, Reason, Resume};
+use super::framing;
+use super::interpreter::{Interpreter, Output};
+use super::lit::LitType;
+use super::stdlib::type_name;
+use color_eyre::eyre::Result;
+use serde_json::{json, Value as Json};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::path::{self, Path};
+use std::process;
+use std::rc::Rc;
+use tracing::trace;
+
+/// Lox programs run on one thread, so every request names this one.
+const THREAD: i64 = 1;
+
+/// The Debug Adapter Protocol connection on stdin and stdout.
+#[derive(Debug, Default)]
+struct Connection {
+    seq: i64,
+}
+
+impl Connection {
+    fn send(&mut self, mut message: Json) -> Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        framing::write(&message)
+    }
+
+    fn respond(&mut self, request: &Json, body: Json) -> Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }))
+    }
+
+    fn fail(&mut self, request: &Json, message: &str) -> Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }))
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+}
+
+/// Sends the program's output to the client as output events, since stdout
+/// carries the protocol.
+#[derive(Debug)]
+struct Events(Rc<RefCell<Connection>>);
+
+impl Events {
+    fn send(&self, category: &str, text: &str) {
+        let body = json!({ "category": category, "output": text });
+        if let Err(e) = self.0.borrow_mut().event("output", body) {
+            trace!(error = %e, "Unable to send output");
+        }
+    }
+}
+
+impl Output for Events {
+    fn print(&self, text: &str) {
+        self.send("stdout", text);
+    }
+
+    fn error(&self, text: &str) {
+        self.send("stderr", text);
+    }
+}
+
+/// What a `variablesReference` handed to the client stands for.
+#[derive(Debug)]
+enum Handle {
+    Variables(Vec<(String, LitType)>),
+    /// A list, map or instance whose contents are shown when it's expanded.
+    Value(LitType),
+}
+
+#[derive(Debug)]
+struct Adapter {
+    connection: Rc<RefCell<Connection>>,
+    /// Handed out since the last pause; a reference is its index plus one.
+    handles: Vec<Handle>,
+}
+
+impl Adapter {
+    fn handle(&mut self, handle: Handle) -> usize {
+        self.handles.push(handle);
+        self.handles.len()
+    }
+
+    fn variable(&mut self, name: String, value: LitType) -> Json {
+        let reference = match value {
+            LitType::List(_) | LitType::Map(_) | LitType::Instance(_) => {
+                self.handle(Handle::Value(value.clone()))
+            }
+            _ => 0,
+        };
+        json!({
+            "name": name,
+            "value": value.quoted(),
+            "type": type_name(&value),
+            "variablesReference": reference,
+        })
+    }
+
+    fn children(value: &LitType) -> Vec<(String, LitType)> {
+        match value {
+            LitType::List(list) => list
+                .items()
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| (index.to_string(), item))
+                .collect(),
+            LitType::Map(map) => map
+                .keys()
+                .into_iter()
+                .map(|key| key.quoted())
+                .zip(map.values())
+                .collect(),
+            LitType::Instance(instance) => instance.fields(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The frame with the id given in a request, where 0 is the innermost call.
+    fn frame<'a>(frames: &'a [Frame], request: &Json) -> Option<&'a Frame> {
+        let id = request["arguments"]["frameId"].as_u64().unwrap_or(0);
+        frames.iter().rev().nth(usize::try_from(id).ok()?)
+    }
+
+    fn stack_trace(&mut self, request: &Json, frames: &[Frame]) -> Result<()> {
+        let stack: Vec<Json> = frames
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(id, frame)| {
+                json!({
+                    "id": id,
+                    "name": frame.name,
+                    "source": source(&frame.location.file),
+                    "line": frame.location.line,
+                    "column": 1,
+                })
+            })
+            .collect();
+        let body = json!({ "totalFrames": stack.len(), "stackFrames": stack });
+        self.connection.borrow_mut().respond(request, body)
+    }
+
+    /// A frame's locals, innermost scope winning, and the globals it sees.
+    fn scopes(&mut self, request: &Json, frames: &[Frame]) -> Result<()> {
+        let Some(environment) = Self::frame(frames, request).and_then(|f| f.environment.clone())
+        else {
+            return self.connection.borrow_mut().fail(request, "Unknown frame.");
+        };
+        let mut chain = Vec::new();
+        let mut scope = Some(&environment);
+        while let Some(current) = scope {
+            chain.push(current.values());
+            scope = current.enclosing();
+        }
+        // Outermost are the builtins and then the globals.
+        chain.pop();
+        let globals = chain.pop().unwrap_or_default();
+        let mut locals: Vec<(String, LitType)> = Vec::new();
+        for (name, value) in chain.into_iter().flatten() {
+            if !locals.iter().any(|(local, _)| *local == name) {
+                locals.push((name, value));
+            }
+        }
+        locals.sort_by(|a, b| a.0.cmp(&b.0));
+        let locals = self.handle(Handle::Variables(locals));
+        let globals = self.handle(Handle::Variables(globals));
+        let body = json!({ "scopes": [
+            { "name": "Locals", "variablesReference": locals, "expensive": false },
+            { "name": "Globals", "variablesReference": globals, "expensive": false },
+        ]});
+        self.connection.borrow_mut().respond(request, body)
+    }
+
+    fn variables(&mut self, request: &Json) -> Result<()> {
+        let reference = request["arguments"]["variablesReference"]
+            .as_u64()
+            .unwrap_or(0);
+        let handle = usize::try_from(reference)
+            .ok()
+            .and_then(|reference| reference.checked_sub(1))
+            .and_then(|index| self.handles.get(index));
+        let children = match handle {
+            Some(Handle::Variables(variables)) => variables.clone(),
+            Some(Handle::Value(value)) => Self::children(value),
+            None => {
+                return self
+                    .connection
+                    .borrow_mut()
+                    .fail(request, "Unknown variables reference.")
+            }
+        };
+        let variables: Vec<Json> = children
+            .into_iter()
+            .map(|(name, value)| self.variable(name, value))
+            .collect();
+        self.connection
+            .borrow_mut()
+            .respond(request, json!({ "variables": variables }))
+    }
+
+    fn evaluate(
+        &mut self,
+        request: &Json,
+        frames: &[Frame],
+        inter: &mut Interpreter,
+    ) -> Result<()> {
+        let Some(environment) = Self::frame(frames, request).and_then(|f| f.environment.clone())
+        else {
+            return self.connection.borrow_mut().fail(request, "Unknown frame.");
+        };
+        let expression = request["arguments"]["expression"]
+            .as_str()
+            .unwrap_or_default();
+        match inter.evaluate_paused(expression, &environment) {
+            Ok(Some(value)) => {
+                let variable = self.variable(String::new(), value);
+                let body = json!({
+                    "result": variable["value"],
+                    "type": variable["type"],
+                    "variablesReference": variable["variablesReference"],
+                });
+                self.connection.borrow_mut().respond(request, body)
+            }
+            Ok(None) => {
+                let body = json!({ "result": "", "variablesReference": 0 });
+                self.connection.borrow_mut().respond(request, body)
+            }
+            Err(e) => self.connection.borrow_mut().fail(request, &e.to_string()),
+        }
+    }
+}
+
+impl Frontend for Adapter {
+    fn paused(
+        &mut self,
+        reason: Reason,
+        frames: &[Frame],
+        breakpoints: &mut BTreeSet<Location>,
+        inter: &mut Interpreter,
+    ) -> Result<Resume> {
+        self.handles.clear();
+        let reason = match reason {
+            Reason::Entry => "entry",
+            Reason::Breakpoint => "breakpoint",
+            Reason::Step => "step",
+        };
+        self.connection.borrow_mut().event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD, "allThreadsStopped": true }),
+        )?;
+        loop {
+            let Some(request) = framing::read()? else {
+                return Ok(Resume::Continue);
+            };
+            let resume = match request["command"].as_str().unwrap_or_default() {
+                "continue" => Resume::Continue,
+                "next" => Resume::StepOver,
+                "stepIn" => Resume::StepInto,
+                "stepOut" => Resume::StepOut,
+                "stackTrace" => {
+                    self.stack_trace(&request, frames)?;
+                    continue;
+                }
+                "scopes" => {
+                    self.scopes(&request, frames)?;
+                    continue;
+                }
+                "variables" => {
+                    self.variables(&request)?;
+                    continue;
+                }
+                "evaluate" => {
+                    self.evaluate(&request, frames, inter)?;
+                    continue;
+                }
+                _ => {
+                    if common(&self.connection, &request, breakpoints)? {
+                        process::exit(0);
+                    }
+                    continue;
+                }
+            };
+            let body = match resume {
+                Resume::Continue => json!({ "allThreadsContinued": true }),
+                _ => json!({}),
+            };
+            self.connection.borrow_mut().respond(&request, body)?;
+            return Ok(resume);
+        }
+    }
+}
+
+/// How the client sees a file the interpreter knows by `file`.
+fn source(file: &str) -> Json {
+    let path = path::absolute(file).unwrap_or_else(|_| file.into());
+    let name = path
+        .file_name()
+        .map_or(file.into(), |name| name.to_string_lossy());
+    json!({ "name": name, "path": path.to_string_lossy() })
+}
+
+/// Answers the requests that mean the same whether or not the program is
+/// paused. Returns whether the client asked to disconnect.
+fn common(
+    connection: &RefCell<Connection>,
+    request: &Json,
+    breakpoints: &mut BTreeSet<Location>,
+) -> Result<bool> {
+    let mut connection = connection.borrow_mut();
+    match request["command"].as_str().unwrap_or_default() {
+        "threads" => {
+            let threads = json!({ "threads": [{ "id": THREAD, "name": "main" }] });
+            connection.respond(request, threads)?;
+        }
+        "setBreakpoints" => {
+            let arguments = &request["arguments"];
+            let Some(path) = arguments["source"]["path"].as_str() else {
+                connection.fail(request, "setBreakpoints needs a source path.")?;
+                return Ok(false);
+            };
+            // Breakpoints are kept the way the interpreter names files.
+            let file = Interpreter::display_path(Path::new(path));
+            breakpoints.retain(|at| at.file != file);
+            let lines: Vec<i64> = arguments["breakpoints"]
+                .as_array()
+                .map(|requested| {
+                    requested
+                        .iter()
+                        .filter_map(|at| at["line"].as_i64())
+                        .collect()
+                })
+                .unwrap_or_default();
+            let mut verified = Vec::new();
+            for line in lines {
+                breakpoints.insert(Location::new(&file, line as i32));
+                verified.push(json!({ "verified": true, "line": line }));
+            }
+            connection.respond(request, json!({ "breakpoints": verified }))?;
+        }
+        "disconnect" => {
+            connection.respond(request, json!({}))?;
+            return Ok(true);
+        }
+        command => {
+            connection.fail(request, &format!("Unsupported request: {}", command))?;
+        }
+    }
+    Ok(false)
+}
+
+/// Runs the adapter for `rlok dap` until the client disconnects. The program
+/// named by `launch` starts once the client sends `configurationDone`.
+pub fn serve(inter: &mut Interpreter) -> Result<()> {
+    let connection = Rc::new(RefCell::new(Connection::default()));
+    let mut breakpoints = BTreeSet::new();
+    let mut launch = None;
+    loop {
+        let Some(request) = framing::read()? else {
+            return Ok(());
+        };
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => {
+                let capabilities = json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsEvaluateForHovers": true,
+                });
+                let mut connection = connection.borrow_mut();
+                connection.respond(&request, capabilities)?;
+                connection.event("initialized", json!({}))?;
+            }
+            "launch" => {
+                let arguments = &request["arguments"];
+                let Some(program) = arguments["program"].as_str() else {
+                    connection
+                        .borrow_mut()
+                        .fail(&request, "launch needs a program.")?;
+                    continue;
+                };
+                let stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+                launch = Some((program.to_string(), stop_on_entry));
+                connection.borrow_mut().respond(&request, json!({}))?;
+            }
+            "configurationDone" => {
+                connection.borrow_mut().respond(&request, json!({}))?;
+                if let Some((program, stop_on_entry)) = launch.take() {
+                    let breakpoints = std::mem::take(&mut breakpoints);
+                    run(inter, &connection, &program, stop_on_entry, breakpoints)?;
+                }
+            }
+            _ => {
+                if common(&connection, &request, &mut breakpoints)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn run(
+    inter: &mut Interpreter,
+    connection: &Rc<RefCell<Connection>>,
+    program: &str,
+    stop_on_entry: bool,
+    breakpoints: BTreeSet<Location>,
+) -> Result<()> {
+    inter.set_output(Box::new(Events(connection.clone())));
+    let adapter = Adapter {
+        connection: connection.clone(),
+        handles: Vec::new(),
+    };
+    let debugger = Debugger::new(Box::new(adapter), stop_on_entry).set_breakpoints(breakpoints);
+    let file = Interpreter::display_path(Path::new(program));
+    let exit_code = match inter.debug_file(&file, debugger) {
+        Ok(()) if inter.had_runtime_error() => 70,
+        Ok(()) => 0,
+        Err(e) => {
+            inter.report(&e);
+            1
+        }
+    };
+    let mut connection = connection.borrow_mut();
+    connection.event("exited", json!({ "exitCode": exit_code }))?;
+    connection.event("terminated", json!({}))
+}
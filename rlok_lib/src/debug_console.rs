@@ -0,0 +1,160 @@
+use super::debugger::{Frame, Frontend, Location, Reason, Resume};
+use super::interpreter::Interpreter;
+use super::repl::Repl;
+use color_eyre::eyre::Result;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::collections::BTreeSet;
+use std::process;
+
+const HELP: &str = "\
+break [file:]line  set a breakpoint, or list them with no argument (b)
+delete [file:]line remove a breakpoint (d)
+step               run to the next statement, going into calls (s)
+next               run to the next statement in this function (n)
+out                run until this function returns (o)
+continue           run to the next breakpoint (c)
+print <expr>       evaluate an expression where the program is paused (p)
+vars               list the variables in scope
+backtrace          show the call stack (bt)
+list               show the source around the current line (l)
+quit               stop the program (q)
+An empty line repeats the last command.";
+
+/// The `rlok debug` prompt, which reads commands from the terminal whenever
+/// the program pauses.
+pub struct Console {
+    editor: DefaultEditor,
+    last: String,
+}
+
+impl std::fmt::Debug for Console {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Console").field("last", &self.last).finish()
+    }
+}
+
+impl Console {
+    pub fn build() -> Result<Self> {
+        Ok(Console {
+            editor: DefaultEditor::new()?,
+            last: String::new(),
+        })
+    }
+
+    fn show(inter: &Interpreter, location: &Location) {
+        match inter.source_line(location) {
+            Some(text) => println!("{:>4} | {}", location.line, text),
+            None => println!("{}", location),
+        }
+    }
+
+    fn list(inter: &Interpreter, location: &Location) {
+        for line in (location.line - 3).max(1)..=location.line + 3 {
+            let at = Location::new(&location.file, line);
+            let Some(text) = inter.source_line(&at) else {
+                break;
+            };
+            let marker = if line == location.line { ">" } else { " " };
+            println!("{}{:>4} | {}", marker, line, text);
+        }
+    }
+
+    fn breakpoint(argument: &str, current: &Location) -> Option<Location> {
+        let (file, line) = match argument.rsplit_once(':') {
+            Some((file, line)) => (file, line),
+            None => (current.file.as_str(), argument),
+        };
+        let line = line.trim().parse().ok().filter(|line| *line > 0)?;
+        Some(Location::new(file.trim(), line))
+    }
+}
+
+impl Frontend for Console {
+    fn paused(
+        &mut self,
+        reason: Reason,
+        frames: &[Frame],
+        breakpoints: &mut BTreeSet<Location>,
+        inter: &mut Interpreter,
+    ) -> Result<Resume> {
+        let Some(frame) = frames.last() else {
+            return Ok(Resume::Continue);
+        };
+        let current = frame.location.clone();
+        match reason {
+            Reason::Entry => println!("Stopped at the start of {}", current.file),
+            Reason::Breakpoint => println!("Breakpoint at {}", current),
+            Reason::Step => (),
+        }
+        Self::show(inter, &current);
+        loop {
+            let line = match self.editor.readline("(debug) ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                // Without anything left to read, let the program run to the end.
+                Err(ReadlineError::Eof) => return Ok(Resume::Continue),
+                Err(e) => return Err(e.into()),
+            };
+            let line = if line.trim().is_empty() {
+                self.last.clone()
+            } else {
+                let _ = self.editor.add_history_entry(line.as_str());
+                self.last = line.trim().to_string();
+                self.last.clone()
+            };
+            let (command, argument) = line
+                .split_once(char::is_whitespace)
+                .map_or((line.as_str(), ""), |(command, argument)| {
+                    (command, argument.trim())
+                });
+            match command {
+                "" => (),
+                "s" | "step" => return Ok(Resume::StepInto),
+                "n" | "next" => return Ok(Resume::StepOver),
+                "o" | "out" | "finish" => return Ok(Resume::StepOut),
+                "c" | "continue" => return Ok(Resume::Continue),
+                "b" | "break" if argument.is_empty() => {
+                    for breakpoint in breakpoints.iter() {
+                        println!("{}", breakpoint);
+                    }
+                }
+                "b" | "break" => match Self::breakpoint(argument, &current) {
+                    Some(breakpoint) => {
+                        println!("Breakpoint set at {}", breakpoint);
+                        breakpoints.insert(breakpoint);
+                    }
+                    None => {
+                        println!("Expected a line number, as in `break 12` or `break main.lox:12`.")
+                    }
+                },
+                "d" | "delete" => match Self::breakpoint(argument, &current) {
+                    Some(breakpoint) if breakpoints.remove(&breakpoint) => {
+                        println!("Deleted the breakpoint at {}", breakpoint)
+                    }
+                    _ => println!("No breakpoint at {}", argument),
+                },
+                "p" | "print" => {
+                    let Some(environment) = &frame.environment else {
+                        continue;
+                    };
+                    match inter.evaluate_paused(argument, environment) {
+                        Ok(Some(value)) => println!("{}", value.quoted()),
+                        Ok(None) => (),
+                        Err(e) => inter.report(&e),
+                    }
+                }
+                "vars" => Repl::print_scopes(inter),
+                "bt" | "backtrace" | "where" => {
+                    for (depth, frame) in frames.iter().rev().enumerate() {
+                        println!("#{} {} at {}", depth, frame.name, frame.location);
+                    }
+                }
+                "l" | "list" => Self::list(inter, &current),
+                "q" | "quit" => process::exit(0),
+                "h" | "help" => println!("{}", HELP),
+                _ => println!("Unknown command `{}`. Type `help` for the list.", command),
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use super::environment::Environment;
+use super::interpreter::Interpreter;
+use color_eyre::eyre::Result;
+use std::collections::BTreeSet;
+use std::path::{Component, Path};
+use tracing::trace;
+
+/// A line in a source file, as the debugger reports positions and sets breakpoints.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub file: String,
+    pub line: i32,
+}
+
+impl Location {
+    pub fn new(file: &str, line: i32) -> Self {
+        Location {
+            file: file.into(),
+            line,
+        }
+    }
+
+    /// Whether this position is the breakpoint `at`, which may name its file by
+    /// any trailing part of the path, such as `main.lox` for `./src/main.lox`.
+    pub fn hits(&self, at: &Location) -> bool {
+        fn parts(file: &str) -> Vec<Component<'_>> {
+            Path::new(file)
+                .components()
+                .filter(|part| !matches!(part, Component::CurDir))
+                .collect()
+        }
+        let (file, breakpoint) = (parts(&self.file), parts(&at.file));
+        self.line == at.line && !breakpoint.is_empty() && file.ends_with(&breakpoint)
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+/// A function call in progress, and the statement it has reached.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub name: String,
+    pub location: Location,
+    /// The scope that statement runs in, once the call has reached one.
+    pub environment: Option<Environment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reason {
+    Entry,
+    Breakpoint,
+    Step,
+}
+
+/// How to carry on after a pause.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resume {
+    Continue,
+    /// Stop at the very next statement, even inside a call.
+    StepInto,
+    /// Stop at the next statement in this function or the one it returns to.
+    StepOver,
+    /// Stop at the next statement after this function returns.
+    StepOut,
+}
+
+/// What the user sees and controls while the program is paused.
+pub trait Frontend: std::fmt::Debug {
+    /// Called before a statement runs when the program pauses there. `frames`
+    /// holds the call stack with the innermost call last.
+    fn paused(
+        &mut self,
+        reason: Reason,
+        frames: &[Frame],
+        breakpoints: &mut BTreeSet<Location>,
+        inter: &mut Interpreter,
+    ) -> Result<Resume>;
+}
+
+#[derive(Debug)]
+pub struct Debugger {
+    frontend: Box<dyn Frontend>,
+    breakpoints: BTreeSet<Location>,
+    frames: Vec<Frame>,
+    resume: Resume,
+    /// How deep the call stack was when stepping began.
+    step_depth: usize,
+    started: bool,
+    /// The statement that ran last, by source offset and call depth, so that
+    /// a statement hits its breakpoint once per pass rather than once per line.
+    last: Option<(usize, usize)>,
+}
+
+impl Debugger {
+    pub fn new(frontend: Box<dyn Frontend>, stop_on_entry: bool) -> Self {
+        Debugger {
+            frontend,
+            breakpoints: BTreeSet::new(),
+            frames: vec![Frame {
+                name: "<script>".into(),
+                location: Location::new("", 0),
+                environment: None,
+            }],
+            resume: Resume::Continue,
+            step_depth: 0,
+            started: !stop_on_entry,
+            last: None,
+        }
+    }
+
+    pub fn set_breakpoints(mut self, breakpoints: BTreeSet<Location>) -> Self {
+        self.breakpoints = breakpoints;
+        self
+    }
+
+    pub fn enter_call(&mut self, name: &str) {
+        let location = self
+            .frames
+            .last()
+            .map_or(Location::new("", 0), |frame| frame.location.clone());
+        self.frames.push(Frame {
+            name: name.into(),
+            location,
+            environment: None,
+        });
+        self.last = None;
+    }
+
+    pub fn leave_call(&mut self) {
+        self.frames.pop();
+        self.last = None;
+    }
+
+    /// Called as a loop starts another iteration, so its statements can hit
+    /// their breakpoints again.
+    pub fn next_pass(&mut self) {
+        self.last = None;
+    }
+
+    /// Called before each statement runs, pausing there if a breakpoint or
+    /// the step in progress says to.
+    pub fn statement(
+        &mut self,
+        location: Location,
+        offset: usize,
+        environment: Environment,
+        inter: &mut Interpreter,
+    ) -> Result<()> {
+        let depth = self.frames.len();
+        if let Some(frame) = self.frames.last_mut() {
+            frame.location = location.clone();
+            frame.environment = Some(environment);
+        }
+        let new_pass = self.last != Some((offset, depth));
+        self.last = Some((offset, depth));
+        let stepping = match self.resume {
+            Resume::Continue => false,
+            Resume::StepInto => true,
+            Resume::StepOver => depth <= self.step_depth,
+            Resume::StepOut => depth < self.step_depth,
+        };
+        let reason = if !self.started {
+            Reason::Entry
+        } else if new_pass && self.breakpoints.iter().any(|at| location.hits(at)) {
+            Reason::Breakpoint
+        } else if stepping {
+            Reason::Step
+        } else {
+            return Ok(());
+        };
+        trace!(location = %location, reason = ?reason, "Paused");
+        self.started = true;
+        self.resume = self
+            .frontend
+            .paused(reason, &self.frames, &mut self.breakpoints, inter)?;
+        self.step_depth = depth;
+        Ok(())
+    }
+}
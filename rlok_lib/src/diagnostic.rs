@@ -0,0 +1,87 @@
+use super::error_handler::{ParserError, ResolverError, RuntimeError, ScannerError};
+use super::source_map::{SourceFile, SourceMap};
+use super::span::Span;
+use color_eyre::eyre::Report;
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const RESET: &str = "\x1b[0m";
+
+pub struct Diagnostic<'a> {
+    sources: &'a SourceMap,
+    color: bool,
+}
+
+impl<'a> Diagnostic<'a> {
+    pub fn new(sources: &'a SourceMap, color: bool) -> Self {
+        Diagnostic { sources, color }
+    }
+
+    pub fn span_of(error: &Report) -> Option<Span> {
+        if let Some(e) = error.downcast_ref::<ScannerError>() {
+            e.span()
+        } else if let Some(e) = error.downcast_ref::<ParserError>() {
+            e.span()
+        } else if let Some(e) = error.downcast_ref::<ResolverError>() {
+            e.span()
+        } else if let Some(e) = error.downcast_ref::<RuntimeError>() {
+            e.span()
+        } else {
+            None
+        }
+    }
+
+    pub fn render(&self, error: &Report) -> String {
+        let message = self.paint(RED, &error.to_string());
+        let located =
+            Self::span_of(error).and_then(|span| Some((span, self.sources.find(span.start())?)));
+        match located {
+            Some((span, file)) => format!("{}\n{}", message, self.snippet(span, file)),
+            None => message,
+        }
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.into()
+        }
+    }
+
+    fn snippet(&self, span: Span, file: &SourceFile) -> String {
+        let source = file.text();
+        let start = (span.start() - file.origin()).min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let text = source[line_start..line_end].trim_end_matches('\r');
+        let end = (span.end().saturating_sub(file.origin()))
+            .min(line_start + text.len())
+            .max(start);
+
+        let padding: String = source[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let carets = "^".repeat(source[start..end].chars().count().max(1));
+        let underline = format!("{}{}", padding, self.paint(RED, &carets));
+
+        let line = span.line().to_string();
+        let gutter = " ".repeat(line.len());
+        format!(
+            "{}{} {}:{}:{}\n{}\n{} {}\n{} {}",
+            gutter,
+            self.paint(BLUE, "-->"),
+            file.name(),
+            span.line(),
+            span.column(),
+            self.paint(BLUE, &format!("{} |", gutter)),
+            self.paint(BLUE, &format!("{} |", line)),
+            text,
+            self.paint(BLUE, &format!("{} |", gutter)),
+            underline,
+        )
+    }
+}
@@ -1,39 +1,39 @@
 use super::error_handler::RuntimeError;
 use super::lit::LitType;
+use super::span::Span;
 use super::tokens::Token;
 use color_eyre::eyre::{Report, Result};
 use std::collections::HashMap;
-use tracing::{span, trace, Level};
+use std::sync::{Arc, Mutex};
+use tracing::trace;
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct Environment {
-    values: HashMap<String, LitType>,
-    enclosing: Option<Box<Environment>>,
+    values: Arc<Mutex<HashMap<String, LitType>>>,
+    enclosing: Option<Arc<Environment>>,
 }
 
 impl Environment {
-    pub fn new(enclosing: Option<Box<Environment>>) -> Self {
+    pub fn new(enclosing: Option<Environment>) -> Self {
         trace!("Creating new environment");
         Environment {
-            values: HashMap::new(),
-            enclosing,
+            values: Arc::new(Mutex::new(HashMap::new())),
+            enclosing: enclosing.map(Arc::new),
         }
     }
 
-    pub fn define(&mut self, name: String, value: LitType) {
-        self.values.insert(name, value);
+    pub fn define(&self, name: String, value: LitType) {
+        self.values.lock().unwrap().insert(name, value);
         trace!(env = %self, "Environment Define");
     }
 
-    pub fn get(&self, token: Token, span: String) -> Result<Option<LitType>> {
-        if self.values.contains_key(&token.lexeme) {
-            if let Some(val) = self.values.get(&token.lexeme) {
-                trace!(get = %token, "Environment Get");
-                return Ok(Some(val.clone()));
-            }
+    pub fn get(&self, token: Token, span: Span) -> Result<Option<LitType>> {
+        if let Some(val) = self.values.lock().unwrap().get(&token.lexeme) {
+            trace!(get = %token, "Environment Get");
+            return Ok(Some(val.clone()));
         }
         if let Some(ref enc) = self.enclosing {
-            return Ok(enc.get(token.clone(), span)?);
+            return enc.get(token, span);
         }
         Err(Report::new(RuntimeError::UndefinedVariable(
             token.lexeme,
@@ -41,49 +41,150 @@ impl Environment {
         )))
     }
 
-    pub fn assign(&mut self, name: Token, value: LitType, span: String) -> Result<()> {
-        if self.values.contains_key(&name.lexeme) {
-            self.values.insert(name.lexeme.clone(), value.clone());
-            trace!(name = %name.lexeme, value = %value, "Environment Assign");
+    fn ancestor(&self, distance: usize) -> Environment {
+        let mut environment = self.clone();
+        for _ in 0..distance {
+            if let Some(ref enc) = environment.enclosing {
+                environment = (**enc).clone();
+            }
+        }
+        environment
+    }
+
+    pub fn get_at(&self, distance: usize, token: Token, span: Span) -> Result<Option<LitType>> {
+        if let Some(val) = self.ancestor(distance).lookup_local(&token.lexeme) {
+            trace!(get = %token, distance, "Environment Get At");
+            return Ok(Some(val));
+        }
+        Err(Report::new(RuntimeError::UndefinedVariable(
+            token.lexeme,
+            span,
+        )))
+    }
+
+    pub fn assign_at(
+        &self,
+        distance: usize,
+        name: Token,
+        value: LitType,
+        span: Span,
+    ) -> Result<()> {
+        let environment = self.ancestor(distance);
+        if let Some(slot) = environment.values.lock().unwrap().get_mut(&name.lexeme) {
+            trace!(name = %name.lexeme, value = %value, distance, "Environment Assign At");
+            *slot = value;
             return Ok(());
         }
-        if let Some(ref mut enc) = self.enclosing {
-            enc.assign(name.clone(), value.clone(), span)?;
+        Err(Report::new(RuntimeError::UndefinedVariable(
+            name.lexeme,
+            span,
+        )))
+    }
+
+    pub fn ptr_eq(&self, other: &Environment) -> bool {
+        Arc::ptr_eq(&self.values, &other.values)
+    }
+
+    pub fn lookup_local(&self, name: &str) -> Option<LitType> {
+        self.values.lock().unwrap().get(name).cloned()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<LitType> {
+        if let Some(val) = self.values.lock().unwrap().get(name) {
+            return Some(val.clone());
+        }
+        self.enclosing.as_ref().and_then(|enc| enc.lookup(name))
+    }
+
+    pub fn assign(&self, name: Token, value: LitType, span: Span) -> Result<()> {
+        if let Some(slot) = self.values.lock().unwrap().get_mut(&name.lexeme) {
+            trace!(name = %name.lexeme, value = %value, "Environment Assign");
+            *slot = value;
             return Ok(());
         }
+        if let Some(ref enc) = self.enclosing {
+            return enc.assign(name, value, span);
+        }
         Err(Report::new(RuntimeError::UndefinedVariable(
             name.lexeme,
             span,
         )))
     }
+
+    pub fn enclosing(&self) -> Option<&Environment> {
+        self.enclosing.as_deref()
+    }
+
+    /// This scope's own variables, sorted by name.
+    pub fn values(&self) -> Vec<(String, LitType)> {
+        self.snapshot()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.snapshot().into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// Every variable in this scope and the ones enclosing it, innermost
+    /// scope first, with values shown as the REPL would print them.
+    pub fn scopes(&self) -> Vec<Vec<(String, String)>> {
+        let mut scopes = vec![self
+            .snapshot()
+            .into_iter()
+            .map(|(name, value)| (name, value.quoted()))
+            .collect()];
+        if let Some(ref enc) = self.enclosing {
+            scopes.extend(enc.scopes());
+        }
+        scopes
+    }
+
+    fn snapshot(&self) -> Vec<(String, LitType)> {
+        let mut values: Vec<(String, LitType)> = self
+            .values
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        values.sort_by(|a, b| a.0.cmp(&b.0));
+        values
+    }
+}
+
+impl std::fmt::Debug for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Only names are printed: values may be closures that capture this
+        // very scope, which would recurse forever.
+        let names: Vec<String> = self.snapshot().into_iter().map(|(k, _)| k).collect();
+        f.debug_struct("Environment")
+            .field("values", &names)
+            .field("enclosing", &self.enclosing)
+            .finish()
+    }
 }
 
 impl std::fmt::Display for Environment {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         fn get_enclosing(env: &Environment) -> String {
-            let mut values = String::new();
-            if env.values.len() > 0 {
-                values = env
-                    .values
-                    .clone()
-                    .into_iter()
-                    .fold(String::new(), |acc, (k, v)| {
-                        if acc.len() > 0 {
-                            format!("{}, {:?} = {:?}", acc, k, v)
-                        } else {
-                            format!("{:?} = {:?}", k, v)
-                        }
-                    });
-            }
+            let values = env
+                .snapshot()
+                .into_iter()
+                .fold(String::new(), |acc, (k, v)| {
+                    if !acc.is_empty() {
+                        format!("{}, {:?} = {:?}", acc, k, v)
+                    } else {
+                        format!("{:?} = {:?}", k, v)
+                    }
+                });
             if let Some(ref enc) = env.enclosing {
                 let last_val = get_enclosing(enc);
-                if values.len() > 0 {
-                    return format!("{}, {}", values, last_val);
+                if !values.is_empty() {
+                    format!("{}, {}", values, last_val)
                 } else {
-                    return format!("{}", last_val);
+                    last_val
                 }
             } else {
-                return values;
+                values
             }
         }
         write!(f, "{}", get_enclosing(self))
@@ -1,13 +1,25 @@
 use super::expression::Expr;
 use super::lit::LitType;
+use super::span::Span;
 use super::statement::Statement;
 use super::tokens::Token;
+use color_eyre::eyre::Report;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ParserError {
     #[error("[Error] [Parser] Connot have more than 255 arguments: {0}")]
     MaxArguments(Token),
+    #[error("[Error] [Parser] [MissingIndex] [line {}] Expect an index expression after '['.", .0.line)]
+    MissingIndex(Token),
+    #[error("[Error] [Parser] [MissingMapValue] [line {}] Expect a value after ':'.", .0.line)]
+    MissingMapValue(Token),
+    #[error("[Error] [Parser] [EmptyInterpolation] [line {}] Expect an expression inside '${{}}'.", .0.line)]
+    EmptyInterpolation(Token),
+    #[error("[Error] [Parser] [OutsideLoop] [line {}] Can't use '{}' outside of a loop.", .0.line, .0.lexeme)]
+    OutsideLoop(Token),
+    #[error("[Error] [Parser] [UnknownLabel] [line {}] No enclosing loop is labeled '{}'.", .0.line, .0.lexeme)]
+    UnknownLabel(Token),
     #[error("[Error] [Parser] While missing condition: {0}")]
     WhileMissingCondition(Token),
     #[error("[Error] [Parser] While missing body: {0}")]
@@ -24,12 +36,8 @@ pub enum ParserError {
     UnexpectedAssignmentTarget(Token),
     #[error("[Error] [Parser] Invalid assignment target: {0}")]
     InvalidAssignmentTarget(Token),
-    #[error("[Error] [Parser] Expression missing expression: {0}")]
-    ExpressionNoExpression(Token),
     #[error("[Error] [Parser] Print missing expression: {0}")]
     PrintNoExpression(Token),
-    #[error("[Error] [Parser] Variable declaration error")]
-    VarDeclartionError,
     #[error("[Error] [Parser] Variable expression missing on token: {0}")]
     VarMissingExpr(Token),
     #[error(
@@ -37,6 +45,7 @@ pub enum ParserError {
     )]
     PrimaryTokenError {
         line: i32,
+        span: Span,
         location: String,
         message: String,
     },
@@ -45,57 +54,389 @@ pub enum ParserError {
     )]
     ConsumeTokenError {
         line: i32,
+        span: Span,
         location: String,
         message: String,
     },
 }
 
+impl ParserError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParserError::MaxArguments(token)
+            | ParserError::MissingIndex(token)
+            | ParserError::MissingMapValue(token)
+            | ParserError::EmptyInterpolation(token)
+            | ParserError::OutsideLoop(token)
+            | ParserError::UnknownLabel(token)
+            | ParserError::WhileMissingCondition(token)
+            | ParserError::MissingIfCondition(token)
+            | ParserError::UnexpectedAssignmentTarget(token)
+            | ParserError::InvalidAssignmentTarget(token)
+            | ParserError::PrintNoExpression(token)
+            | ParserError::VarMissingExpr(token) => Some(token.into()),
+            ParserError::WhileMissingBody(expr)
+            | ParserError::LogicAndMissingRight(expr)
+            | ParserError::LogicOrMissingRight(expr)
+            | ParserError::MissingThenBranch(expr) => Some(expr.span()),
+            ParserError::PrimaryTokenError { span, .. }
+            | ParserError::ConsumeTokenError { span, .. } => Some(*span),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ScannerError {
     #[error("[Error] [Scanner] [UnexpectedToken] [line {line:?}] Error: {message:?}")]
-    UnexpectedTokenError { line: i32, message: String },
+    UnexpectedToken {
+        line: i32,
+        span: Span,
+        message: String,
+    },
+    #[error("[Error] [Scanner] [NumberError] [line {line:?}] Error: {message:?}")]
+    InvalidNumber {
+        line: i32,
+        span: Span,
+        message: String,
+    },
+    #[error("[Error] [Scanner] [EscapeError] [line {line:?}, column {}] Error: {message:?}", .span.column())]
+    InvalidEscape {
+        line: i32,
+        span: Span,
+        message: String,
+    },
     #[error("[Error] [Scanner] [StringError] [line {line:?}] Error: {message:?}")]
-    StringError { line: i32, message: String },
+    UnterminatedString {
+        line: i32,
+        span: Span,
+        message: String,
+    },
+}
+
+impl ScannerError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ScannerError::UnexpectedToken { span, .. }
+            | ScannerError::InvalidNumber { span, .. }
+            | ScannerError::InvalidEscape { span, .. }
+            | ScannerError::UnterminatedString { span, .. } => Some(*span),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ResolverError {
+    #[error("[Error] [Resolver] [OwnInitializer] [line {}] Can't read local variable '{0}' in its own initializer.", .1.line())]
+    OwnInitializer(String, Span),
+    #[error("[Error] [Resolver] [AlreadyDeclared] [line {}] Already a variable named '{0}' in this scope.", .1.line())]
+    AlreadyDeclared(String, Span),
+    #[error("[Error] [Resolver] [TopLevelReturn] [line {}] Can't return from top-level code.", .0.line())]
+    TopLevelReturn(Span),
+    #[error("[Error] [Resolver] [InitializerReturn] [line {}] Can't return a value from an initializer.", .0.line())]
+    InitializerReturn(Span),
+    #[error("[Error] [Resolver] [ThisOutsideClass] [line {}] Can't use 'this' outside of a class.", .0.line())]
+    ThisOutsideClass(Span),
+    #[error("[Error] [Resolver] [SuperOutsideClass] [line {}] Can't use 'super' outside of a class.", .0.line())]
+    SuperOutsideClass(Span),
+    #[error("[Error] [Resolver] [SuperWithoutSuperclass] [line {}] Can't use 'super' in a class with no superclass.", .0.line())]
+    SuperWithoutSuperclass(Span),
+    #[error("[Error] [Resolver] [InheritFromSelf] [line {}] A class can't inherit from itself.", .0.line())]
+    InheritFromSelf(Span),
+    #[error("[Error] [Resolver] [UnreachableArm] [line {}] Match arm is unreachable: an earlier arm already matches '{0}'.", .1.line())]
+    UnreachableArm(String, Span),
+}
+
+impl ResolverError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ResolverError::OwnInitializer(_, span)
+            | ResolverError::AlreadyDeclared(_, span)
+            | ResolverError::TopLevelReturn(span)
+            | ResolverError::InitializerReturn(span)
+            | ResolverError::ThisOutsideClass(span)
+            | ResolverError::SuperOutsideClass(span)
+            | ResolverError::SuperWithoutSuperclass(span)
+            | ResolverError::InheritFromSelf(span)
+            | ResolverError::UnreachableArm(_, span) => Some(*span),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum ModuleError {
+    #[error("Can't find module \"{0}\".")]
+    NotFound(String),
+    #[error("Import cycle: {0}.")]
+    Cycle(String),
+    #[error("Module \"{0}\" has errors.")]
+    Invalid(String),
 }
 
 #[derive(Error, Debug)]
 pub enum RuntimeError {
-    #[error("return")]
-    Return(LitType),
-    #[error("[Error] [Runtime] [NativeFunctionError] Error calling native function")]
-    NativeFunctionError,
-    #[error("[Error] [Runtime] [IncorrectArgumentCount] Expected {0} arguments but got {1}.")]
-    IncorrectArgumentCount(usize, usize),
-    #[error("[Error] [Runtime] [NotCallable] Can only call functions and classes: {0}")]
-    NotCallable(Token),
-    #[error("[Error] [Runtime] [InvalidLiteral] {0}")]
+    Throw(LitType, Span),
+    ImportError(ModuleError, Span),
+    MissingExport(String, String, Span),
+    NativeFunctionError(String),
+    /// A `NativeFunctionError`, positioned at the call that raised it.
+    NativeCallError(String, Span),
+    IncorrectArgumentCount(usize, usize, Span),
+    StackOverflow(Span),
+    NotCallable(Span),
     InvalidLiteral(Expr),
-    #[error("[Error] [Runtime] [InvalidGrouping] {0}")]
     InvalidGrouping(Expr),
-    #[error("[Error] [Runtime] [RightHandBoolOrNil] {0}")]
-    RighthandBoolorNil(Expr),
-    #[error("[Error] [Runtime] [UnaryExpects] Unary expect '!' or '1':  {0}")]
+    RighthandBoolorNil(Span),
     UnaryExpects(Expr),
-    #[error("[Error] [Runtime] [InvalidUnary] {0}")]
-    InvalidUnary(Expr),
-    #[error("[Error] [Runtime] [DivideByZero] {0}")]
-    DivideByZero(Expr),
-    #[error("[Error] [Runtime] [InvalidNumerical] Got expression {0} with token {1}")]
+    InvalidUnary(Span),
+    DivideByZero(Span),
+    IntegerOverflow(Span),
+    NonIntegralOperand(Span),
+    InvalidShift(Span),
     InvalidNumerical(Expr, Token),
-    #[error("[Error] [Runtime] [InvalidStringConcat] {0}")]
-    InvalidStringConcat(Expr),
-    #[error("[Error] [Runtime] [BinaryTypeMismatch] {0}")]
-    BinaryTypeMismatch(Expr),
-    #[error("[Error] [Runtime] [InvalidBinaryExpr] {0}")]
+    InvalidStringConcat(Span),
+    BinaryTypeMismatch(Span),
     InvalidBinaryExpr(Expr),
-    #[error("[Error] [Runtime] Variable is undefined: {0} {1}")]
-    UndefinedVariable(String, String),
-    #[error("[Error] [Runtime] Expression is not a variable: {0}")]
+    UndefinedVariable(String, Span),
     ExpressionNotVariable(Expr),
-    #[error("[Error] [Runtime] Statement missing expression: {0}")]
     StatementMissingExpression(Statement),
-    #[error("[Error] [Runtime] Statement not expected here: {0}")]
     UnexpectedStatement(Statement),
-    #[error("[Error] [Runtime] Invalid assignment target: {0} with {1}")]
     InvalidAssignmentTarget(Token, Expr),
+    OnlyInstancesHaveProperties(Span),
+    OnlyInstancesHaveFields(Span),
+    UndefinedProperty(String, Span),
+    IndexOutOfBounds(i64, usize, Span),
+    InvalidIndex(String, Span),
+    NotIndexable(String, Span),
+    MissingKey(String, Span),
+    InvalidKey(String, Span),
+    SuperclassMustBeClass(Span),
+    /// A value the bytecode VM got back from a native that it has no form for.
+    UnsupportedNativeValue(String, Span),
+    InvalidOpCode(u8),
+}
+
+/// How a thrown value that nothing caught is described: error values by their message.
+fn uncaught(value: &LitType) -> String {
+    match value {
+        LitType::Instance(instance) => instance
+            .field("message")
+            .unwrap_or_else(|| value.clone())
+            .to_string(),
+        _ => value.to_string(),
+    }
+}
+
+impl RuntimeError {
+    /// The error for a native function to fail with; the interpreter adds the
+    /// position of the call.
+    pub fn native(message: impl Into<String>) -> Self {
+        RuntimeError::NativeFunctionError(message.into())
+    }
+
+    /// What a native's failure at the call at `span` is reported as: positioned
+    /// there if the native didn't say where, and as a native function error if
+    /// it isn't a runtime error at all.
+    pub(crate) fn from_native(error: Report, span: Span) -> Self {
+        match error.downcast::<RuntimeError>() {
+            Ok(RuntimeError::NativeFunctionError(message)) => {
+                RuntimeError::NativeCallError(message, span)
+            }
+            Ok(error) => error,
+            Err(error) => RuntimeError::NativeCallError(error.to_string(), span),
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            RuntimeError::NativeFunctionError(_) | RuntimeError::InvalidOpCode(_) => None,
+            RuntimeError::NativeCallError(_, span)
+            | RuntimeError::IncorrectArgumentCount(_, _, span)
+            | RuntimeError::StackOverflow(span)
+            | RuntimeError::Throw(_, span)
+            | RuntimeError::ImportError(_, span)
+            | RuntimeError::MissingExport(_, _, span)
+            | RuntimeError::NotCallable(span)
+            | RuntimeError::RighthandBoolorNil(span)
+            | RuntimeError::InvalidUnary(span)
+            | RuntimeError::DivideByZero(span)
+            | RuntimeError::IntegerOverflow(span)
+            | RuntimeError::NonIntegralOperand(span)
+            | RuntimeError::InvalidShift(span)
+            | RuntimeError::InvalidStringConcat(span)
+            | RuntimeError::BinaryTypeMismatch(span)
+            | RuntimeError::OnlyInstancesHaveProperties(span)
+            | RuntimeError::OnlyInstancesHaveFields(span)
+            | RuntimeError::SuperclassMustBeClass(span)
+            | RuntimeError::UnsupportedNativeValue(_, span) => Some(*span),
+            RuntimeError::InvalidAssignmentTarget(token, _) => Some(token.into()),
+            RuntimeError::InvalidLiteral(expr)
+            | RuntimeError::InvalidGrouping(expr)
+            | RuntimeError::UnaryExpects(expr)
+            | RuntimeError::InvalidNumerical(expr, _)
+            | RuntimeError::InvalidBinaryExpr(expr)
+            | RuntimeError::ExpressionNotVariable(expr) => Some(expr.span()),
+            RuntimeError::UndefinedVariable(_, span)
+            | RuntimeError::UndefinedProperty(_, span)
+            | RuntimeError::IndexOutOfBounds(_, _, span)
+            | RuntimeError::InvalidIndex(_, span)
+            | RuntimeError::NotIndexable(_, span)
+            | RuntimeError::MissingKey(_, span)
+            | RuntimeError::InvalidKey(_, span) => Some(*span),
+            RuntimeError::StatementMissingExpression(stmt)
+            | RuntimeError::UnexpectedStatement(stmt) => Some(stmt.span()),
+        }
+    }
+}
+
+impl RuntimeError {
+    /// The error's kind, line and message, which is what a catch clause sees of it.
+    pub fn details(&self) -> (&'static str, Option<i32>, String) {
+        (
+            self.kind(),
+            self.span().map(|span| span.line()),
+            self.message(),
+        )
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            RuntimeError::Throw(..) => "Uncaught",
+            RuntimeError::ImportError(..) => "ImportError",
+            RuntimeError::MissingExport(..) => "MissingExport",
+            RuntimeError::NativeFunctionError(..) | RuntimeError::NativeCallError(..) => {
+                "NativeFunctionError"
+            }
+            RuntimeError::IncorrectArgumentCount(..) => "IncorrectArgumentCount",
+            RuntimeError::StackOverflow(..) => "StackOverflow",
+            RuntimeError::NotCallable(..) => "NotCallable",
+            RuntimeError::InvalidLiteral(..) => "InvalidLiteral",
+            RuntimeError::InvalidGrouping(..) => "InvalidGrouping",
+            RuntimeError::RighthandBoolorNil(..) => "RightHandBoolOrNil",
+            RuntimeError::UnaryExpects(..) => "UnaryExpects",
+            RuntimeError::InvalidUnary(..) => "InvalidUnary",
+            RuntimeError::DivideByZero(..) => "DivideByZero",
+            RuntimeError::IntegerOverflow(..) => "IntegerOverflow",
+            RuntimeError::NonIntegralOperand(..) => "NonIntegralOperand",
+            RuntimeError::InvalidShift(..) => "InvalidShift",
+            RuntimeError::InvalidNumerical(..) => "InvalidNumerical",
+            RuntimeError::InvalidStringConcat(..) => "InvalidStringConcat",
+            RuntimeError::BinaryTypeMismatch(..) => "BinaryTypeMismatch",
+            RuntimeError::InvalidBinaryExpr(..) => "InvalidBinaryExpr",
+            RuntimeError::UndefinedVariable(..) => "UndefinedVariable",
+            RuntimeError::ExpressionNotVariable(..) => "ExpressionNotVariable",
+            RuntimeError::StatementMissingExpression(..) => "StatementMissingExpression",
+            RuntimeError::UnexpectedStatement(..) => "UnexpectedStatement",
+            RuntimeError::InvalidAssignmentTarget(..) => "InvalidAssignmentTarget",
+            RuntimeError::OnlyInstancesHaveProperties(..) => "OnlyInstancesHaveProperties",
+            RuntimeError::OnlyInstancesHaveFields(..) => "OnlyInstancesHaveFields",
+            RuntimeError::UndefinedProperty(..) => "UndefinedProperty",
+            RuntimeError::IndexOutOfBounds(..) => "IndexOutOfBounds",
+            RuntimeError::InvalidIndex(..) => "InvalidIndex",
+            RuntimeError::NotIndexable(..) => "NotIndexable",
+            RuntimeError::MissingKey(..) => "MissingKey",
+            RuntimeError::InvalidKey(..) => "InvalidKey",
+            RuntimeError::SuperclassMustBeClass(..) => "SuperclassMustBeClass",
+            RuntimeError::UnsupportedNativeValue(..) => "UnsupportedNativeValue",
+            RuntimeError::InvalidOpCode(..) => "InvalidOpCode",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            RuntimeError::Throw(value, _) => format!("Uncaught exception: {}", uncaught(value)),
+            RuntimeError::ImportError(error, _) => error.to_string(),
+            RuntimeError::MissingExport(module, name, _) => {
+                format!("Module \"{}\" has no member '{}'.", module, name)
+            }
+            RuntimeError::NativeFunctionError(message)
+            | RuntimeError::NativeCallError(message, _) => {
+                format!("Error calling native function: {}", message)
+            }
+            RuntimeError::IncorrectArgumentCount(expected, got, _) => {
+                format!("Expected {} arguments but got {}.", expected, got)
+            }
+            RuntimeError::StackOverflow(_) => "Stack overflow.".into(),
+            RuntimeError::NotCallable(_) => "Can only call functions and classes.".into(),
+            RuntimeError::InvalidLiteral(expr)
+            | RuntimeError::InvalidGrouping(expr)
+            | RuntimeError::InvalidBinaryExpr(expr) => expr.to_string(),
+            RuntimeError::RighthandBoolorNil(_) => "Operand of '!' must be a bool or nil.".into(),
+            RuntimeError::UnaryExpects(expr) => format!("Unary expect '!' or '1':  {}", expr),
+            RuntimeError::InvalidUnary(_) => "Operand of '-' must be a number.".into(),
+            RuntimeError::DivideByZero(_) => "Division by zero.".into(),
+            RuntimeError::IntegerOverflow(_) => "Integer overflow.".into(),
+            RuntimeError::NonIntegralOperand(_) => "Bitwise operands must be integers.".into(),
+            RuntimeError::InvalidShift(_) => "Shift amount must be between 0 and 63.".into(),
+            RuntimeError::InvalidNumerical(_, operator) => {
+                format!("Operator '{}' is not supported on numbers.", operator)
+            }
+            RuntimeError::InvalidStringConcat(_) => "Strings only support '+'.".into(),
+            RuntimeError::BinaryTypeMismatch(_) => {
+                "Operands must be two numbers or two strings.".into()
+            }
+            RuntimeError::UndefinedVariable(name, _) => format!("Variable is undefined: {}", name),
+            RuntimeError::ExpressionNotVariable(expr) => {
+                format!("Expression is not a variable: {}", expr)
+            }
+            RuntimeError::StatementMissingExpression(stmt) => {
+                format!("Statement missing expression: {}", stmt)
+            }
+            RuntimeError::UnexpectedStatement(stmt) => {
+                format!("Statement not expected here: {}", stmt)
+            }
+            RuntimeError::InvalidAssignmentTarget(token, expr) => {
+                format!("Invalid assignment target: {} with {}", token, expr)
+            }
+            RuntimeError::OnlyInstancesHaveProperties(_) => {
+                "Only instances have properties.".into()
+            }
+            RuntimeError::OnlyInstancesHaveFields(_) => "Only instances have fields.".into(),
+            RuntimeError::UndefinedProperty(name, _) => format!("Undefined property: {}", name),
+            RuntimeError::IndexOutOfBounds(index, len, _) => format!(
+                "Index {} is out of bounds for a list of length {}.",
+                index, len
+            ),
+            RuntimeError::InvalidIndex(ty, _) => format!("List index must be an int, got {}.", ty),
+            RuntimeError::NotIndexable(ty, _) => {
+                format!("Only lists and maps can be indexed, got {}.", ty)
+            }
+            RuntimeError::MissingKey(key, _) => format!("Key {} is not in the map.", key),
+            RuntimeError::InvalidKey(ty, _) => format!("A {} can't be used as a map key.", ty),
+            RuntimeError::SuperclassMustBeClass(_) => "Superclass must be a class.".into(),
+            RuntimeError::UnsupportedNativeValue(value, _) => format!(
+                "Value {} returned by a native function can't be used here.",
+                value
+            ),
+            RuntimeError::InvalidOpCode(byte) => format!("Unknown opcode {}", byte),
+        }
+    }
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (kind, line, message) = self.details();
+        write!(f, "[Error] [Runtime] [{}] ", kind)?;
+        if let Some(line) = line {
+            write!(f, "[line {}] ", line)?;
+        }
+        write!(f, "{}", message)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CompilerError {
+    #[error("[Error] [Compiler] [TooManyConstants] [line {0}] Too many constants in one chunk.")]
+    TooManyConstants(i32),
+    #[error("[Error] [Compiler] [TooManyLocals] [line {0}] Too many local variables in function.")]
+    TooManyLocals(i32),
+    #[error(
+        "[Error] [Compiler] [TooManyUpvalues] [line {0}] Too many closure variables in function."
+    )]
+    TooManyUpvalues(i32),
+    #[error("[Error] [Compiler] [JumpTooLarge] [line {0}] Too much code to jump over.")]
+    JumpTooLarge(i32),
+    #[error("[Error] [Compiler] [LoopTooLarge] [line {0}] Loop body too large.")]
+    LoopTooLarge(i32),
+    #[error("[Error] [Compiler] [TooManyElements] [line {0}] Too many elements in a list or map literal.")]
+    TooManyElements(i32),
 }
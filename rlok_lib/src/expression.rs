@@ -1,5 +1,6 @@
 use super::lit::LitType;
 use super::span::Span;
+use super::statement::Statement;
 use super::tokens::Token;
 
 #[derive(Debug, Clone)]
@@ -26,11 +27,21 @@ pub enum Expr {
     Variable {
         span: Span,
         name: Token,
+        depth: Option<usize>,
     },
     Assign {
         span: Span,
         name: Token,
         value: Box<Expr>,
+        depth: Option<usize>,
+    },
+    /// `++` or `--` on a variable; a prefix one evaluates to the updated value.
+    Increment {
+        span: Span,
+        name: Token,
+        operator: Token,
+        prefix: bool,
+        depth: Option<usize>,
     },
     Logcial {
         span: Span,
@@ -38,14 +49,99 @@ pub enum Expr {
         operator: Token,
         right: Box<Expr>,
     },
+    Conditional {
+        span: Span,
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
     Call {
         span: Span,
         callee: Box<Expr>,
         paren: Token,
-        arguments: Vec<Box<Expr>>,
+        arguments: Vec<Expr>,
+    },
+    Get {
+        span: Span,
+        object: Box<Expr>,
+        name: Token,
+    },
+    Set {
+        span: Span,
+        object: Box<Expr>,
+        name: Token,
+        value: Box<Expr>,
+    },
+    List {
+        span: Span,
+        elements: Vec<Expr>,
+    },
+    Stringify {
+        span: Span,
+        expression: Box<Expr>,
+    },
+    Map {
+        span: Span,
+        entries: Vec<(Expr, Expr)>,
+    },
+    Index {
+        span: Span,
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    SetIndex {
+        span: Span,
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
+    This {
+        span: Span,
+        keyword: Token,
+        depth: Option<usize>,
+    },
+    Super {
+        span: Span,
+        keyword: Token,
+        method: Token,
+        depth: Option<usize>,
+    },
+    /// An anonymous function, held as a `Statement::Function` named "lambda".
+    Lambda {
+        span: Span,
+        declaration: Box<Statement>,
     },
 }
 
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Binary { span, .. }
+            | Expr::Grouping { span, .. }
+            | Expr::Literal { span, .. }
+            | Expr::Unary { span, .. }
+            | Expr::Variable { span, .. }
+            | Expr::Assign { span, .. }
+            | Expr::Increment { span, .. }
+            | Expr::Logcial { span, .. }
+            | Expr::Conditional { span, .. }
+            | Expr::Call { span, .. }
+            | Expr::Get { span, .. }
+            | Expr::Set { span, .. }
+            | Expr::List { span, .. }
+            | Expr::Map { span, .. }
+            | Expr::Stringify { span, .. }
+            | Expr::Index { span, .. }
+            | Expr::SetIndex { span, .. }
+            | Expr::This { span, .. }
+            | Expr::Super { span, .. }
+            | Expr::Lambda { span, .. } => *span,
+        }
+    }
+}
+
 impl std::fmt::Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -71,11 +167,16 @@ impl std::fmt::Display for Expr {
                 operator,
                 right,
             } => write!(f, "{:?}{:?} ", operator, right),
-            Expr::Variable { span: _, name } => write!(f, "{:?}", name),
+            Expr::Variable {
+                span: _,
+                name,
+                depth: _,
+            } => write!(f, "{:?}", name),
             Expr::Assign {
                 span: _,
                 name,
                 value,
+                depth: _,
             } => write!(f, "{:?} = {:?}", name, value),
             Expr::Logcial {
                 span: _,
@@ -83,14 +184,33 @@ impl std::fmt::Display for Expr {
                 operator,
                 right,
             } => write!(f, "{:?} {:?} {:?}", left, operator, right),
+            Expr::Conditional {
+                span: _,
+                condition,
+                then_branch,
+                else_branch,
+            } => write!(f, "{:?} ? {:?} : {:?}", condition, then_branch, else_branch),
+            Expr::Increment {
+                span: _,
+                name,
+                operator,
+                prefix,
+                depth: _,
+            } => {
+                if *prefix {
+                    write!(f, "{:?}{:?}", operator, name)
+                } else {
+                    write!(f, "{:?}{:?}", name, operator)
+                }
+            }
             Expr::Call {
                 span: _,
                 callee,
                 paren: _,
                 arguments,
             } => {
-                let arguments_out = arguments.into_iter().fold(String::new(), |acc, arg| {
-                    if acc.len() > 0 {
+                let arguments_out = arguments.iter().fold(String::new(), |acc, arg| {
+                    if !acc.is_empty() {
                         format!("{:?}, {:?}", acc, arg)
                     } else {
                         format!("{:?}", arg)
@@ -98,6 +218,60 @@ impl std::fmt::Display for Expr {
                 });
                 write!(f, "{:?}({:?})", callee, arguments_out)
             }
+            Expr::Get {
+                span: _,
+                object,
+                name,
+            } => write!(f, "{:?}.{:?}", object, name),
+            Expr::Set {
+                span: _,
+                object,
+                name,
+                value,
+            } => write!(f, "{:?}.{:?} = {:?}", object, name, value),
+            Expr::List { span: _, elements } => {
+                let elements: Vec<String> = elements.iter().map(|e| format!("{:?}", e)).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Expr::Stringify {
+                span: _,
+                expression,
+            } => write!(f, "str({:?})", expression),
+            Expr::Map { span: _, entries } => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| format!("{:?}: {:?}", key, value))
+                    .collect();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
+            Expr::Index {
+                span: _,
+                object,
+                bracket: _,
+                index,
+            } => write!(f, "{:?}[{:?}]", object, index),
+            Expr::SetIndex {
+                span: _,
+                object,
+                bracket: _,
+                index,
+                value,
+            } => write!(f, "{:?}[{:?}] = {:?}", object, index, value),
+            Expr::This {
+                span: _,
+                keyword,
+                depth: _,
+            } => write!(f, "{:?}", keyword),
+            Expr::Super {
+                span: _,
+                keyword,
+                method,
+                depth: _,
+            } => write!(f, "{:?}.{:?}", keyword, method),
+            Expr::Lambda {
+                span: _,
+                declaration,
+            } => write!(f, "{}", declaration),
         }
     }
 }
@@ -0,0 +1,42 @@
+use color_eyre::eyre::Result;
+use serde_json::Value as Json;
+use std::io::{self, BufRead, Read, Write};
+use tracing::trace;
+
+/// The next message on stdin, or `None` once it's closed. The debug adapter
+/// and language server protocols both send a JSON body after a
+/// `Content-Length` header.
+pub fn read() -> Result<Option<Json>> {
+    let mut input = io::stdin().lock();
+    let mut length = None;
+    let length = loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            match length {
+                Some(length) => break length,
+                None => continue,
+            }
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = Some(value.trim().parse::<usize>()?);
+        }
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    let message: Json = serde_json::from_slice(&body)?;
+    trace!(message = %message, "Received");
+    Ok(Some(message))
+}
+
+pub fn write(message: &Json) -> Result<()> {
+    let body = message.to_string();
+    trace!(message = %body, "Sending");
+    let mut output = io::stdout().lock();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()?;
+    Ok(())
+}
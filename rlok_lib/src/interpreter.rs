@@ -1,47 +1,183 @@
+use super::compiler::Compiler;
+use super::dap;
+use super::debug_console::Console;
+use super::debugger::{Debugger, Location};
+use super::diagnostic::Diagnostic;
 use super::environment::Environment;
-use super::error_handler::RuntimeError;
+use super::error_handler::{ModuleError, RuntimeError};
 use super::expression::Expr;
 use super::lit::LitType;
-use super::lox_callable::{Callable, Clock, LoxCallable, LoxFunction};
+use super::lox_callable::{Callable, LoxCallable, LoxFunction, NativeFn, NativeFunction};
+use super::lox_class::{LoxClass, LoxInstance};
+use super::lox_list::LoxList;
+use super::lox_map::LoxMap;
+use super::lox_module::LoxModule;
+use super::lsp;
+use super::numeric::{Number, NumberError};
 use super::parser::Parser;
+use super::pattern::MatchArm;
+use super::repl::Repl;
+use super::resolver::Resolver;
 use super::scanner::Scanner;
+use super::source_map::{SourceMap, REPL};
 use super::span::Span;
 use super::statement::Statement;
+use super::stdlib;
 use super::tokens::{Token, TokenType};
+use super::vm::{FRAMES_MAX, VM};
 use color_eyre::eyre::{Report, Result};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::env;
 use std::fs;
 use std::io;
-use std::io::Write;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
 use tracing::{span, trace, Level};
 
+/// The source name of expressions typed into the debugger.
+const DEBUG: &str = "<debug>";
+
+/// How a statement finished. Runtime errors are the `Err` side of the
+/// `Result` a statement evaluates to, so `?` carries them outwards.
+#[derive(Debug)]
+pub enum Completion {
+    /// Ran to the end. Expression statements keep their value for the REPL.
+    Normal(Option<LitType>),
+    Return(LitType),
+    Break(Option<String>),
+    Continue(Option<String>),
+    /// A thrown value on its way out to a catch clause.
+    Throw(LitType, Span),
+}
+
+/// A value thrown inside a call, carried out through the expressions around
+/// the call as an error until the statement holding them turns it back into
+/// a `Completion::Throw`.
+#[derive(Debug, Error)]
+#[error("Uncaught exception: {0}")]
+pub(crate) struct Thrown(pub LitType, pub Span);
+
+impl Completion {
+    /// Turns a throw that escaped a script or module into the error it is
+    /// reported as.
+    pub(crate) fn uncaught(self) -> Result<Completion> {
+        match self {
+            Completion::Throw(value, span) => Err(Report::new(RuntimeError::Throw(value, span))),
+            completion => Ok(completion),
+        }
+    }
+}
+
+/// Where `print` output and error reports go instead of stdout and stderr,
+/// such as into the debug adapter's output events.
+pub trait Output: std::fmt::Debug {
+    fn print(&self, text: &str);
+    fn error(&self, text: &str);
+}
+
 #[derive(Debug)]
 pub struct Interpreter {
     pub globals: Environment,
     pub environment: Environment,
-    parser: Option<Parser>,
+    builtins: Environment,
+    vm: Option<VM>,
+    natives: Vec<NativeFunction>,
     is_repl: bool,
+    file: String,
+    sources: SourceMap,
+    color: bool,
+    search_paths: Vec<PathBuf>,
+    loading: Vec<PathBuf>,
+    modules: HashMap<PathBuf, LoxModule>,
+    error_class: LoxClass,
+    had_runtime_error: bool,
+    debugger: Option<Debugger>,
+    output: Option<Box<dyn Output>>,
+    /// How many Lox function calls are in progress, limited like the VM's frames.
+    depth: usize,
+    /// The call expression being made, where entering its callee can fail.
+    call_span: Span,
 }
 
 impl Interpreter {
     #[tracing::instrument]
     pub fn build() -> Self {
-        env_logger::init();
-        let mut globals = Environment::new(None);
-        globals.define(
-            "clock".into(),
-            LitType::Callable(LoxCallable::Clock(Clock::new("clock".into(), None))),
-        );
-        Interpreter {
-            parser: None,
+        let _ = env_logger::try_init();
+        let builtins = Environment::new(None);
+        let globals = Environment::new(Some(builtins.clone()));
+        let mut interpreter = Interpreter {
+            vm: None,
+            natives: Vec::new(),
             globals: globals.clone(),
             environment: globals.clone(),
+            builtins,
             is_repl: false,
+            file: REPL.into(),
+            sources: SourceMap::default(),
+            color: io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none(),
+            search_paths: env::var_os("RLOK_PATH")
+                .map(|paths| env::split_paths(&paths).collect())
+                .unwrap_or_default(),
+            loading: Vec::new(),
+            modules: HashMap::new(),
+            error_class: LoxClass::new("Error".into(), None),
+            had_runtime_error: false,
+            debugger: None,
+            output: None,
+            depth: 0,
+            call_span: Span::default(),
+        };
+        stdlib::register(&mut interpreter);
+        interpreter
+    }
+
+    pub fn register_native<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&mut Interpreter, Vec<LitType>) -> Result<LitType> + Send + Sync + 'static,
+    {
+        trace!(name, arity, "Registering native function");
+        let function: Arc<NativeFn> = Arc::new(function);
+        let native = NativeFunction::new(name.into(), None).set_native(arity, function);
+        self.builtins.define(
+            name.into(),
+            LitType::Callable(LoxCallable::Native(native.clone())),
+        );
+        if let Some(vm) = &mut self.vm {
+            vm.define_native(native.clone());
         }
+        self.natives.push(native);
     }
 
-    pub fn start(&mut self, args: Vec<String>) -> Result<()> {
+    fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+        if let Some(index) = args.iter().position(|arg| arg == flag) {
+            args.remove(index);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn start(&mut self, mut args: Vec<String>) -> Result<()> {
         trace!("Starting Interpreter");
-        if args.len() == 2 {
+        if Self::take_flag(&mut args, "--vm") {
+            self.vm = Some(self.new_vm());
+        }
+        if Self::take_flag(&mut args, "--color") {
+            self.color = true;
+        }
+        if Self::take_flag(&mut args, "--no-color") {
+            self.color = false;
+        }
+        if args.len() == 2 && args[1] == "dap" {
+            dap::serve(self)?;
+        } else if args.len() == 2 && args[1] == "lsp" {
+            lsp::serve(self)?;
+        } else if args.len() == 3 && args[1] == "debug" {
+            self.debug_file(&args[2], Debugger::new(Box::new(Console::build()?), true))?;
+        } else if args.len() == 2 {
             self.run_file(&args[1])?;
         } else {
             self.is_repl = true;
@@ -50,86 +186,389 @@ impl Interpreter {
         Ok(())
     }
 
-    fn run(&mut self, contents: String) -> Result<()> {
-        let mut scanner = Scanner::build(contents);
-        let tokens = scanner.scan_tokens()?;
-        self.parser = Some(Parser::new(tokens.clone())?);
-        if let Some(ref mut parser) = self.parser {
-            match parser.parse() {
-                Ok(ast) => {
-                    let span = span!(Level::TRACE, "interpreter");
-                    let _enter = span.enter();
-                    if let Some(ast) = ast {
-                        for stmt in &ast {
-                            trace!(statement = %stmt, "Processing statement.");
-                            match self.evaluate_statement(stmt.clone()) {
-                                Ok(output) => {
-                                    if self.is_repl {
-                                        if let Some(out) = output {
-                                            Self::print_lit(out);
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!("{}", e);
-                                }
-                            }
+    fn new_vm(&self) -> VM {
+        let mut vm = VM::new();
+        for native in &self.natives {
+            vm.define_native(native.clone());
+        }
+        vm
+    }
+
+    /// Adds a directory to search for imported modules, after the importing file's own.
+    pub fn add_search_path(&mut self, path: &str) {
+        self.search_paths.push(path.into());
+    }
+
+    /// Whether an error went uncaught while running a script, which should
+    /// make the process exit unsuccessfully.
+    pub fn had_runtime_error(&self) -> bool {
+        self.had_runtime_error
+    }
+
+    pub(crate) fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Sends `print` output and error reports to `output`, without color.
+    pub fn set_output(&mut self, output: Box<dyn Output>) {
+        self.color = false;
+        self.output = Some(output);
+    }
+
+    pub(crate) fn report(&self, error: &Report) {
+        let diagnostic = Diagnostic::new(&self.sources, self.color);
+        match &self.output {
+            Some(output) => output.error(&format!("{}\n", diagnostic.render(error))),
+            None => eprintln!("{}", diagnostic.render(error)),
+        }
+    }
+
+    /// Scans `contents`, reporting the error if it can't be.
+    pub(crate) fn scan_source(&mut self, name: &str, contents: String) -> Option<Vec<Token>> {
+        let (origin, line) = self.sources.add(name, &contents);
+        let mut scanner = Scanner::build(contents).set_origin(origin, line);
+        match scanner.scan_tokens() {
+            Ok(tokens) => Some(tokens),
+            Err(e) => {
+                self.report(&e);
+                None
+            }
+        }
+    }
+
+    /// Scans, parses and resolves `contents`, reporting every error found.
+    pub(crate) fn parse_source(&mut self, name: &str, contents: String) -> Option<Vec<Statement>> {
+        self.parse_with(name, contents, Resolver::new())
+    }
+
+    fn parse_with(
+        &mut self,
+        name: &str,
+        contents: String,
+        resolver: Resolver,
+    ) -> Option<Vec<Statement>> {
+        let tokens = self.scan_source(name, contents)?;
+        let interactive = name == REPL || name == DEBUG;
+        let mut parser = Parser::new(tokens).ok()?.set_interactive(interactive);
+        match parser.parse() {
+            Ok(mut ast) => {
+                let errors = resolver.resolve(&mut ast);
+                if !errors.is_empty() {
+                    for e in errors {
+                        self.report(&e);
+                    }
+                    return None;
+                }
+                Some(ast)
+            }
+            Err(errors) => {
+                for e in errors {
+                    self.report(&e);
+                }
+                None
+            }
+        }
+    }
+
+    pub fn run(&mut self, contents: String) -> Result<()> {
+        let file = self.file.clone();
+        let Some(ast) = self.parse_source(&file, contents) else {
+            return Ok(());
+        };
+        let span = span!(Level::TRACE, "interpreter");
+        let _enter = span.enter();
+        if let Some(mut vm) = self.vm.take() {
+            match Compiler::new(self.is_repl).compile(&ast) {
+                Ok(functions) => {
+                    for function in functions {
+                        if let Err(e) = vm.interpret(function, self) {
+                            self.report(&e);
+                            self.had_runtime_error |= !self.is_repl;
+                        }
+                    }
+                }
+                Err(e) => self.report(&e),
+            }
+            self.vm = Some(vm);
+            return Ok(());
+        }
+        for stmt in &ast {
+            trace!(statement = %stmt, "Processing statement.");
+            match self
+                .evaluate_statement(stmt.clone())
+                .and_then(Completion::uncaught)
+            {
+                Ok(completion) => {
+                    if self.is_repl {
+                        if let Completion::Normal(Some(out)) = completion {
+                            self.print_lit(out);
                         }
-                    } else {
-                        eprintln!("Failed to generate AST")
                     }
                 }
                 Err(e) => {
-                    eprintln!("{}", e);
+                    self.report(&e);
+                    self.had_runtime_error |= !self.is_repl;
                 }
             }
         }
         Ok(())
     }
 
-    fn run_file(&mut self, file: &str) -> Result<()> {
+    pub fn run_file(&mut self, file: &str) -> Result<()> {
         let contents = fs::read_to_string(file)?;
-        self.run(contents)?;
+        self.file = file.into();
+        self.loading.push(fs::canonicalize(file)?);
+        let result = self.run(contents);
+        self.loading.pop();
+        result
+    }
+
+    /// Runs a file under `debugger`, on the tree-walking interpreter whatever
+    /// backend was chosen.
+    pub fn debug_file(&mut self, file: &str, debugger: Debugger) -> Result<()> {
+        self.vm = None;
+        self.debugger = Some(debugger);
+        let result = self.run_file(file);
+        self.debugger = None;
+        result
+    }
+
+    /// Fails with a catchable stack overflow rather than running out of
+    /// native stack, at the depth where the VM would run out of frames.
+    pub(crate) fn enter_call(&mut self, name: &str) -> Result<()> {
+        if self.depth >= FRAMES_MAX {
+            return Err(Report::new(RuntimeError::StackOverflow(self.call_span)));
+        }
+        self.depth += 1;
+        if let Some(debugger) = &mut self.debugger {
+            debugger.enter_call(name);
+        }
         Ok(())
     }
 
-    fn run_prompt(&mut self) -> Result<()> {
-        loop {
-            print!("> ");
-            io::stdout().flush().unwrap();
-            let mut buffer = String::new();
-            io::stdin()
-                .read_line(&mut buffer)
-                .expect("Unable to read line");
-            if buffer.len() <= 1 {
+    pub(crate) fn leave_call(&mut self) {
+        self.depth -= 1;
+        if let Some(debugger) = &mut self.debugger {
+            debugger.leave_call();
+        }
+    }
+
+    fn next_pass(&mut self) {
+        if let Some(debugger) = &mut self.debugger {
+            debugger.next_pass();
+        }
+    }
+
+    /// Hands the statement at `span` to the debugger, which may pause before it runs.
+    fn debug_statement(&mut self, span: Span) -> Result<()> {
+        let Some(mut debugger) = self.debugger.take() else {
+            return Ok(());
+        };
+        let file = self
+            .sources
+            .find(span.start())
+            .map_or(self.file.clone(), |file| file.name().into());
+        let location = Location::new(&file, span.line());
+        let result = debugger.statement(location, span.start(), self.environment.clone(), self);
+        self.debugger = Some(debugger);
+        result
+    }
+
+    /// The name of the file `span` was read from.
+    pub(crate) fn file_at(&self, span: Span) -> Option<&str> {
+        self.sources.find(span.start()).map(|file| file.name())
+    }
+
+    pub(crate) fn source_line(&self, location: &Location) -> Option<&str> {
+        self.sources.line(&location.file, location.line)
+    }
+
+    /// Runs `source` in `environment`, the scope of a paused call, and returns
+    /// the value of its last expression statement. Syntax errors are reported
+    /// and give `None`.
+    pub(crate) fn evaluate_paused(
+        &mut self,
+        source: &str,
+        environment: &Environment,
+    ) -> Result<Option<LitType>> {
+        let mut scopes = Vec::new();
+        let mut scope = Some(environment);
+        while let Some(current) = scope.filter(|current| !current.ptr_eq(&self.globals)) {
+            scopes.push(current.names());
+            scope = current.enclosing();
+        }
+        scopes.reverse();
+        let Some(ast) = self.parse_with(DEBUG, source.into(), Resolver::within(scopes)) else {
+            return Ok(None);
+        };
+        let previous = std::mem::replace(&mut self.environment, environment.clone());
+        let mut result = Ok(None);
+        for stmt in ast {
+            result = match self.evaluate_statement(stmt).and_then(Completion::uncaught) {
+                Ok(Completion::Normal(value)) => Ok(value),
+                Ok(_) => Ok(None),
+                Err(e) => Err(e),
+            };
+            if result.is_err() {
                 break;
-            } else {
-                match self.run(buffer) {
-                    Ok(()) => (),
-                    Err(e) => return Err(e),
-                }
             }
         }
-        Ok(())
+        self.environment = previous;
+        result
     }
 
-    pub fn get_span(&self, span: Span) -> String {
-        if let Some(parser) = &self.parser {
-            parser.get_span(span)
-        } else {
-            String::from("NO SPAN")
+    /// Runs a file in the current session, for the REPL's `:load`.
+    pub(crate) fn load_file(&mut self, file: &str) -> Result<()> {
+        let repl = std::mem::take(&mut self.file);
+        let result = self.run_file(file);
+        self.file = repl;
+        result
+    }
+
+    /// Forgets every global and loaded module, for the REPL's `:reset`.
+    pub(crate) fn reset(&mut self) {
+        self.globals = Environment::new(Some(self.builtins.clone()));
+        self.environment = self.globals.clone();
+        self.modules.clear();
+        if self.vm.is_some() {
+            self.vm = Some(self.new_vm());
         }
     }
 
-    fn literal_expr(&self, expr: Expr) -> Result<LitType> {
-        if let Expr::Literal { span: _, value } = expr.clone() {
-            if let Some(val) = value {
-                let span = span!(Level::TRACE, "literal expression");
-                let _enter = span.enter();
-                trace!(value = %val);
-                return Ok(val);
+    /// The variables in scope, innermost scope first and builtins last.
+    pub(crate) fn scopes(&self) -> Vec<Vec<(String, String)>> {
+        match &self.vm {
+            Some(vm) => vm.scopes(),
+            None => self.environment.scopes(),
+        }
+    }
+
+    /// A module's path as shown in diagnostics: relative to the working directory when possible.
+    pub(crate) fn display_path(file: &Path) -> String {
+        env::current_dir()
+            .ok()
+            .and_then(|dir| file.strip_prefix(dir).ok())
+            .unwrap_or(file)
+            .display()
+            .to_string()
+    }
+
+    /// Finds `path` next to the importing file, or failing that in one of the search paths.
+    pub(crate) fn find_module(&self, path: &str) -> Result<PathBuf, ModuleError> {
+        let base = self
+            .loading
+            .last()
+            .and_then(|file| file.parent())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+        std::iter::once(base)
+            .chain(self.search_paths.iter().cloned())
+            .map(|dir| dir.join(path))
+            .find(|candidate| candidate.is_file())
+            .and_then(|candidate| fs::canonicalize(candidate).ok())
+            .ok_or_else(|| ModuleError::NotFound(path.into()))
+    }
+
+    /// Reads and parses a module that isn't loaded yet, refusing to re-enter one
+    /// that is still running its top-level code.
+    pub(crate) fn parse_module(
+        &mut self,
+        path: &str,
+        file: &Path,
+    ) -> Result<Vec<Statement>, ModuleError> {
+        if let Some(index) = self.loading.iter().position(|loading| loading == file) {
+            let cycle: Vec<String> = self.loading[index..]
+                .iter()
+                .map(|loading| Self::display_path(loading))
+                .chain(std::iter::once(Self::display_path(file)))
+                .collect();
+            return Err(ModuleError::Cycle(cycle.join(" -> ")));
+        }
+        let contents = fs::read_to_string(file).map_err(|_| ModuleError::NotFound(path.into()))?;
+        self.parse_source(&Self::display_path(file), contents)
+            .ok_or_else(|| ModuleError::Invalid(path.into()))
+    }
+
+    pub(crate) fn enter_module(&mut self, file: PathBuf) {
+        self.loading.push(file);
+    }
+
+    pub(crate) fn leave_module(&mut self) {
+        self.loading.pop();
+    }
+
+    fn import_module(&mut self, path: &Token, span: Span) -> Result<LoxModule> {
+        let span_trace = span!(Level::TRACE, "import");
+        let _enter = span_trace.enter();
+        let name = path.literal.clone().unwrap_or_default();
+        let error = |e| Report::new(RuntimeError::ImportError(e, span));
+        let file = self.find_module(&name).map_err(error)?;
+        if let Some(module) = self.modules.get(&file) {
+            return Ok(module.clone());
+        }
+        let ast = self.parse_module(&name, &file).map_err(error)?;
+        trace!(module = %name, "Running module");
+        let module = LoxModule::new(name, Environment::new(Some(self.builtins.clone())));
+        let environment = std::mem::replace(&mut self.environment, module.globals());
+        let globals = std::mem::replace(&mut self.globals, module.globals());
+        self.enter_module(file.clone());
+        let mut result = Ok(());
+        for stmt in ast {
+            result = self
+                .evaluate_statement(stmt)
+                .and_then(Completion::uncaught)
+                .map(|_| ());
+            if result.is_err() {
+                break;
             }
         }
+        self.leave_module();
+        self.environment = environment;
+        self.globals = globals;
+        result?;
+        self.modules.insert(file, module.clone());
+        Ok(module)
+    }
+
+    fn import_statement(
+        &mut self,
+        path: Token,
+        alias: Option<Token>,
+        names: Vec<Token>,
+        span: Span,
+    ) -> Result<()> {
+        let module = self.import_module(&path, span)?;
+        for name in names {
+            let Some(value) = module.get(&name.lexeme) else {
+                return Err(Report::new(RuntimeError::MissingExport(
+                    module.name().into(),
+                    name.lexeme.clone(),
+                    Span::from(&name),
+                )));
+            };
+            self.environment.define(name.lexeme, value);
+        }
+        if let Some(alias) = alias {
+            self.environment
+                .define(alias.lexeme, LitType::Module(module));
+        }
+        Ok(())
+    }
+
+    fn run_prompt(&mut self) -> Result<()> {
+        Repl::build()?.run(self)
+    }
+
+    fn literal_expr(&self, expr: Expr) -> Result<LitType> {
+        if let Expr::Literal {
+            span: _,
+            value: Some(val),
+        } = expr.clone()
+        {
+            let span = span!(Level::TRACE, "literal expression");
+            let _enter = span.enter();
+            trace!(value = %val);
+            return Ok(val);
+        }
         Err(Report::new(RuntimeError::InvalidLiteral(expr)))
     }
 
@@ -159,9 +598,12 @@ impl Interpreter {
             let right = self.evaluate_expr(*right)?;
             match operator.ty {
                 TokenType::Minus => {
-                    if let LitType::Float(f) = right {
-                        trace!(value = f);
-                        return Ok(LitType::Float(-f));
+                    if let Some(number) = right.number() {
+                        trace!(value = %number);
+                        return number
+                            .checked_neg()
+                            .map(LitType::from)
+                            .map_err(|e| Self::number_error(e, expr));
                     }
                 }
                 TokenType::Bang => match right {
@@ -173,12 +615,30 @@ impl Interpreter {
                         trace!(value = "Nil");
                         return Ok(LitType::Bool(true));
                     }
-                    _ => return Err(Report::new(RuntimeError::RighthandBoolorNil(expr))),
+                    _ => return Err(Report::new(RuntimeError::RighthandBoolorNil(expr.span()))),
                 },
+                TokenType::Tilde => {
+                    return match right.number() {
+                        Some(number) => number
+                            .bit_not()
+                            .map(LitType::from)
+                            .map_err(|e| Self::number_error(e, expr)),
+                        None => Err(Report::new(RuntimeError::NonIntegralOperand(expr.span()))),
+                    };
+                }
                 _ => return Err(Report::new(RuntimeError::UnaryExpects(expr))),
             }
         }
-        Err(Report::new(RuntimeError::InvalidUnary(expr)))
+        Err(Report::new(RuntimeError::InvalidUnary(expr.span())))
+    }
+
+    fn number_error(error: NumberError, expr: Expr) -> Report {
+        match error {
+            NumberError::DivideByZero => Report::new(RuntimeError::DivideByZero(expr.span())),
+            NumberError::Overflow => Report::new(RuntimeError::IntegerOverflow(expr.span())),
+            NumberError::NotIntegral => Report::new(RuntimeError::NonIntegralOperand(expr.span())),
+            NumberError::InvalidShift => Report::new(RuntimeError::InvalidShift(expr.span())),
+        }
     }
 
     fn binary_expr(&mut self, expr: Expr) -> Result<LitType> {
@@ -194,59 +654,82 @@ impl Interpreter {
             let span = span!(Level::TRACE, "binary expression");
             let _enter = span.enter();
             trace!(left = %left, operator = %operator.ty, right=%right);
-            if let LitType::Float(r) = right {
-                if let LitType::Float(l) = left {
-                    match operator.ty {
-                        TokenType::Plus => return Ok(LitType::Float(l + r)),
-                        TokenType::Minus => return Ok(LitType::Float(l - r)),
-                        TokenType::Slash => {
-                            if r == 0.0 {
-                                return Err(Report::new(RuntimeError::DivideByZero(expr)));
-                            } else {
-                                return Ok(LitType::Float(l / r));
-                            }
-                        }
-                        TokenType::Star => return Ok(LitType::Float(l * r)),
-                        TokenType::Less => return Ok(LitType::Bool(l < r)),
-                        TokenType::LessEqual => return Ok(LitType::Bool(l <= r)),
-                        TokenType::Greater => return Ok(LitType::Bool(l > r)),
-                        TokenType::GreaterEqual => return Ok(LitType::Bool(l >= r)),
-                        TokenType::EqualEqual => return Ok(LitType::Bool(l == r)),
-                        _ => {
-                            return Err(Report::new(RuntimeError::InvalidNumerical(expr, operator)))
-                        }
+            match operator.ty {
+                TokenType::EqualEqual => return Ok(LitType::Bool(left == right)),
+                TokenType::BangEqual => return Ok(LitType::Bool(left != right)),
+                _ => {}
+            }
+            if let (Some(l), Some(r)) = (left.number(), right.number()) {
+                let result = match operator.ty {
+                    TokenType::Plus => l.checked_add(r),
+                    TokenType::Minus => l.checked_sub(r),
+                    TokenType::Slash => l.checked_div(r),
+                    TokenType::Percent => l.checked_rem(r),
+                    TokenType::TildeSlash => l.checked_floor_div(r),
+                    TokenType::StarStar => l.checked_pow(r),
+                    TokenType::Ampersand => l.bit_and(r),
+                    TokenType::Pipe => l.bit_or(r),
+                    TokenType::Caret => l.bit_xor(r),
+                    TokenType::LessLess => l.shift_left(r),
+                    TokenType::GreaterGreater => l.shift_right(r),
+                    TokenType::Star => l.checked_mul(r),
+                    TokenType::Less => {
+                        return Ok(LitType::Bool(l.compare(r) == Some(Ordering::Less)))
                     }
-                }
+                    TokenType::LessEqual => {
+                        return Ok(LitType::Bool(matches!(
+                            l.compare(r),
+                            Some(Ordering::Less | Ordering::Equal)
+                        )))
+                    }
+                    TokenType::Greater => {
+                        return Ok(LitType::Bool(l.compare(r) == Some(Ordering::Greater)))
+                    }
+                    TokenType::GreaterEqual => {
+                        return Ok(LitType::Bool(matches!(
+                            l.compare(r),
+                            Some(Ordering::Greater | Ordering::Equal)
+                        )))
+                    }
+                    _ => return Err(Report::new(RuntimeError::InvalidNumerical(expr, operator))),
+                };
+                return result
+                    .map(LitType::from)
+                    .map_err(|e| Self::number_error(e, expr));
             }
             if let LitType::Str(r) = right {
                 if let LitType::Str(l) = left {
                     match operator.ty {
                         TokenType::Plus => return Ok(LitType::Str(format!("{}{}", l, r))),
-                        _ => return Err(Report::new(RuntimeError::InvalidStringConcat(expr))),
+                        _ => {
+                            return Err(Report::new(RuntimeError::InvalidStringConcat(expr.span())))
+                        }
                     }
                 }
             }
-            return Err(Report::new(RuntimeError::BinaryTypeMismatch(expr)));
+            return Err(Report::new(RuntimeError::BinaryTypeMismatch(expr.span())));
         }
         Err(Report::new(RuntimeError::InvalidBinaryExpr(expr)))
     }
 
     fn var_expr(&self, expr: Expr) -> Result<LitType> {
         match expr {
-            Expr::Variable { span, name } => {
-                if let Some(val) = self
-                    .environment
-                    .get(name.clone(), self.get_span(span.clone()))?
-                {
+            Expr::Variable { span, name, depth } => {
+                let value = if let Some(distance) = depth {
+                    self.environment.get_at(distance, name.clone(), span)?
+                } else {
+                    self.globals.get(name.clone(), span)?
+                };
+                if let Some(val) = value {
                     let span = span!(Level::TRACE, "var expression");
                     let _enter = span.enter();
                     trace!(name = %name, val = %val);
                     return Ok(val);
                 }
-                return Err(Report::new(RuntimeError::UndefinedVariable(
+                Err(Report::new(RuntimeError::UndefinedVariable(
                     name.lexeme,
-                    self.get_span(span),
-                )));
+                    span,
+                )))
             }
             _ => Err(Report::new(RuntimeError::ExpressionNotVariable(expr))),
         }
@@ -266,55 +749,85 @@ impl Interpreter {
                 return Ok(left);
             };
         }
-        return self.evaluate_expr(right);
+        self.evaluate_expr(right)
     }
 
-    fn print_lit(lit: LitType) {
-        match lit {
-            LitType::Float(flt) => println!("{}", flt),
-            LitType::Str(str) => println!("{}", str),
-            LitType::Bool(bl) => println!("{}", bl),
-            LitType::Callable(call) => match call {
-                LoxCallable::Function(func) => println!("{}", func.to_string()),
-                LoxCallable::Clock(clock) => println!("{}", clock.to_string()),
-            },
-            LitType::Nil => println!("nil"),
+    fn print_lit(&self, lit: LitType) {
+        match &self.output {
+            Some(output) => output.print(&format!("{}\n", lit)),
+            None => println!("{}", lit),
         }
     }
 
     fn is_truthy(lit: LitType) -> bool {
         match lit {
-            LitType::Float(flt) => {
-                if flt > 0.0 {
-                    true
-                } else {
-                    false
-                }
-            }
-            LitType::Str(str) => {
-                if str.len() > 0 {
-                    true
-                } else {
-                    false
-                }
-            }
+            LitType::Int(_) | LitType::Float(_) => lit.number().is_some_and(Number::is_truthy),
+            LitType::Str(str) => !str.is_empty(),
             LitType::Bool(bl) => bl,
+            LitType::List(list) => !list.is_empty(),
+            LitType::Map(map) => !map.is_empty(),
             LitType::Nil => false,
             _ => false,
         }
     }
 
-    fn while_statement(&mut self, condition: Expr, body: Statement) -> Result<Option<LitType>> {
-        let span = span!(Level::TRACE, "while statement");
+    /// Whether a break or continue aimed at `target` belongs to the loop labeled `label`.
+    fn targets(target: &Option<String>, label: &Option<Token>) -> bool {
+        match target {
+            Some(target) => label.as_ref().map(|label| &label.lexeme) == Some(target),
+            None => true,
+        }
+    }
+
+    fn loop_statement(
+        &mut self,
+        condition: Option<Expr>,
+        body: Statement,
+        increment: Option<Expr>,
+        label: Option<Token>,
+    ) -> Result<Completion> {
+        let span = span!(Level::TRACE, "loop statement");
         let _enter = span.enter();
-        while Self::is_truthy(self.evaluate_expr(condition.clone())?) {
-            trace!(body = %body, "While...");
-            let output = self.evaluate_statement(body.clone())?;
-            if let Some(output) = output {
-                return Ok(Some(output));
+        loop {
+            self.next_pass();
+            if let Some(condition) = &condition {
+                if !Self::is_truthy(self.evaluate_expr(condition.clone())?) {
+                    break;
+                }
+            }
+            trace!(body = %body, "Looping...");
+            match self.evaluate_statement(body.clone())? {
+                Completion::Break(target) if Self::targets(&target, &label) => break,
+                Completion::Continue(target) if Self::targets(&target, &label) => (),
+                Completion::Normal(_) => (),
+                jump => return Ok(jump),
             }
+            if let Some(increment) = &increment {
+                self.evaluate_expr(increment.clone())?;
+            }
+        }
+        Ok(Completion::Normal(None))
+    }
+
+    fn for_statement(
+        &mut self,
+        initializer: Option<Statement>,
+        condition: Option<Expr>,
+        increment: Option<Expr>,
+        body: Statement,
+        label: Option<Token>,
+    ) -> Result<Completion> {
+        let environment = Environment::new(Some(self.environment.clone()));
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let mut result = Ok(Completion::Normal(None));
+        if let Some(initializer) = initializer {
+            result = self.evaluate_statement(initializer);
+        }
+        if result.is_ok() {
+            result = self.loop_statement(condition, body, increment, label);
         }
-        Ok(None)
+        self.environment = previous;
+        result
     }
 
     fn if_statement(
@@ -322,7 +835,7 @@ impl Interpreter {
         condition: Expr,
         then_condition: Statement,
         else_condition: Option<Statement>,
-    ) -> Result<Option<LitType>> {
+    ) -> Result<Completion> {
         let span = span!(Level::TRACE, "if statement");
         let _enter = span.enter();
         trace!(condition = %condition);
@@ -331,7 +844,7 @@ impl Interpreter {
         } else if let Some(els) = else_condition {
             return self.evaluate_statement(els);
         }
-        Ok(None)
+        Ok(Completion::Normal(None))
     }
 
     fn var_statement(&mut self, stmt: Statement) -> Result<()> {
@@ -356,25 +869,33 @@ impl Interpreter {
 
     pub fn block_statement(
         &mut self,
-        statements: Vec<Box<Statement>>,
+        statements: Vec<Statement>,
         environment: Environment,
-    ) -> Result<Option<LitType>> {
+    ) -> Result<Completion> {
         let span_trace = span!(Level::TRACE, "b>");
         let _enter = span_trace.enter();
-        self.environment = environment;
+        let previous = std::mem::replace(&mut self.environment, environment);
         trace!(env = %self.environment, "Starting block statement");
+        let mut result = Ok(Completion::Normal(None));
         for stmt in statements {
             trace!(statement = %stmt, "Processing statement in block");
-            self.evaluate_statement(*stmt.clone())?;
+            match self.evaluate_statement(stmt) {
+                Ok(Completion::Normal(_)) => (),
+                other => {
+                    result = other;
+                    break;
+                }
+            }
         }
-        Ok(None)
+        self.environment = previous;
+        result
     }
 
     fn function_statement(
         &mut self,
         name: Token,
         params: Vec<Token>,
-        body: Vec<Box<Statement>>,
+        body: Vec<Statement>,
         span: Span,
     ) -> Result<()> {
         let span_trace = span!(Level::TRACE, "function statement");
@@ -386,24 +907,189 @@ impl Interpreter {
             params,
             body,
         };
-        let function = LitType::Callable(LoxCallable::Function(LoxFunction::new(
-            name.lexeme.clone(),
-            Some(stmt.clone()),
-        )));
+        let function = LitType::Callable(LoxCallable::Function(
+            LoxFunction::new(name.lexeme.clone(), Some(stmt.clone()))
+                .set_closure(self.environment.clone())
+                .set_globals(self.globals.clone()),
+        ));
         self.environment.define(name.lexeme, function);
         Ok(())
     }
 
-    fn return_statement(&mut self, _keyword: Token, value: Expr) -> Result<LitType> {
+    fn class_statement(
+        &mut self,
+        name: Token,
+        superclass: Option<Expr>,
+        methods: Vec<Statement>,
+    ) -> Result<()> {
+        let span_trace = span!(Level::TRACE, "class statement");
+        let _enter = span_trace.enter();
+        trace!(name = %name);
+        let mut superclass_value = None;
+        if let Some(superclass) = superclass {
+            let span = superclass.span();
+            match self.evaluate_expr(superclass)? {
+                LitType::Callable(LoxCallable::Class(class)) => superclass_value = Some(class),
+                _ => return Err(Report::new(RuntimeError::SuperclassMustBeClass(span))),
+            }
+        }
+        let mut closure = self.environment.clone();
+        if let Some(ref superclass) = superclass_value {
+            closure = Environment::new(Some(closure));
+            closure.define(
+                "super".into(),
+                LitType::Callable(LoxCallable::Class(superclass.clone())),
+            );
+        }
+        let mut class_methods = HashMap::new();
+        for method in methods {
+            if let Statement::Function {
+                span: _,
+                name: ref method_name,
+                params: _,
+                body: _,
+            } = method
+            {
+                let function = LoxFunction::new(method_name.lexeme.clone(), Some(method.clone()))
+                    .set_closure(closure.clone())
+                    .set_globals(self.globals.clone())
+                    .set_initializer(method_name.lexeme == "init");
+                class_methods.insert(method_name.lexeme.clone(), function);
+            }
+        }
+        let class = LoxClass::new(name.lexeme.clone(), None)
+            .set_superclass(superclass_value)
+            .set_methods(class_methods);
+        self.environment
+            .define(name.lexeme, LitType::Callable(LoxCallable::Class(class)));
+        Ok(())
+    }
+
+    /// The value a catch clause receives for `error`: a thrown value as is, and
+    /// built-in errors as an `Error` instance.
+    fn caught(&self, error: &Report) -> LitType {
+        let cause = error.root_cause();
+        let (span, details) = match cause.downcast_ref::<RuntimeError>() {
+            Some(RuntimeError::Throw(value, _)) => return value.clone(),
+            Some(error) => (error.span(), error.details()),
+            None => (None, ("Error", None, cause.to_string())),
+        };
+        let error = LoxInstance::new(self.error_class.clone());
+        for (name, value) in self.error_fields(span, details) {
+            error.set_field(name, value);
+        }
+        LitType::Instance(error)
+    }
+
+    /// The fields of the `Error` instance for an error at `span` with the given
+    /// kind, line and message; the bytecode VM builds its instances from these too.
+    pub(crate) fn error_fields(
+        &self,
+        span: Option<Span>,
+        (kind, line, message): (&str, Option<i32>, String),
+    ) -> [(&'static str, LitType); 4] {
+        let file = span.and_then(|span| self.file_at(span));
+        [
+            ("message", LitType::Str(message)),
+            ("kind", LitType::Str(kind.into())),
+            (
+                "line",
+                line.map_or(LitType::Nil, |line| LitType::Int(line.into())),
+            ),
+            (
+                "file",
+                file.map_or(LitType::Nil, |file| LitType::Str(file.into())),
+            ),
+        ]
+    }
+
+    fn try_statement(
+        &mut self,
+        body: Statement,
+        catch: Option<(Token, Statement)>,
+        finally: Option<Statement>,
+    ) -> Result<Completion> {
+        let span = span!(Level::TRACE, "try statement");
+        let _enter = span.enter();
+        let mut result = self.evaluate_statement(body);
+        let thrown = match &result {
+            Ok(Completion::Throw(value, _)) => Some(value.clone()),
+            Err(error) => Some(self.caught(error)),
+            Ok(_) => None,
+        };
+        if let (Some(value), Some((name, handler))) = (thrown, catch) {
+            trace!(name = %name, value = %value, "Caught error");
+            let environment = Environment::new(Some(self.environment.clone()));
+            environment.define(name.lexeme, value);
+            result = self.block_statement(vec![handler], environment);
+        }
+        if let Some(finally) = finally {
+            // Leaving a finally block early overrides how the try block finished.
+            match self.evaluate_statement(finally)? {
+                Completion::Normal(_) => (),
+                jump => return Ok(jump),
+            }
+        }
+        result
+    }
+
+    fn match_statement(&mut self, subject: Expr, arms: Vec<MatchArm>) -> Result<Completion> {
+        let span = span!(Level::TRACE, "match statement");
+        let _enter = span.enter();
+        let value = self.evaluate_expr(subject)?;
+        for arm in arms {
+            if !arm.pattern.matches(&value) {
+                continue;
+            }
+            trace!(pattern = %arm.pattern, value = %value, "Matched");
+            let environment = Environment::new(Some(self.environment.clone()));
+            if let Some(name) = arm.pattern.binding() {
+                environment.define(name.lexeme.clone(), value.clone());
+            }
+            let previous = std::mem::replace(&mut self.environment, environment);
+            let guard = match arm.guard {
+                Some(guard) => self.evaluate_expr(guard).map(Self::is_truthy),
+                None => Ok(true),
+            };
+            let result = match guard {
+                Ok(true) => self.evaluate_statement(arm.body).map(Some),
+                Ok(false) => Ok(None),
+                Err(error) => Err(error),
+            };
+            self.environment = previous;
+            if let Some(completion) = result? {
+                return Ok(completion);
+            }
+        }
+        Ok(Completion::Normal(None))
+    }
+
+    fn return_statement(&mut self, _keyword: Token, value: Option<Expr>) -> Result<Completion> {
         let span = span!(Level::TRACE, "return statement");
         let _enter = span.enter();
+        let value = match value {
+            Some(value) => self.evaluate_expr(value)?,
+            None => LitType::Nil,
+        };
         trace!(value = %value);
-        Err(Report::new(RuntimeError::Return(
-            self.evaluate_expr(value)?,
-        )))
+        Ok(Completion::Return(value))
     }
 
-    fn evaluate_statement(&mut self, stmt: Statement) -> Result<Option<LitType>> {
+    fn evaluate_statement(&mut self, stmt: Statement) -> Result<Completion> {
+        match self.execute_statement(stmt) {
+            Err(error) => match error.downcast_ref::<Thrown>() {
+                Some(Thrown(value, span)) => Ok(Completion::Throw(value.clone(), *span)),
+                None => Err(error),
+            },
+            completion => completion,
+        }
+    }
+
+    fn execute_statement(&mut self, stmt: Statement) -> Result<Completion> {
+        // A block isn't a step of its own; its statements are.
+        if self.debugger.is_some() && !matches!(stmt, Statement::Block { .. }) {
+            self.debug_statement(stmt.span())?;
+        }
         match stmt.clone() {
             Statement::Print {
                 span: _,
@@ -411,32 +1097,25 @@ impl Interpreter {
             } => {
                 let value = self.evaluate_expr(expression)?;
                 trace!(value = %value, "Print lit statement");
-                Self::print_lit(value);
-                Ok(None)
+                self.print_lit(value);
+                Ok(Completion::Normal(None))
             }
             Statement::Expression {
                 span: _,
                 expression,
-            } => {
-                return Ok(Some(self.evaluate_expr(expression)?));
-            }
+            } => Ok(Completion::Normal(Some(self.evaluate_expr(expression)?))),
             Statement::Var {
                 span: _,
                 name: _,
                 expression: _,
             } => {
                 self.var_statement(stmt)?;
-                return Ok(None);
+                Ok(Completion::Normal(None))
             }
             Statement::Block {
                 span: _,
                 statements,
-            } => {
-                return Ok(self.block_statement(
-                    statements,
-                    Environment::new(Some(Box::new(self.environment.clone()))),
-                )?);
-            }
+            } => self.block_statement(statements, Environment::new(Some(self.environment.clone()))),
             Statement::If {
                 span: _,
                 condition,
@@ -444,18 +1123,41 @@ impl Interpreter {
                 else_branch,
             } => {
                 if let Some(els) = else_branch {
-                    return self.if_statement(condition, *then_branch, Some(*els));
+                    self.if_statement(condition, *then_branch, Some(*els))
                 } else {
-                    return self.if_statement(condition, *then_branch, None);
+                    self.if_statement(condition, *then_branch, None)
                 }
             }
             Statement::While {
                 span: _,
                 condition,
                 body,
-            } => {
-                return self.while_statement(condition, *body);
-            }
+                label,
+            } => self.loop_statement(Some(condition), *body, None, label),
+            Statement::For {
+                span: _,
+                initializer,
+                condition,
+                increment,
+                body,
+                label,
+            } => self.for_statement(
+                initializer.map(|init| *init),
+                condition,
+                increment,
+                *body,
+                label,
+            ),
+            Statement::Break {
+                span: _,
+                keyword: _,
+                label,
+            } => Ok(Completion::Break(label.map(|label| label.lexeme))),
+            Statement::Continue {
+                span: _,
+                keyword: _,
+                label,
+            } => Ok(Completion::Continue(label.map(|label| label.lexeme))),
             Statement::Function {
                 span,
                 name,
@@ -463,26 +1165,82 @@ impl Interpreter {
                 body,
             } => {
                 self.function_statement(name, params, body, span)?;
-                Ok(None)
+                Ok(Completion::Normal(None))
             }
             Statement::Return {
                 span: _,
                 keyword,
                 value,
+            } => self.return_statement(keyword, value),
+            Statement::Class {
+                span: _,
+                name,
+                superclass,
+                methods,
+            } => {
+                self.class_statement(name, superclass, methods)?;
+                Ok(Completion::Normal(None))
+            }
+            Statement::Import {
+                span,
+                path,
+                alias,
+                names,
             } => {
-                return Ok(Some(self.return_statement(keyword, value)?));
+                self.import_statement(path, alias, names, span)?;
+                Ok(Completion::Normal(None))
             }
+            Statement::Throw {
+                span,
+                keyword: _,
+                value,
+            } => Ok(Completion::Throw(self.evaluate_expr(value)?, span)),
+            Statement::Try {
+                span: _,
+                body,
+                catch,
+                finally,
+            } => self.try_statement(
+                *body,
+                catch.map(|(name, handler)| (name, *handler)),
+                finally.map(|finally| *finally),
+            ),
+            Statement::Match {
+                span: _,
+                keyword: _,
+                subject,
+                arms,
+            } => self.match_statement(subject, arms),
+        }
+    }
+
+    fn assign_variable(
+        &self,
+        name: Token,
+        value: LitType,
+        span: Span,
+        depth: Option<usize>,
+    ) -> Result<()> {
+        if let Some(distance) = depth {
+            self.environment.assign_at(distance, name, value, span)
+        } else {
+            self.globals.assign(name, value, span)
         }
     }
 
-    fn assign_expr(&mut self, name: Token, value: Expr, span: Span) -> Result<LitType> {
+    fn assign_expr(
+        &mut self,
+        name: Token,
+        value: Expr,
+        span: Span,
+        depth: Option<usize>,
+    ) -> Result<LitType> {
         if let Expr::Literal { span: _, ref value } = value {
             if let Some(val) = value {
                 let span_tracing = span!(Level::TRACE, "assign expression");
                 let _enter = span_tracing.enter();
                 trace!(name = %name.clone(), value = %val.clone(), "assigning");
-                self.environment
-                    .assign(name.clone(), val.clone(), self.get_span(span))?;
+                self.assign_variable(name.clone(), val.clone(), span, depth)?;
                 return Ok(val.clone());
             }
         } else {
@@ -491,8 +1249,7 @@ impl Interpreter {
             trace!(value = %value.clone());
             let val = self.evaluate_expr(value.clone())?;
             trace!(name = %name.clone(), value = %val.clone(), "assigning");
-            self.environment
-                .assign(name.clone(), val.clone(), self.get_span(span))?;
+            self.assign_variable(name.clone(), val.clone(), span, depth)?;
             return Ok(val.clone());
         }
         Err(Report::new(RuntimeError::InvalidAssignmentTarget(
@@ -500,46 +1257,247 @@ impl Interpreter {
         )))
     }
 
-    fn call_expr(
+    fn increment_expr(
         &mut self,
-        callee: Expr,
-        paren: Token,
-        arguments: Vec<Box<Expr>>,
+        name: Token,
+        operator: Token,
+        prefix: bool,
+        span: Span,
+        depth: Option<usize>,
     ) -> Result<LitType> {
+        let old = self.var_expr(Expr::Variable {
+            span,
+            name: name.clone(),
+            depth,
+        })?;
+        let mut step = operator;
+        step.ty = if step.ty == TokenType::PlusPlus {
+            TokenType::Plus
+        } else {
+            TokenType::Minus
+        };
+        let new = self.binary_expr(Expr::Binary {
+            span,
+            left: Box::new(Expr::Literal {
+                span,
+                value: Some(old.clone()),
+            }),
+            operator: step,
+            right: Box::new(Expr::Literal {
+                span,
+                value: Some(LitType::Int(1)),
+            }),
+        })?;
+        self.assign_variable(name, new.clone(), span, depth)?;
+        Ok(if prefix { new } else { old })
+    }
+
+    fn call_expr(&mut self, callee: Expr, arguments: Vec<Expr>, span: Span) -> Result<LitType> {
         let callee = self.evaluate_expr(callee)?;
         let mut args = Vec::new();
-        for arg in arguments.clone() {
-            args.push(self.evaluate_expr(*arg)?);
+        for arg in arguments {
+            args.push(self.evaluate_expr(arg)?);
         }
         if let LitType::Callable(call) = callee.clone() {
             match call {
                 LoxCallable::Function(func) => {
-                    if arguments.len() != func.arity() {
+                    if args.len() != func.arity() {
                         return Err(Report::new(RuntimeError::IncorrectArgumentCount(
                             func.arity(),
-                            arguments.len(),
+                            args.len(),
+                            span,
                         )));
                     }
-                    let span = span!(Level::TRACE, "call expression");
-                    let _enter = span.enter();
+                    let span_trace = span!(Level::TRACE, "call expression");
+                    let _enter = span_trace.enter();
                     trace!(callee = func.as_string(), "Calling function");
-                    return Ok(func.call(self, arguments)?);
+                    self.call_span = span;
+                    return func.call(self, args);
                 }
-                LoxCallable::Clock(clock) => {
-                    if arguments.len() != clock.arity() {
+                LoxCallable::Native(native) => {
+                    if args.len() != native.arity() {
                         return Err(Report::new(RuntimeError::IncorrectArgumentCount(
-                            clock.arity(),
-                            arguments.len(),
+                            native.arity(),
+                            args.len(),
+                            span,
                         )));
                     }
-                    let span = span!(Level::TRACE, "call expression");
-                    let _enter = span.enter();
-                    trace!(callee = clock.as_string(), "Calling function");
-                    return Ok(clock.call(self, arguments)?);
+                    let span_trace = span!(Level::TRACE, "call expression");
+                    let _enter = span_trace.enter();
+                    trace!(callee = native.as_string(), "Calling native function");
+                    return native
+                        .call(self, args)
+                        .map_err(|error| Report::new(RuntimeError::from_native(error, span)));
+                }
+                LoxCallable::Class(class) => {
+                    if args.len() != class.arity() {
+                        return Err(Report::new(RuntimeError::IncorrectArgumentCount(
+                            class.arity(),
+                            args.len(),
+                            span,
+                        )));
+                    }
+                    let span_trace = span!(Level::TRACE, "call expression");
+                    let _enter = span_trace.enter();
+                    trace!(callee = class.as_string(), "Calling class");
+                    self.call_span = span;
+                    return class.call(self, args);
                 }
             }
         }
-        Err(Report::new(RuntimeError::NotCallable(paren)))
+        Err(Report::new(RuntimeError::NotCallable(span)))
+    }
+
+    fn get_expr(&mut self, object: Expr, name: Token, span: Span) -> Result<LitType> {
+        let span_tracing = span!(Level::TRACE, "get expression");
+        let _enter = span_tracing.enter();
+        trace!(name = %name);
+        match self.evaluate_expr(object)? {
+            LitType::Instance(instance) => instance.get(name, span),
+            LitType::Module(module) => module.get(&name.lexeme).ok_or_else(|| {
+                Report::new(RuntimeError::MissingExport(
+                    module.name().into(),
+                    name.lexeme,
+                    span,
+                ))
+            }),
+            _ => Err(Report::new(RuntimeError::OnlyInstancesHaveProperties(span))),
+        }
+    }
+
+    fn set_expr(&mut self, object: Expr, name: Token, value: Expr, span: Span) -> Result<LitType> {
+        let span_tracing = span!(Level::TRACE, "set expression");
+        let _enter = span_tracing.enter();
+        trace!(name = %name);
+        if let LitType::Instance(instance) = self.evaluate_expr(object)? {
+            let value = self.evaluate_expr(value)?;
+            instance.set(name, value.clone());
+            return Ok(value);
+        }
+        Err(Report::new(RuntimeError::OnlyInstancesHaveFields(span)))
+    }
+
+    fn list_expr(&mut self, elements: Vec<Expr>) -> Result<LitType> {
+        let mut items = Vec::with_capacity(elements.len());
+        for element in elements {
+            items.push(self.evaluate_expr(element)?);
+        }
+        Ok(LitType::List(LoxList::new(items)))
+    }
+
+    fn map_expr(&mut self, entries: Vec<(Expr, Expr)>, span: Span) -> Result<LitType> {
+        let map = LoxMap::new();
+        for (key, value) in entries {
+            let key = self.evaluate_expr(key)?;
+            let value = self.evaluate_expr(value)?;
+            Self::check_key(&key, span)?;
+            map.set(key, value);
+        }
+        Ok(LitType::Map(map))
+    }
+
+    fn check_key(key: &LitType, span: Span) -> Result<()> {
+        if key.is_hashable() {
+            Ok(())
+        } else {
+            Err(Report::new(RuntimeError::InvalidKey(
+                stdlib::type_name(key).into(),
+                span,
+            )))
+        }
+    }
+
+    fn list_position(index: &LitType, span: Span) -> Result<i64> {
+        match index {
+            LitType::Int(i) => Ok(*i),
+            other => Err(Report::new(RuntimeError::InvalidIndex(
+                stdlib::type_name(other).into(),
+                span,
+            ))),
+        }
+    }
+
+    fn index_expr(&mut self, object: Expr, index: Expr, span: Span) -> Result<LitType> {
+        let object = self.evaluate_expr(object)?;
+        let index = self.evaluate_expr(index)?;
+        trace!(object = %object, index = %index, "index expression");
+        match object {
+            LitType::List(list) => {
+                let i = Self::list_position(&index, span)?;
+                list.get(i)
+                    .ok_or_else(|| Report::new(RuntimeError::IndexOutOfBounds(i, list.len(), span)))
+            }
+            LitType::Map(map) => {
+                Self::check_key(&index, span)?;
+                map.get(&index)
+                    .ok_or_else(|| Report::new(RuntimeError::MissingKey(index.quoted(), span)))
+            }
+            other => Err(Report::new(RuntimeError::NotIndexable(
+                stdlib::type_name(&other).into(),
+                span,
+            ))),
+        }
+    }
+
+    fn set_index_expr(
+        &mut self,
+        object: Expr,
+        index: Expr,
+        value: Expr,
+        span: Span,
+    ) -> Result<LitType> {
+        let object = self.evaluate_expr(object)?;
+        let index = self.evaluate_expr(index)?;
+        let value = self.evaluate_expr(value)?;
+        trace!(object = %object, index = %index, value = %value, "set index expression");
+        match object {
+            LitType::List(list) => {
+                let i = Self::list_position(&index, span)?;
+                list.set(i, value)
+                    .ok_or_else(|| Report::new(RuntimeError::IndexOutOfBounds(i, list.len(), span)))
+            }
+            LitType::Map(map) => {
+                Self::check_key(&index, span)?;
+                map.set(index, value.clone());
+                Ok(value)
+            }
+            other => Err(Report::new(RuntimeError::NotIndexable(
+                stdlib::type_name(&other).into(),
+                span,
+            ))),
+        }
+    }
+
+    fn super_expr(
+        &mut self,
+        keyword: Token,
+        method: Token,
+        span: Span,
+        depth: Option<usize>,
+    ) -> Result<LitType> {
+        let span_tracing = span!(Level::TRACE, "super expression");
+        let _enter = span_tracing.enter();
+        trace!(method = %method);
+        let distance = depth.unwrap_or_default();
+        let mut this_token = keyword.clone();
+        this_token.lexeme = "this".into();
+        let superclass = self.environment.get_at(distance, keyword, span)?;
+        let instance = self
+            .environment
+            .get_at(distance.saturating_sub(1), this_token, span)?;
+        if let (Some(LitType::Callable(LoxCallable::Class(class))), Some(instance)) =
+            (superclass, instance)
+        {
+            if let Some(function) = class.find_method(&method.lexeme) {
+                return Ok(LitType::Callable(LoxCallable::Function(
+                    function.bind(instance),
+                )));
+            }
+        }
+        Err(Report::new(RuntimeError::UndefinedProperty(
+            method.lexeme,
+            span,
+        )))
     }
 
     pub fn evaluate_expr(&mut self, expr: Expr) -> Result<LitType> {
@@ -560,22 +1518,101 @@ impl Interpreter {
                 right: _,
             } => Ok(self.unary_expr(expr)?),
             Expr::Literal { span: _, value: _ } => Ok(self.literal_expr(expr)?),
-            Expr::Variable { span: _, name: _ } => Ok(self.var_expr(expr)?),
-            Expr::Assign { span, name, value } => {
-                Ok(self.assign_expr(name.clone(), *value.clone(), span.clone())?)
-            }
+            Expr::Variable {
+                span: _,
+                name: _,
+                depth: _,
+            } => Ok(self.var_expr(expr)?),
+            Expr::Assign {
+                span,
+                name,
+                value,
+                depth,
+            } => Ok(self.assign_expr(name.clone(), *value.clone(), *span, *depth)?),
             Expr::Logcial {
                 span: _,
                 left,
                 operator,
                 right,
             } => Ok(self.logical_expr(*left.clone(), operator.ty.clone(), *right.clone())?),
-            Expr::Call {
+            Expr::Conditional {
                 span: _,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if Self::is_truthy(self.evaluate_expr(*condition.clone())?) {
+                    self.evaluate_expr(*then_branch.clone())
+                } else {
+                    self.evaluate_expr(*else_branch.clone())
+                }
+            }
+            Expr::Increment {
+                span,
+                name,
+                operator,
+                prefix,
+                depth,
+            } => self.increment_expr(name.clone(), operator.clone(), *prefix, *span, *depth),
+            Expr::Call {
+                span,
                 callee,
-                paren,
+                paren: _,
                 arguments,
-            } => Ok(self.call_expr(*callee.clone(), paren.clone(), arguments.clone())?),
+            } => Ok(self.call_expr(*callee.clone(), arguments.clone(), *span)?),
+            Expr::Get { span, object, name } => {
+                Ok(self.get_expr(*object.clone(), name.clone(), *span)?)
+            }
+            Expr::Set {
+                span,
+                object,
+                name,
+                value,
+            } => Ok(self.set_expr(*object.clone(), name.clone(), *value.clone(), *span)?),
+            Expr::List { span: _, elements } => Ok(self.list_expr(elements.clone())?),
+            Expr::Stringify {
+                span: _,
+                expression,
+            } => Ok(LitType::Str(
+                self.evaluate_expr(*expression.clone())?.to_string(),
+            )),
+            Expr::Map { span, entries } => Ok(self.map_expr(entries.clone(), *span)?),
+            Expr::Index {
+                span,
+                object,
+                bracket: _,
+                index,
+            } => Ok(self.index_expr(*object.clone(), *index.clone(), *span)?),
+            Expr::SetIndex {
+                span,
+                object,
+                bracket: _,
+                index,
+                value,
+            } => Ok(self.set_index_expr(*object.clone(), *index.clone(), *value.clone(), *span)?),
+            Expr::This {
+                span,
+                keyword,
+                depth,
+            } => Ok(self.var_expr(Expr::Variable {
+                span: *span,
+                name: keyword.clone(),
+                depth: *depth,
+            })?),
+            Expr::Super {
+                span,
+                keyword,
+                method,
+                depth,
+            } => Ok(self.super_expr(keyword.clone(), method.clone(), *span, *depth)?),
+            Expr::Lambda {
+                span: _,
+                declaration,
+            } => Ok(LitType::Callable(LoxCallable::Function(
+                LoxFunction::new("lambda".into(), Some(*declaration.clone()))
+                    .set_closure(self.environment.clone())
+                    .set_globals(self.globals.clone()),
+            ))),
         }
     }
 }
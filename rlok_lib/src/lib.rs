@@ -1,11 +1,42 @@
+mod ast_printer;
+mod chunk;
+mod compiler;
+mod dap;
+mod debug_console;
+pub mod debugger;
+mod diagnostic;
 mod environment;
 mod error_handler;
 mod expression;
+mod framing;
 pub mod interpreter;
 mod lit;
 mod lox_callable;
+mod lox_class;
+mod lox_list;
+mod lox_map;
+mod lox_module;
+mod lsp;
+mod numeric;
 mod parser;
+mod pattern;
+mod repl;
+mod resolver;
 mod scanner;
+mod source_map;
 mod span;
 mod statement;
+mod stdlib;
 mod tokens;
+mod value;
+mod vm;
+
+pub use error_handler::RuntimeError;
+pub use interpreter::Interpreter;
+pub use lit::LitType;
+pub use lox_callable::{Callable, LoxCallable, LoxFunction, NativeFunction};
+pub use lox_class::{LoxClass, LoxInstance};
+pub use lox_list::LoxList;
+pub use lox_map::LoxMap;
+pub use lox_module::LoxModule;
+pub use numeric::Number;
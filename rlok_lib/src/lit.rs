@@ -1,28 +1,114 @@
 use super::lox_callable::LoxCallable;
+use super::lox_class::LoxInstance;
+use super::lox_list::LoxList;
+use super::lox_map::LoxMap;
+use super::lox_module::LoxModule;
+use super::numeric::Number;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
 
 #[derive(Debug, Clone)]
 pub enum LitType {
-    Float(f32),
+    Int(i64),
+    Float(f64),
     Str(String),
     Bool(bool),
     Callable(LoxCallable),
+    Instance(LoxInstance),
+    List(LoxList),
+    Map(LoxMap),
+    Module(LoxModule),
     Nil,
 }
 
+impl LitType {
+    pub fn number(&self) -> Option<Number> {
+        match self {
+            LitType::Int(i) => Some(Number::Int(*i)),
+            LitType::Float(f) => Some(Number::Float(*f)),
+            _ => None,
+        }
+    }
+
+    /// Whether the value can be used as a map key.
+    pub fn is_hashable(&self) -> bool {
+        match self {
+            LitType::Float(f) => !f.is_nan(),
+            LitType::Int(_) | LitType::Str(_) | LitType::Bool(_) | LitType::Nil => true,
+            _ => false,
+        }
+    }
+
+    /// Like `Display`, but quotes strings so they read correctly inside collections.
+    pub fn quoted(&self) -> String {
+        match self {
+            LitType::Str(s) => format!("{:?}", s),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl PartialEq for LitType {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LitType::Str(l), LitType::Str(r)) => l == r,
+            (LitType::Bool(l), LitType::Bool(r)) => l == r,
+            (LitType::Nil, LitType::Nil) => true,
+            (LitType::List(l), LitType::List(r)) => l.ptr_eq(r),
+            (LitType::Map(l), LitType::Map(r)) => l.ptr_eq(r),
+            (LitType::Instance(l), LitType::Instance(r)) => l.ptr_eq(r),
+            (LitType::Module(l), LitType::Module(r)) => l.ptr_eq(r),
+            (l, r) => match (l.number(), r.number()) {
+                (Some(l), Some(r)) => l.compare(r) == Some(Ordering::Equal),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl Eq for LitType {}
+
+impl Hash for LitType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            // Whole floats hash like the equal int so that `m[1]` and `m[1.0]` agree.
+            LitType::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => {
+                (*f as i64).hash(state)
+            }
+            LitType::Float(f) => f.to_bits().hash(state),
+            LitType::Int(i) => i.hash(state),
+            LitType::Str(s) => s.hash(state),
+            LitType::Bool(b) => b.hash(state),
+            other => std::mem::discriminant(other).hash(state),
+        }
+    }
+}
+
+impl From<Number> for LitType {
+    fn from(number: Number) -> Self {
+        match number {
+            Number::Int(i) => LitType::Int(i),
+            Number::Float(f) => LitType::Float(f),
+        }
+    }
+}
+
 impl std::fmt::Display for LitType {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
+            LitType::Int(int) => write!(f, "{}", int),
             LitType::Float(flt) => write!(f, "{}", flt),
             LitType::Str(str) => write!(f, "{}", str),
             LitType::Bool(bl) => write!(f, "{}", bl),
             LitType::Callable(call) => match call {
-                LoxCallable::Function(func) => {
-                    write!(f, "{:?}", func)
-                }
-                LoxCallable::Clock(clock) => {
-                    write!(f, "{:?}", clock)
-                }
+                LoxCallable::Function(func) => write!(f, "{}", func),
+                LoxCallable::Native(native) => write!(f, "{}", native),
+                LoxCallable::Class(class) => write!(f, "{}", class),
             },
+            LitType::Instance(instance) => write!(f, "{}", instance),
+            LitType::List(list) => write!(f, "{}", list),
+            LitType::Map(map) => write!(f, "{}", map),
+            LitType::Module(module) => write!(f, "{}", module),
             LitType::Nil => write!(f, "nil"),
         }
     }
@@ -1,17 +1,20 @@
 use super::environment::Environment;
-use super::error_handler::RuntimeError;
-use super::expression::Expr;
-use super::interpreter::Interpreter;
+use super::interpreter::{Completion, Interpreter, Thrown};
 use super::lit::LitType;
+use super::lox_class::LoxClass;
 use super::statement::Statement;
+use super::value::Value;
 use color_eyre::eyre::{Report, Result};
-use std::time::SystemTime;
+use std::sync::Arc;
 use tracing::trace;
 
+pub type NativeFn = dyn Fn(&mut Interpreter, Vec<LitType>) -> Result<LitType> + Send + Sync;
+
 #[derive(Debug, Clone)]
 pub enum LoxCallable {
     Function(LoxFunction),
-    Clock(Clock),
+    Native(NativeFunction),
+    Class(LoxClass),
 }
 
 impl std::fmt::Display for LoxCallable {
@@ -20,8 +23,11 @@ impl std::fmt::Display for LoxCallable {
             LoxCallable::Function(func) => {
                 write!(f, "{:?}", func)
             }
-            LoxCallable::Clock(clock) => {
-                write!(f, "{:?}", clock)
+            LoxCallable::Native(native) => {
+                write!(f, "{:?}", native)
+            }
+            LoxCallable::Class(class) => {
+                write!(f, "{:?}", class)
             }
         }
     }
@@ -29,7 +35,7 @@ impl std::fmt::Display for LoxCallable {
 pub trait Callable: std::fmt::Debug + std::fmt::Display {
     fn new(callee: String, declaration: Option<Statement>) -> Self;
     fn callee(&self) -> String;
-    fn call(&self, inter: &mut Interpreter, arguments: Vec<Box<Expr>>) -> Result<LitType>;
+    fn call(&self, inter: &mut Interpreter, arguments: Vec<LitType>) -> Result<LitType>;
     fn arity(&self) -> usize;
     fn as_string(&self) -> String;
 }
@@ -38,11 +44,18 @@ pub trait Callable: std::fmt::Debug + std::fmt::Display {
 pub struct LoxFunction {
     declaration: Box<Option<Statement>>,
     callee: String,
+    closure: Environment,
+    globals: Environment,
+    is_initializer: bool,
+    /// The bytecode VM's object this stands for, when it was handed over from there.
+    compiled: Option<Value>,
 }
 
-#[derive(Debug, Clone)]
-pub struct Clock {
+#[derive(Clone)]
+pub struct NativeFunction {
     callee: String,
+    arity: usize,
+    function: Arc<NativeFn>,
 }
 
 impl std::fmt::Display for LoxFunction {
@@ -51,43 +64,110 @@ impl std::fmt::Display for LoxFunction {
     }
 }
 
-impl std::fmt::Display for Clock {
+impl std::fmt::Display for NativeFunction {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "<fun {}>", self.callee)
     }
 }
 
-impl Callable for Clock {
+impl std::fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("callee", &self.callee)
+            .field("arity", &self.arity)
+            .finish()
+    }
+}
+
+impl NativeFunction {
+    pub fn set_native(mut self, arity: usize, function: Arc<NativeFn>) -> NativeFunction {
+        self.arity = arity;
+        self.function = function;
+        self
+    }
+}
+
+impl Callable for NativeFunction {
     fn new(callee: String, _declaration: Option<Statement>) -> Self {
-        trace!(callee, "Creating function");
-        Clock { callee }
+        trace!(callee, "Creating native function");
+        NativeFunction {
+            callee,
+            arity: 0,
+            function: Arc::new(|_, _| Ok(LitType::Nil)),
+        }
     }
 
     fn callee(&self) -> String {
         self.callee.clone()
     }
 
-    fn call(&self, _inter: &mut Interpreter, _arguments: Vec<Box<Expr>>) -> Result<LitType> {
-        trace!("Callling clock function");
-        match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-            Ok(time) => Ok(LitType::Float(time.as_secs_f32())),
-            Err(_) => Err(Report::new(RuntimeError::NativeFunctionError)),
-        }
+    fn call(&self, inter: &mut Interpreter, arguments: Vec<LitType>) -> Result<LitType> {
+        trace!(callee = self.callee, "Calling native function");
+        (self.function)(inter, arguments)
     }
+
     fn arity(&self) -> usize {
-        0
+        self.arity
     }
+
     fn as_string(&self) -> String {
         "<native fn>".into()
     }
 }
 
+impl LoxFunction {
+    pub fn bind(&self, instance: LitType) -> LoxFunction {
+        trace!(callee = self.callee, instance = %instance, "Binding method");
+        let environment = Environment::new(Some(self.closure.clone()));
+        environment.define("this".into(), instance);
+        self.clone().set_closure(environment)
+    }
+
+    pub fn set_closure(mut self, closure: Environment) -> LoxFunction {
+        self.closure = closure;
+        self
+    }
+
+    /// The globals of the module the function was declared in.
+    pub fn set_globals(mut self, globals: Environment) -> LoxFunction {
+        self.globals = globals;
+        self
+    }
+
+    pub fn set_initializer(mut self, is_initializer: bool) -> LoxFunction {
+        self.is_initializer = is_initializer;
+        self
+    }
+
+    pub(crate) fn set_compiled(mut self, compiled: Value) -> LoxFunction {
+        self.compiled = Some(compiled);
+        self
+    }
+
+    pub(crate) fn compiled(&self) -> Option<&Value> {
+        self.compiled.as_ref()
+    }
+
+    fn initializer_value(&self, value: LitType) -> LitType {
+        if self.is_initializer {
+            if let Some(this) = self.closure.lookup("this") {
+                return this;
+            }
+        }
+        value
+    }
+}
+
 impl Callable for LoxFunction {
     fn new(callee: String, declaration: Option<Statement>) -> Self {
         trace!(callee, "Creating function");
         LoxFunction {
             callee,
             declaration: Box::new(declaration),
+            closure: Environment::new(None),
+            globals: Environment::new(None),
+            is_initializer: false,
+            compiled: None,
         }
     }
 
@@ -95,8 +175,8 @@ impl Callable for LoxFunction {
         self.callee.clone()
     }
 
-    fn call(&self, inter: &mut Interpreter, arguments: Vec<Box<Expr>>) -> Result<LitType> {
-        let mut environment = Environment::new(Some(Box::new(inter.globals.clone())));
+    fn call(&self, inter: &mut Interpreter, arguments: Vec<LitType>) -> Result<LitType> {
+        let environment = Environment::new(Some(self.closure.clone()));
         if let Some(declaration) = *self.declaration.clone() {
             if let Statement::Function {
                 span: _,
@@ -108,55 +188,50 @@ impl Callable for LoxFunction {
                 trace!(name = %name, "Called function");
                 for (index, param) in params.iter().enumerate() {
                     trace!(param = %param, index, "parameter");
-                    environment.define(
-                        param.lexeme.clone(),
-                        inter.evaluate_expr(*arguments[index].clone())?,
-                    );
+                    environment.define(param.lexeme.clone(), arguments[index].clone());
                 }
-                match inter.block_statement(body, environment) {
-                    Ok(_) => return Ok(LitType::Nil),
-                    Err(err) => {
-                        let error: Report = err;
-                        if let Some(err) = error.root_cause().downcast_ref::<RuntimeError>() {
-                            if let RuntimeError::Return(val) = err {
-                                trace!(val = %val.clone(), "Returning");
-                                return Ok(val.clone());
-                            }
-                        }
-                        return Err(error);
+                inter.enter_call(&name.lexeme)?;
+                let globals = std::mem::replace(&mut inter.globals, self.globals.clone());
+                let result = inter.block_statement(body, environment);
+                inter.leave_call();
+                inter.globals = globals;
+                match result? {
+                    Completion::Return(val) => {
+                        trace!(val = %val.clone(), "Returning");
+                        return Ok(self.initializer_value(val));
                     }
+                    Completion::Throw(value, span) => {
+                        return Err(Report::new(Thrown(value, span)));
+                    }
+                    _ => return Ok(self.initializer_value(LitType::Nil)),
                 }
             }
         }
-        return Ok(LitType::Nil);
+        Ok(LitType::Nil)
     }
 
     fn arity(&self) -> usize {
-        if let Some(dec) = *self.declaration.clone() {
-            if let Statement::Function {
-                span: _,
-                name: _,
-                params,
-                body: _,
-            } = dec
-            {
-                return params.len() as usize;
-            }
+        if let Some(Statement::Function {
+            span: _,
+            name: _,
+            params,
+            body: _,
+        }) = *self.declaration.clone()
+        {
+            return params.len();
         }
         0
     }
 
     fn as_string(&self) -> String {
-        if let Some(dec) = *self.declaration.clone() {
-            if let Statement::Function {
-                span: _,
-                name,
-                params: _,
-                body: _,
-            } = dec
-            {
-                return format!("<fn {}>", name.lexeme);
-            }
+        if let Some(Statement::Function {
+            span: _,
+            name,
+            params: _,
+            body: _,
+        }) = *self.declaration.clone()
+        {
+            return format!("<fn {}>", name.lexeme);
         }
         "<fn>".into()
     }
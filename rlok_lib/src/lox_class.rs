@@ -0,0 +1,190 @@
+use super::error_handler::RuntimeError;
+use super::interpreter::Interpreter;
+use super::lit::LitType;
+use super::lox_callable::{Callable, LoxCallable, LoxFunction};
+use super::span::Span;
+use super::statement::Statement;
+use super::tokens::Token;
+use super::value::Value;
+use color_eyre::eyre::{Report, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::trace;
+
+#[derive(Debug, Clone)]
+pub struct LoxClass {
+    callee: String,
+    superclass: Option<Box<LoxClass>>,
+    methods: HashMap<String, LoxFunction>,
+    /// The bytecode VM's object this stands for, when it was handed over from there.
+    compiled: Option<Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoxInstance {
+    class: LoxClass,
+    fields: Arc<Mutex<HashMap<String, LitType>>>,
+    /// The bytecode VM's object this stands for, when it was handed over from there.
+    compiled: Option<Value>,
+}
+
+impl std::fmt::Display for LoxClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<class {}>", self.callee)
+    }
+}
+
+impl std::fmt::Display for LoxInstance {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<instance {}>", self.class.callee)
+    }
+}
+
+impl LoxClass {
+    pub fn set_superclass(mut self, superclass: Option<LoxClass>) -> LoxClass {
+        self.superclass = superclass.map(Box::new);
+        self
+    }
+
+    pub fn set_methods(mut self, methods: HashMap<String, LoxFunction>) -> LoxClass {
+        self.methods = methods;
+        self
+    }
+
+    pub(crate) fn set_compiled(mut self, compiled: Value) -> LoxClass {
+        self.compiled = Some(compiled);
+        self
+    }
+
+    pub(crate) fn compiled(&self) -> Option<&Value> {
+        self.compiled.as_ref()
+    }
+
+    pub fn find_method(&self, name: &str) -> Option<LoxFunction> {
+        if let Some(method) = self.methods.get(name) {
+            return Some(method.clone());
+        }
+        if let Some(ref superclass) = self.superclass {
+            return superclass.find_method(name);
+        }
+        None
+    }
+}
+
+impl Callable for LoxClass {
+    fn new(callee: String, _declaration: Option<Statement>) -> Self {
+        trace!(callee, "Creating class");
+        LoxClass {
+            callee,
+            superclass: None,
+            methods: HashMap::new(),
+            compiled: None,
+        }
+    }
+
+    fn callee(&self) -> String {
+        self.callee.clone()
+    }
+
+    fn call(&self, inter: &mut Interpreter, arguments: Vec<LitType>) -> Result<LitType> {
+        trace!(class = %self, "Instantiating class");
+        let instance = LitType::Instance(LoxInstance::new(self.clone()));
+        if let Some(initializer) = self.find_method("init") {
+            initializer.bind(instance.clone()).call(inter, arguments)?;
+        }
+        Ok(instance)
+    }
+
+    fn arity(&self) -> usize {
+        if let Some(initializer) = self.find_method("init") {
+            return initializer.arity();
+        }
+        0
+    }
+
+    fn as_string(&self) -> String {
+        format!("<class {}>", self.callee)
+    }
+}
+
+impl LoxInstance {
+    pub fn new(class: LoxClass) -> Self {
+        LoxInstance {
+            class,
+            fields: Arc::new(Mutex::new(HashMap::new())),
+            compiled: None,
+        }
+    }
+
+    pub(crate) fn set_compiled(mut self, compiled: Value) -> LoxInstance {
+        self.compiled = Some(compiled);
+        self
+    }
+
+    pub(crate) fn compiled(&self) -> Option<&Value> {
+        self.compiled.as_ref()
+    }
+
+    pub fn get(&self, name: Token, span: Span) -> Result<LitType> {
+        if let Some(value) = self.fields.lock().unwrap().get(&name.lexeme) {
+            trace!(name = %name, value = %value, "Instance Get");
+            return Ok(value.clone());
+        }
+        if let Some(method) = self.class.find_method(&name.lexeme) {
+            trace!(name = %name, "Instance Get Method");
+            return Ok(LitType::Callable(LoxCallable::Function(
+                method.bind(LitType::Instance(self.clone())),
+            )));
+        }
+        Err(Report::new(RuntimeError::UndefinedProperty(
+            name.lexeme,
+            span,
+        )))
+    }
+
+    pub fn field(&self, name: &str) -> Option<LitType> {
+        if let Some(Value::Instance(instance)) = &self.compiled {
+            let value = instance.lock().unwrap().fields.get(name).cloned();
+            return value.map(|value| value.to_lit());
+        }
+        self.fields.lock().unwrap().get(name).cloned()
+    }
+
+    /// Every field, sorted by name.
+    pub fn fields(&self) -> Vec<(String, LitType)> {
+        let mut fields: Vec<(String, LitType)> = match &self.compiled {
+            Some(Value::Instance(instance)) => {
+                let compiled = instance.lock().unwrap().fields.clone();
+                compiled
+                    .into_iter()
+                    .map(|(name, value)| (name, value.to_lit()))
+                    .collect()
+            }
+            _ => self
+                .fields
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        };
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        fields
+    }
+
+    pub fn set_field(&self, name: &str, value: LitType) {
+        self.fields.lock().unwrap().insert(name.into(), value);
+    }
+
+    pub fn set(&self, name: Token, value: LitType) {
+        trace!(name = %name, value = %value, "Instance Set");
+        self.fields.lock().unwrap().insert(name.lexeme, value);
+    }
+
+    pub fn ptr_eq(&self, other: &LoxInstance) -> bool {
+        match (&self.compiled, &other.compiled) {
+            (Some(l), Some(r)) => l.ptr_eq(r),
+            _ => Arc::ptr_eq(&self.fields, &other.fields),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use super::lit::LitType;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone)]
+pub struct LoxList {
+    items: Arc<Mutex<Vec<LitType>>>,
+}
+
+impl std::fmt::Display for LoxList {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // A list that (indirectly) contains itself is already locked further up.
+        let Ok(items) = self.items.try_lock() else {
+            return write!(f, "[...]");
+        };
+        write!(f, "[")?;
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", item.quoted())?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl LoxList {
+    pub fn new(items: Vec<LitType>) -> Self {
+        LoxList {
+            items: Arc::new(Mutex::new(items)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn items(&self) -> Vec<LitType> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn ptr_eq(&self, other: &LoxList) -> bool {
+        Arc::ptr_eq(&self.items, &other.items)
+    }
+
+    fn position(&self, index: i64, len: usize) -> Option<usize> {
+        usize::try_from(index).ok().filter(|i| *i < len)
+    }
+
+    pub fn get(&self, index: i64) -> Option<LitType> {
+        let items = self.items.lock().unwrap();
+        self.position(index, items.len()).map(|i| items[i].clone())
+    }
+
+    pub fn set(&self, index: i64, value: LitType) -> Option<LitType> {
+        let mut items = self.items.lock().unwrap();
+        let i = self.position(index, items.len())?;
+        items[i] = value.clone();
+        Some(value)
+    }
+
+    pub fn push(&self, value: LitType) {
+        self.items.lock().unwrap().push(value);
+    }
+
+    pub fn pop(&self) -> Option<LitType> {
+        self.items.lock().unwrap().pop()
+    }
+
+    pub fn insert(&self, index: i64, value: LitType) -> Option<()> {
+        let mut items = self.items.lock().unwrap();
+        let i = self.position(index, items.len() + 1)?;
+        items.insert(i, value);
+        Some(())
+    }
+
+    pub fn remove(&self, index: i64) -> Option<LitType> {
+        let mut items = self.items.lock().unwrap();
+        let i = self.position(index, items.len())?;
+        Some(items.remove(i))
+    }
+}
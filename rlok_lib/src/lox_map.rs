@@ -0,0 +1,97 @@
+use super::lit::LitType;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Default)]
+struct Entries {
+    index: HashMap<LitType, usize>,
+    entries: Vec<(LitType, LitType)>,
+}
+
+/// A map that remembers insertion order, so `keys()` and printing are stable.
+#[derive(Debug, Clone, Default)]
+pub struct LoxMap {
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl std::fmt::Display for LoxMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let Ok(entries) = self.entries.try_lock() else {
+            return write!(f, "{{...}}");
+        };
+        write!(f, "{{")?;
+        for (i, (key, value)) in entries.entries.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", key.quoted(), value.quoted())?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl LoxMap {
+    pub fn new() -> Self {
+        LoxMap::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn ptr_eq(&self, other: &LoxMap) -> bool {
+        Arc::ptr_eq(&self.entries, &other.entries)
+    }
+
+    pub fn get(&self, key: &LitType) -> Option<LitType> {
+        let entries = self.entries.lock().unwrap();
+        let i = *entries.index.get(key)?;
+        Some(entries.entries[i].1.clone())
+    }
+
+    pub fn has(&self, key: &LitType) -> bool {
+        self.entries.lock().unwrap().index.contains_key(key)
+    }
+
+    /// Callers check `LitType::is_hashable` on the key first.
+    pub fn set(&self, key: LitType, value: LitType) {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(&i) = entries.index.get(&key) {
+            entries.entries[i].1 = value;
+        } else {
+            let i = entries.entries.len();
+            entries.index.insert(key.clone(), i);
+            entries.entries.push((key, value));
+        }
+    }
+
+    pub fn delete(&self, key: &LitType) -> Option<LitType> {
+        let mut entries = self.entries.lock().unwrap();
+        let i = entries.index.remove(key)?;
+        let (_, value) = entries.entries.remove(i);
+        for position in entries.index.values_mut() {
+            if *position > i {
+                *position -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn keys(&self) -> Vec<LitType> {
+        let entries = self.entries.lock().unwrap();
+        entries.entries.iter().map(|(key, _)| key.clone()).collect()
+    }
+
+    pub fn values(&self) -> Vec<LitType> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .entries
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,56 @@
+use super::environment::Environment;
+use super::lit::LitType;
+use super::value::Value;
+
+#[derive(Debug, Clone)]
+pub struct LoxModule {
+    name: String,
+    globals: Environment,
+    /// The bytecode VM's object this stands for, when it was handed over from there.
+    compiled: Option<Value>,
+}
+
+impl std::fmt::Display for LoxModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
+
+impl LoxModule {
+    pub fn new(name: String, globals: Environment) -> Self {
+        LoxModule {
+            name,
+            globals,
+            compiled: None,
+        }
+    }
+
+    pub(crate) fn set_compiled(mut self, compiled: Value) -> LoxModule {
+        self.compiled = Some(compiled);
+        self
+    }
+
+    pub(crate) fn compiled(&self) -> Option<&Value> {
+        self.compiled.as_ref()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn globals(&self) -> Environment {
+        self.globals.clone()
+    }
+
+    /// Looks up one of the module's own globals; builtins aren't exported.
+    pub fn get(&self, name: &str) -> Option<LitType> {
+        self.globals.lookup_local(name)
+    }
+
+    pub fn ptr_eq(&self, other: &LoxModule) -> bool {
+        match (&self.compiled, &other.compiled) {
+            (Some(l), Some(r)) => l.ptr_eq(r),
+            _ => self.globals.ptr_eq(&other.globals),
+        }
+    }
+}
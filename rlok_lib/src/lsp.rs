@@ -0,0 +1,919 @@
+use super::diagnostic::Diagnostic;
+use super::expression::Expr;
+use super::framing;
+use super::interpreter::Interpreter;
+use super::parser::Parser;
+use super::resolver::Resolver;
+use super::scanner::Scanner;
+use super::span::Span;
+use super::statement::Statement;
+use super::tokens::{Token, TokenType};
+use color_eyre::eyre::{Report, Result};
+use serde_json::{json, Value as Json};
+use std::collections::HashMap;
+use std::iter;
+
+const TOKEN_TYPES: [&str; 12] = [
+    "keyword",
+    "string",
+    "number",
+    "operator",
+    "comment",
+    "variable",
+    "parameter",
+    "function",
+    "method",
+    "class",
+    "property",
+    "namespace",
+];
+const DECLARATION: usize = 1;
+const DEFAULT_LIBRARY: usize = 2;
+
+const METHOD_NOT_FOUND: i64 = -32601;
+
+const SYMBOL_CLASS: i64 = 5;
+const SYMBOL_METHOD: i64 = 6;
+const SYMBOL_FUNCTION: i64 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Variable,
+    Parameter,
+    Function,
+    Method,
+    Class,
+    Module,
+}
+
+impl Kind {
+    fn token_type(self) -> &'static str {
+        match self {
+            Kind::Variable => "variable",
+            Kind::Parameter => "parameter",
+            Kind::Function => "function",
+            Kind::Method => "method",
+            Kind::Class => "class",
+            Kind::Module => "namespace",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Definition {
+    kind: Kind,
+    /// The name where it's declared.
+    span: Span,
+    /// What hovering over the name shows, like `fun area(width, height)`.
+    detail: String,
+}
+
+/// Where each name in a document is declared and used, found by walking the
+/// syntax tree with the same scopes the resolver uses.
+#[derive(Debug, Default)]
+struct Index {
+    definitions: Vec<Definition>,
+    /// Every variable, function and class name, declarations included, with
+    /// its definition when it has one. Builtins don't.
+    names: Vec<(Span, Option<usize>)>,
+    /// Field and method names after a `.`, which are only known at runtime.
+    properties: Vec<Span>,
+    scopes: Vec<HashMap<String, usize>>,
+    /// Definitions by the offset of their name, so declaring one twice is harmless.
+    declared: HashMap<usize, usize>,
+}
+
+impl Index {
+    fn build(ast: &[Statement]) -> Self {
+        let mut index = Index {
+            scopes: vec![HashMap::new()],
+            ..Default::default()
+        };
+        // Globals can be used above their declaration, such as by a function
+        // that calls another declared below it.
+        for stmt in ast {
+            index.declaration(stmt);
+        }
+        for stmt in ast {
+            index.statement(stmt);
+        }
+        index
+    }
+
+    /// The name at `offset`, counting the position just after it.
+    fn at(&self, offset: usize) -> Option<(Span, Option<usize>)> {
+        self.names
+            .iter()
+            .find(|(span, _)| span.start() <= offset && offset <= span.end())
+            .copied()
+    }
+
+    fn define(&mut self, name: &Token, kind: Kind, detail: String) -> usize {
+        if let Some(&id) = self.declared.get(&name.offset) {
+            return id;
+        }
+        self.definitions.push(Definition {
+            kind,
+            span: name.into(),
+            detail,
+        });
+        let id = self.definitions.len() - 1;
+        self.declared.insert(name.offset, id);
+        self.names.push((name.into(), Some(id)));
+        id
+    }
+
+    fn declare(&mut self, name: &Token, kind: Kind, detail: String) {
+        let id = self.define(name, kind, detail);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), id);
+        }
+    }
+
+    fn lookup(&mut self, name: &Token) {
+        let definition = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.lexeme).copied());
+        self.names.push((name.into(), definition));
+    }
+
+    fn signature(name: &Token, params: &[Token]) -> String {
+        let params: Vec<&str> = params.iter().map(|param| param.lexeme.as_str()).collect();
+        format!("{}({})", name.lexeme, params.join(", "))
+    }
+
+    /// Declares the names `stmt` binds in the current scope.
+    fn declaration(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Var { name, .. } => {
+                self.declare(name, Kind::Variable, format!("var {}", name.lexeme))
+            }
+            Statement::Function { name, params, .. } => {
+                let detail = format!("fun {}", Self::signature(name, params));
+                self.declare(name, Kind::Function, detail);
+            }
+            Statement::Class {
+                name, superclass, ..
+            } => {
+                let detail = match superclass {
+                    Some(Expr::Variable {
+                        name: superclass, ..
+                    }) => format!("class {} < {}", name.lexeme, superclass.lexeme),
+                    _ => format!("class {}", name.lexeme),
+                };
+                self.declare(name, Kind::Class, detail);
+            }
+            Statement::Import {
+                path, alias, names, ..
+            } => {
+                if let Some(alias) = alias {
+                    let detail = format!("import {} as {}", path.lexeme, alias.lexeme);
+                    self.declare(alias, Kind::Module, detail);
+                }
+                for name in names {
+                    let detail = format!("from {} import {}", path.lexeme, name.lexeme);
+                    self.declare(name, Kind::Variable, detail);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for stmt in statements {
+            self.statement(stmt);
+        }
+    }
+
+    fn scoped(&mut self, walk: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashMap::new());
+        walk(self);
+        self.scopes.pop();
+    }
+
+    fn function(&mut self, params: &[Token], body: &[Statement]) {
+        self.scoped(|index| {
+            for param in params {
+                let detail = format!("(parameter) {}", param.lexeme);
+                index.declare(param, Kind::Parameter, detail);
+            }
+            index.statements(body);
+        });
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Expression { expression, .. } | Statement::Print { expression, .. } => {
+                self.expr(expression)
+            }
+            Statement::Var { expression, .. } => {
+                if let Some(expression) = expression {
+                    self.expr(expression);
+                }
+                self.declaration(stmt);
+            }
+            Statement::Block { statements, .. } => {
+                self.scoped(|index| index.statements(statements))
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expr(condition);
+                self.statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.expr(condition);
+                self.statement(body);
+            }
+            Statement::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                ..
+            } => self.scoped(|index| {
+                if let Some(initializer) = initializer {
+                    index.statement(initializer);
+                }
+                if let Some(condition) = condition {
+                    index.expr(condition);
+                }
+                if let Some(increment) = increment {
+                    index.expr(increment);
+                }
+                index.statement(body);
+            }),
+            Statement::Break { .. } | Statement::Continue { .. } => (),
+            Statement::Function { params, body, .. } => {
+                self.declaration(stmt);
+                self.function(params, body);
+            }
+            Statement::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Statement::Class {
+                name,
+                superclass,
+                methods,
+                ..
+            } => {
+                self.declaration(stmt);
+                if let Some(superclass) = superclass {
+                    self.expr(superclass);
+                }
+                for method in methods {
+                    if let Statement::Function {
+                        name: method,
+                        params,
+                        body,
+                        ..
+                    } = method
+                    {
+                        let detail =
+                            format!("fun {}.{}", name.lexeme, Self::signature(method, params));
+                        self.define(method, Kind::Method, detail);
+                        self.function(params, body);
+                    }
+                }
+            }
+            Statement::Import { .. } => self.declaration(stmt),
+            Statement::Throw { value, .. } => self.expr(value),
+            Statement::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.statement(body);
+                if let Some((name, handler)) = catch {
+                    self.scoped(|index| {
+                        index.declare(name, Kind::Variable, format!("catch ({})", name.lexeme));
+                        index.statement(handler);
+                    });
+                }
+                if let Some(finally) = finally {
+                    self.statement(finally);
+                }
+            }
+            Statement::Match { subject, arms, .. } => {
+                self.expr(subject);
+                for arm in arms {
+                    self.scoped(|index| {
+                        if let Some(name) = arm.pattern.binding() {
+                            let detail = format!("(binding) {}", name.lexeme);
+                            index.declare(name, Kind::Variable, detail);
+                        }
+                        if let Some(guard) = &arm.guard {
+                            index.expr(guard);
+                        }
+                        index.statement(&arm.body);
+                    });
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Binary { left, right, .. } | Expr::Logcial { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Grouping { expression, .. } | Expr::Stringify { expression, .. } => {
+                self.expr(expression)
+            }
+            Expr::Unary { right, .. } => self.expr(right),
+            Expr::Literal { .. } | Expr::This { .. } => (),
+            Expr::Variable { name, .. } | Expr::Increment { name, .. } => self.lookup(name),
+            Expr::Assign { name, value, .. } => {
+                self.lookup(name);
+                self.expr(value);
+            }
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.expr(condition);
+                self.expr(then_branch);
+                self.expr(else_branch);
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.expr(callee);
+                for argument in arguments {
+                    self.expr(argument);
+                }
+            }
+            Expr::Get { object, name, .. } => {
+                self.expr(object);
+                self.properties.push(name.into());
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+                ..
+            } => {
+                self.expr(object);
+                self.properties.push(name.into());
+                self.expr(value);
+            }
+            Expr::List { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::Index { object, index, .. } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.expr(object);
+                self.expr(index);
+                self.expr(value);
+            }
+            Expr::Super { method, .. } => self.properties.push(method.into()),
+            Expr::Lambda { declaration, .. } => {
+                if let Statement::Function { params, body, .. } = &**declaration {
+                    self.function(params, body);
+                }
+            }
+        }
+    }
+}
+
+/// An open file, analysed whenever the editor sends its text.
+#[derive(Debug)]
+struct Document {
+    text: String,
+    /// The offset each line starts at.
+    lines: Vec<usize>,
+    tokens: Vec<Token>,
+    /// Only built when the text parses.
+    index: Option<Index>,
+    symbols: Vec<Json>,
+    diagnostics: Vec<Json>,
+}
+
+impl Document {
+    fn analyse(text: String) -> Self {
+        let lines = iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let mut document = Document {
+            text,
+            lines,
+            tokens: Vec::new(),
+            index: None,
+            symbols: Vec::new(),
+            diagnostics: Vec::new(),
+        };
+        let mut scanner = Scanner::build(document.text.clone());
+        document.tokens = match scanner.scan_tokens() {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                document.report(&e);
+                return document;
+            }
+        };
+        let Ok(mut parser) = Parser::new(document.tokens.clone()) else {
+            return document;
+        };
+        match parser.parse() {
+            Ok(mut ast) => {
+                for e in Resolver::new().resolve(&mut ast) {
+                    document.report(&e);
+                }
+                document.index = Some(Index::build(&ast));
+                document.symbols = document.symbols(ast.iter().collect());
+            }
+            Err(errors) => {
+                for e in errors {
+                    document.report(&e);
+                }
+            }
+        }
+        document
+    }
+
+    fn report(&mut self, error: &Report) {
+        let range = Diagnostic::span_of(error)
+            .map_or_else(|| self.range(Span::default()), |span| self.range(span));
+        self.diagnostics.push(json!({
+            "range": range,
+            "severity": 1,
+            "source": "rlok",
+            "message": error.to_string(),
+        }));
+    }
+
+    /// The line and UTF-16 column of `offset`, both counting from 0.
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.text.len());
+        let line = self.lines.partition_point(|&start| start <= offset) - 1;
+        let character = self.text[self.lines[line]..offset].encode_utf16().count();
+        (line, character)
+    }
+
+    fn offset(&self, position: &Json) -> usize {
+        let line = position["line"].as_u64().unwrap_or(0) as usize;
+        let Some(&start) = self.lines.get(line) else {
+            return self.text.len();
+        };
+        let mut units = position["character"].as_u64().unwrap_or(0) as usize;
+        let mut offset = start;
+        for c in self.text[start..].chars() {
+            if units == 0 || c == '\n' {
+                break;
+            }
+            units = units.saturating_sub(c.len_utf16());
+            offset += c.len_utf8();
+        }
+        offset
+    }
+
+    fn range(&self, span: Span) -> Json {
+        let (start_line, start) = self.position(span.start());
+        let (end_line, end) = self.position(span.end());
+        json!({
+            "start": { "line": start_line, "character": start },
+            "end": { "line": end_line, "character": end },
+        })
+    }
+
+    fn location(&self, uri: &str, span: Span) -> Json {
+        json!({ "uri": uri, "range": self.range(span) })
+    }
+
+    fn request(&self, method: &str, params: &Json, uri: &str, builtins: &[String]) -> Option<Json> {
+        let offset = self.offset(&params["position"]);
+        let result = match method {
+            "textDocument/hover" => self.hover(offset, builtins),
+            "textDocument/definition" => self.definition(uri, offset),
+            "textDocument/references" => {
+                let declaration = params["context"]["includeDeclaration"]
+                    .as_bool()
+                    .unwrap_or(true);
+                self.references(uri, offset, declaration)
+            }
+            "textDocument/documentSymbol" => json!(self.symbols),
+            "textDocument/semanticTokens/full" => json!({ "data": self.semantic_tokens(builtins) }),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    fn hover(&self, offset: usize, builtins: &[String]) -> Json {
+        let Some((span, definition)) = self.index.as_ref().and_then(|index| index.at(offset))
+        else {
+            return Json::Null;
+        };
+        let detail = match (definition, &self.index) {
+            (Some(id), Some(index)) => index.definitions[id].detail.clone(),
+            _ => {
+                let name = &self.text[span.start()..span.end()];
+                if !builtins.iter().any(|builtin| builtin == name) {
+                    return Json::Null;
+                }
+                format!("(builtin) {}", name)
+            }
+        };
+        json!({
+            "contents": { "kind": "markdown", "value": format!("```lox\n{}\n```", detail) },
+            "range": self.range(span),
+        })
+    }
+
+    fn definition(&self, uri: &str, offset: usize) -> Json {
+        let Some(index) = &self.index else {
+            return Json::Null;
+        };
+        match index.at(offset) {
+            Some((_, Some(id))) => self.location(uri, index.definitions[id].span),
+            _ => Json::Null,
+        }
+    }
+
+    fn references(&self, uri: &str, offset: usize, declaration: bool) -> Json {
+        let Some(index) = &self.index else {
+            return Json::Null;
+        };
+        let Some((_, Some(id))) = index.at(offset) else {
+            return json!([]);
+        };
+        let declared = index.definitions[id].span;
+        let references: Vec<Json> = index
+            .names
+            .iter()
+            .filter(|(span, definition)| {
+                *definition == Some(id) && (declaration || *span != declared)
+            })
+            .map(|(span, _)| self.location(uri, *span))
+            .collect();
+        json!(references)
+    }
+
+    /// Functions and classes, with what's declared inside them as children.
+    fn symbols(&self, statements: Vec<&Statement>) -> Vec<Json> {
+        let mut symbols = Vec::new();
+        for stmt in statements {
+            match stmt {
+                Statement::Function {
+                    span,
+                    name,
+                    params,
+                    body,
+                } => {
+                    let children = self.symbols(body.iter().collect());
+                    let detail = Index::signature(name, params);
+                    symbols.push(self.symbol(name, SYMBOL_FUNCTION, detail, *span, children));
+                }
+                Statement::Class {
+                    span,
+                    name,
+                    methods,
+                    ..
+                } => {
+                    let mut children = Vec::new();
+                    for method in methods {
+                        if let Statement::Function {
+                            span,
+                            name,
+                            params,
+                            body,
+                        } = method
+                        {
+                            let nested = self.symbols(body.iter().collect());
+                            let detail = Index::signature(name, params);
+                            children.push(self.symbol(name, SYMBOL_METHOD, detail, *span, nested));
+                        }
+                    }
+                    let symbol = self.symbol(name, SYMBOL_CLASS, String::new(), *span, children);
+                    symbols.push(symbol);
+                }
+                _ => symbols.extend(self.symbols(Self::nested(stmt))),
+            }
+        }
+        symbols
+    }
+
+    fn symbol(
+        &self,
+        name: &Token,
+        kind: i64,
+        detail: String,
+        span: Span,
+        children: Vec<Json>,
+    ) -> Json {
+        json!({
+            "name": name.lexeme,
+            "detail": detail,
+            "kind": kind,
+            "range": self.range(span),
+            "selectionRange": self.range(name.into()),
+            "children": children,
+        })
+    }
+
+    /// The statements directly inside `stmt`, such as a loop's body.
+    fn nested(stmt: &Statement) -> Vec<&Statement> {
+        match stmt {
+            Statement::Block { statements, .. } => statements.iter().collect(),
+            Statement::If {
+                then_branch,
+                else_branch,
+                ..
+            } => iter::once(&**then_branch)
+                .chain(else_branch.as_deref())
+                .collect(),
+            Statement::While { body, .. } => vec![&**body],
+            Statement::For {
+                initializer, body, ..
+            } => initializer
+                .as_deref()
+                .into_iter()
+                .chain([&**body])
+                .collect(),
+            Statement::Try {
+                body,
+                catch,
+                finally,
+                ..
+            } => iter::once(&**body)
+                .chain(catch.as_ref().map(|(_, handler)| &**handler))
+                .chain(finally.as_deref())
+                .collect(),
+            Statement::Match { arms, .. } => arms.iter().map(|arm| &arm.body).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The type of a token that isn't a name, or `None` for punctuation and
+    /// whitespace.
+    fn token_type(ty: &TokenType) -> Option<&'static str> {
+        match ty {
+            TokenType::StringLit | TokenType::Interpolation => Some("string"),
+            TokenType::NumberLit => Some("number"),
+            TokenType::AND
+            | TokenType::AS
+            | TokenType::BREAK
+            | TokenType::CATCH
+            | TokenType::CLASS
+            | TokenType::CONTINUE
+            | TokenType::ELSE
+            | TokenType::FALSE
+            | TokenType::FINALLY
+            | TokenType::FROM
+            | TokenType::FUN
+            | TokenType::FOR
+            | TokenType::IF
+            | TokenType::IMPORT
+            | TokenType::MATCH
+            | TokenType::NIL
+            | TokenType::OR
+            | TokenType::PRINT
+            | TokenType::RETURN
+            | TokenType::SUPER
+            | TokenType::THIS
+            | TokenType::THROW
+            | TokenType::TRUE
+            | TokenType::TRY
+            | TokenType::VAR
+            | TokenType::WHILE => Some("keyword"),
+            TokenType::NewLine
+            | TokenType::CarriageReturn
+            | TokenType::Tab
+            | TokenType::Space
+            | TokenType::LeftParen
+            | TokenType::RightParen
+            | TokenType::LeftBrace
+            | TokenType::RightBrace
+            | TokenType::LeftBracket
+            | TokenType::RightBracket
+            | TokenType::Comma
+            | TokenType::Dot
+            | TokenType::Semicolon
+            | TokenType::Colon
+            | TokenType::Ident
+            | TokenType::EOF => None,
+            _ => Some("operator"),
+        }
+    }
+
+    /// Each token's type as the protocol encodes them: five numbers per token,
+    /// with its line and start relative to the token before.
+    fn semantic_tokens(&self, builtins: &[String]) -> Vec<usize> {
+        let mut names: HashMap<usize, (&str, usize)> = HashMap::new();
+        if let Some(index) = &self.index {
+            for (span, definition) in &index.names {
+                let ty = match definition {
+                    Some(id) => {
+                        let definition = &index.definitions[*id];
+                        let modifiers = if definition.span == *span {
+                            DECLARATION
+                        } else {
+                            0
+                        };
+                        (definition.kind.token_type(), modifiers)
+                    }
+                    None => {
+                        let name = &self.text[span.start()..span.end()];
+                        if builtins.iter().any(|builtin| builtin == name) {
+                            ("function", DEFAULT_LIBRARY)
+                        } else {
+                            ("variable", 0)
+                        }
+                    }
+                };
+                names.insert(span.start(), ty);
+            }
+            for span in &index.properties {
+                names.insert(span.start(), ("property", 0));
+            }
+        }
+        let mut data = Vec::new();
+        let mut previous = (0, 0);
+        // Comments are the only text the scanner makes no tokens for.
+        let mut scanned = 0;
+        for token in &self.tokens {
+            if token.offset > scanned {
+                self.encode(
+                    &mut data,
+                    &mut previous,
+                    scanned,
+                    token.offset,
+                    ("comment", 0),
+                );
+            }
+            scanned = token.offset + token.lexeme.len();
+            let ty = match token.ty {
+                TokenType::Ident => names.get(&token.offset).copied().or(Some(("variable", 0))),
+                _ => Self::token_type(&token.ty).map(|ty| (ty, 0)),
+            };
+            if let Some(ty) = ty {
+                self.encode(&mut data, &mut previous, token.offset, scanned, ty);
+            }
+        }
+        data
+    }
+
+    /// Adds the text from `start` to `end`, split at line ends since a token
+    /// can't span lines.
+    fn encode(
+        &self,
+        data: &mut Vec<usize>,
+        previous: &mut (usize, usize),
+        start: usize,
+        end: usize,
+        (ty, modifiers): (&str, usize),
+    ) {
+        let ty = TOKEN_TYPES.iter().position(|name| *name == ty).unwrap_or(0);
+        let mut offset = start;
+        for piece in self.text[start..end].split('\n') {
+            let length = piece.trim_end_matches('\r').encode_utf16().count();
+            if length > 0 {
+                let (line, character) = self.position(offset);
+                let delta = if line == previous.0 {
+                    character - previous.1
+                } else {
+                    character
+                };
+                data.extend([line - previous.0, delta, length, ty, modifiers]);
+                *previous = (line, character);
+            }
+            offset += piece.len() + 1;
+        }
+    }
+}
+
+fn respond(id: &Json, result: Json) -> Result<()> {
+    framing::write(&json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn fail(id: &Json, code: i64, message: &str) -> Result<()> {
+    framing::write(&json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    }))
+}
+
+fn notify(method: &str, params: Json) -> Result<()> {
+    framing::write(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+}
+
+fn capabilities() -> Json {
+    json!({
+        "capabilities": {
+            // Editors send the whole text on every change.
+            "textDocumentSync": 1,
+            "hoverProvider": true,
+            "definitionProvider": true,
+            "referencesProvider": true,
+            "documentSymbolProvider": true,
+            "semanticTokensProvider": {
+                "legend": {
+                    "tokenTypes": TOKEN_TYPES,
+                    "tokenModifiers": ["declaration", "defaultLibrary"],
+                },
+                "full": true,
+            },
+        },
+        "serverInfo": { "name": "rlok" },
+    })
+}
+
+/// Runs the language server for `rlok lsp` until the editor sends `exit`.
+pub fn serve(inter: &mut Interpreter) -> Result<()> {
+    let builtins: Vec<String> = inter
+        .scopes()
+        .pop()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    let mut documents: HashMap<String, Document> = HashMap::new();
+    let closed = Document::analyse(String::new());
+    while let Some(message) = framing::read()? {
+        let id = &message["id"];
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        match message["method"].as_str().unwrap_or_default() {
+            "initialize" => respond(id, capabilities())?,
+            "shutdown" => respond(id, Json::Null)?,
+            "exit" => return Ok(()),
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                let document = Document::analyse(text.into());
+                notify(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": document.diagnostics }),
+                )?;
+                documents.insert(uri, document);
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                let text = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                    .unwrap_or_default();
+                let document = Document::analyse(text.into());
+                notify(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": document.diagnostics }),
+                )?;
+                documents.insert(uri, document);
+            }
+            "textDocument/didClose" => {
+                documents.remove(&uri);
+                notify(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                )?;
+            }
+            // Notifications such as `initialized` need no answer.
+            _ if id.is_null() => (),
+            method => {
+                let document = documents.get(&uri).unwrap_or(&closed);
+                match document.request(method, params, &uri, &builtins) {
+                    Some(result) => respond(id, result)?,
+                    None => fail(
+                        id,
+                        METHOD_NOT_FOUND,
+                        &format!("Unhandled method {}", method),
+                    )?,
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumberError {
+    DivideByZero,
+    Overflow,
+    NotIntegral,
+    InvalidShift,
+}
+
+impl Number {
+    pub fn as_float(self) -> f64 {
+        match self {
+            Number::Int(i) => i as f64,
+            Number::Float(f) => f,
+        }
+    }
+
+    pub fn is_zero(self) -> bool {
+        match self {
+            Number::Int(i) => i == 0,
+            Number::Float(f) => f == 0.0,
+        }
+    }
+
+    pub fn is_truthy(self) -> bool {
+        match self {
+            Number::Int(i) => i > 0,
+            Number::Float(f) => f > 0.0,
+        }
+    }
+
+    fn exact(
+        self,
+        rhs: Number,
+        int: fn(i64, i64) -> Option<i64>,
+        float: fn(f64, f64) -> f64,
+    ) -> Result<Number, NumberError> {
+        match (self, rhs) {
+            (Number::Int(l), Number::Int(r)) => {
+                int(l, r).map(Number::Int).ok_or(NumberError::Overflow)
+            }
+            (l, r) => Ok(Number::Float(float(l.as_float(), r.as_float()))),
+        }
+    }
+
+    pub fn checked_add(self, rhs: Number) -> Result<Number, NumberError> {
+        self.exact(rhs, i64::checked_add, |l, r| l + r)
+    }
+
+    pub fn checked_sub(self, rhs: Number) -> Result<Number, NumberError> {
+        self.exact(rhs, i64::checked_sub, |l, r| l - r)
+    }
+
+    pub fn checked_mul(self, rhs: Number) -> Result<Number, NumberError> {
+        self.exact(rhs, i64::checked_mul, |l, r| l * r)
+    }
+
+    pub fn checked_div(self, rhs: Number) -> Result<Number, NumberError> {
+        if rhs.is_zero() {
+            return Err(NumberError::DivideByZero);
+        }
+        Ok(Number::Float(self.as_float() / rhs.as_float()))
+    }
+
+    /// The remainder of a floored division, so it takes the sign of `rhs`.
+    pub fn checked_rem(self, rhs: Number) -> Result<Number, NumberError> {
+        if rhs.is_zero() {
+            return Err(NumberError::DivideByZero);
+        }
+        self.exact(
+            rhs,
+            |l, r| {
+                let rem = l.checked_rem(r)?;
+                Some(if rem != 0 && (rem < 0) != (r < 0) {
+                    rem + r
+                } else {
+                    rem
+                })
+            },
+            |l, r| {
+                let rem = l % r;
+                if rem != 0.0 && (rem < 0.0) != (r < 0.0) {
+                    rem + r
+                } else {
+                    rem
+                }
+            },
+        )
+    }
+
+    /// Division rounded down; integers stay integers.
+    pub fn checked_floor_div(self, rhs: Number) -> Result<Number, NumberError> {
+        if rhs.is_zero() {
+            return Err(NumberError::DivideByZero);
+        }
+        self.exact(
+            rhs,
+            |l, r| {
+                let quotient = l.checked_div(r)?;
+                Some(if l % r != 0 && (l < 0) != (r < 0) {
+                    quotient - 1
+                } else {
+                    quotient
+                })
+            },
+            |l, r| (l / r).floor(),
+        )
+    }
+
+    /// An integer raised to a non-negative integer stays exact; anything else is a float.
+    pub fn checked_pow(self, rhs: Number) -> Result<Number, NumberError> {
+        match (self, rhs) {
+            (Number::Int(base), Number::Int(exp)) if exp >= 0 => u32::try_from(exp)
+                .ok()
+                .and_then(|exp| base.checked_pow(exp))
+                .map(Number::Int)
+                .ok_or(NumberError::Overflow),
+            (base, exp) => Ok(Number::Float(base.as_float().powf(exp.as_float()))),
+        }
+    }
+
+    fn bitwise(self, rhs: Number, op: fn(i64, i64) -> i64) -> Result<Number, NumberError> {
+        match (self, rhs) {
+            (Number::Int(l), Number::Int(r)) => Ok(Number::Int(op(l, r))),
+            _ => Err(NumberError::NotIntegral),
+        }
+    }
+
+    pub fn bit_and(self, rhs: Number) -> Result<Number, NumberError> {
+        self.bitwise(rhs, |l, r| l & r)
+    }
+
+    pub fn bit_or(self, rhs: Number) -> Result<Number, NumberError> {
+        self.bitwise(rhs, |l, r| l | r)
+    }
+
+    pub fn bit_xor(self, rhs: Number) -> Result<Number, NumberError> {
+        self.bitwise(rhs, |l, r| l ^ r)
+    }
+
+    fn shift(self, rhs: Number, op: fn(i64, u32) -> i64) -> Result<Number, NumberError> {
+        match (self, rhs) {
+            (Number::Int(l), Number::Int(r)) => u32::try_from(r)
+                .ok()
+                .filter(|r| *r < i64::BITS)
+                .map(|r| Number::Int(op(l, r)))
+                .ok_or(NumberError::InvalidShift),
+            _ => Err(NumberError::NotIntegral),
+        }
+    }
+
+    pub fn shift_left(self, rhs: Number) -> Result<Number, NumberError> {
+        self.shift(rhs, |l, r| l << r)
+    }
+
+    /// An arithmetic shift, so negative numbers stay negative.
+    pub fn shift_right(self, rhs: Number) -> Result<Number, NumberError> {
+        self.shift(rhs, |l, r| l >> r)
+    }
+
+    pub fn bit_not(self) -> Result<Number, NumberError> {
+        match self {
+            Number::Int(i) => Ok(Number::Int(!i)),
+            Number::Float(_) => Err(NumberError::NotIntegral),
+        }
+    }
+
+    pub fn checked_neg(self) -> Result<Number, NumberError> {
+        match self {
+            Number::Int(i) => i
+                .checked_neg()
+                .map(Number::Int)
+                .ok_or(NumberError::Overflow),
+            Number::Float(f) => Ok(Number::Float(-f)),
+        }
+    }
+
+    pub fn compare(self, rhs: Number) -> Option<Ordering> {
+        match (self, rhs) {
+            (Number::Int(l), Number::Int(r)) => Some(l.cmp(&r)),
+            (l, r) => l.as_float().partial_cmp(&r.as_float()),
+        }
+    }
+}
+
+impl std::fmt::Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Number::Int(i) => write!(f, "{}", i),
+            Number::Float(n) => write!(f, "{}", n),
+        }
+    }
+}
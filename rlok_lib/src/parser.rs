@@ -1,7 +1,9 @@
 use super::error_handler::ParserError;
 use super::expression::Expr;
 use super::lit::LitType;
-use super::span::{Span, SpanParser};
+use super::numeric::Number;
+use super::pattern::{MatchArm, Pattern};
+use super::span::Span;
 use super::statement::Statement;
 use super::tokens::{Token, TokenType};
 use color_eyre::eyre::{Report, Result};
@@ -11,17 +13,45 @@ use tracing::{event, span, trace, Level};
 pub struct Parser {
     tokens: Vec<Token>,
     current: i32,
+    loops: Vec<Option<String>>,
+    /// Set while parsing a match guard, where `(x) =>` ends the guard.
+    in_guard: bool,
+    /// Lets the last expression statement of the input leave out its `;`.
+    interactive: bool,
+    /// Errors recovered from so far, so parsing can go on past each one.
+    errors: Vec<Report>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Result<Self> {
-        Ok(Parser { tokens, current: 0 })
+        Ok(Parser {
+            tokens,
+            current: 0,
+            loops: Vec::new(),
+            in_guard: false,
+            interactive: false,
+            errors: Vec::new(),
+        })
+    }
+
+    /// Marks the input as a REPL entry or debugger expression rather than a file.
+    pub fn set_interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
     }
 
     fn peek(&self) -> Token {
         self.tokens[self.current as usize].clone()
     }
 
+    fn peek_next(&self) -> Token {
+        self.tokens[self.current as usize + 1..]
+            .iter()
+            .find(|token| !self.is_white_space(token.ty.clone()))
+            .cloned()
+            .unwrap_or_else(|| self.peek())
+    }
+
     fn previous(&self) -> Token {
         let mut prev: Token;
         let mut prev_current = 1;
@@ -43,15 +73,16 @@ impl Parser {
         matches!(self.peek().ty, TokenType::EOF)
     }
 
-    pub fn get_span(&self, span: Span) -> String {
-        SpanParser::parse(span, self.tokens.clone())
+    fn start_span(&mut self) -> Span {
+        self.skip_white_space();
+        Span::new(&self.peek())
     }
 
     fn is_white_space(&self, token: TokenType) -> bool {
-        return matches!(token, TokenType::Space)
-            || matches!(token, TokenType::CarriageReturn)
-            || matches!(token, TokenType::Tab)
-            || matches!(token, TokenType::NewLine);
+        matches!(
+            token,
+            TokenType::Space | TokenType::CarriageReturn | TokenType::Tab | TokenType::NewLine
+        )
     }
 
     fn skip_white_space(&mut self) {
@@ -69,6 +100,17 @@ impl Parser {
         }
     }
 
+    /// An error at the upcoming token, for when none of several expected tokens follow.
+    fn unexpected(&self, error_message: &str) -> Report {
+        let token = self.peek();
+        Report::new(ParserError::ConsumeTokenError {
+            line: token.line,
+            span: Span::new(&token),
+            location: token.lexeme,
+            message: error_message.into(),
+        })
+    }
+
     fn consume(&mut self, ty: TokenType, error_message: &str) -> Result<Token> {
         self.skip_white_space();
         if self.check(ty) {
@@ -77,6 +119,7 @@ impl Parser {
             let token = self.previous();
             Err(Report::new(ParserError::ConsumeTokenError {
                 line: token.line,
+                span: Span::new(&token),
                 location: token.lexeme,
                 message: error_message.into(),
             }))
@@ -95,7 +138,7 @@ impl Parser {
         if self.is_end() {
             false
         } else {
-            return self.peek().ty == ty;
+            self.peek().ty == ty
         }
     }
 
@@ -110,51 +153,240 @@ impl Parser {
         false
     }
 
-    pub fn parse(&mut self) -> Result<Option<Vec<Statement>>> {
+    pub fn parse(&mut self) -> Result<Vec<Statement>, Vec<Report>> {
         let span = span!(Level::TRACE, "parsing");
         let _enter = span.enter();
 
         let mut statements: Vec<Statement> = Vec::new();
         while !self.is_end() {
-            match self.declaration() {
-                Ok(declaration) => {
-                    if let Some(dec) = declaration {
-                        statements.push(dec.clone())
-                    }
-                }
-                Err(e) => {
-                    return Err(e);
+            if let Some(dec) = self.recovering_declaration() {
+                statements.push(dec)
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    /// Parses one declaration, recording an error and skipping to the next
+    /// statement instead of giving up on the rest of the input.
+    fn recovering_declaration(&mut self) -> Option<Statement> {
+        self.skip_white_space();
+        let start = self.current;
+        match self.declaration() {
+            Ok(declaration) => declaration,
+            Err(e) => {
+                trace!(error = %e, "Parser error");
+                self.errors.push(e);
+                self.synchronize();
+                // Nothing was consumed, e.g. a stray '}' at the top level.
+                if self.current == start {
+                    self.advance();
                 }
+                None
             }
         }
-        Ok(Some(statements))
+    }
+
+    /// Discards tokens up to the end of the broken statement: just past a `;`,
+    /// or before a keyword that starts a statement or the `}` closing a body.
+    fn synchronize(&mut self) {
+        let span = span!(Level::TRACE, "synchronize");
+        let _enter = span.enter();
+        self.skip_white_space();
+        while !self.is_end() && !self.starts_statement() {
+            if self.check(TokenType::RightBrace) {
+                return;
+            }
+            trace!(token = %self.peek(), "Discarding");
+            if self.advance().ty == TokenType::Semicolon {
+                return;
+            }
+        }
+    }
+
+    fn starts_statement(&self) -> bool {
+        matches!(
+            self.peek().ty,
+            TokenType::CLASS
+                | TokenType::FUN
+                | TokenType::VAR
+                | TokenType::FOR
+                | TokenType::IF
+                | TokenType::WHILE
+                | TokenType::PRINT
+                | TokenType::RETURN
+                | TokenType::IMPORT
+                | TokenType::FROM
+                | TokenType::BREAK
+                | TokenType::CONTINUE
+                | TokenType::THROW
+                | TokenType::TRY
+                | TokenType::MATCH
+        )
     }
 
     fn declaration(&mut self) -> Result<Option<Statement>> {
         let span = span!(Level::TRACE, "declaration");
         let _enter = span.enter();
         trace!(token = %self.peek(), "Declaration");
-        if self.match_type(vec![TokenType::FUN]) {
-            return Ok(Some(self.function_declaration("function".into())?));
+        if self.match_type(vec![TokenType::CLASS]) {
+            Ok(Some(self.class_declaration()?))
+        } else if self.peek_next().ty != TokenType::LeftParen
+            && self.match_type(vec![TokenType::FUN])
+        {
+            let span = Span::new(&self.previous());
+            Ok(Some(self.function_declaration("function".into(), span)?))
         } else if self.match_type(vec![TokenType::VAR]) {
-            return self.var_declaration();
+            self.var_declaration()
+        } else if self.match_type(vec![TokenType::IMPORT]) {
+            Ok(Some(self.import_declaration()?))
+        } else if self.match_type(vec![TokenType::FROM]) {
+            Ok(Some(self.import_from_declaration()?))
         } else {
             self.statement()
         }
     }
 
-    fn function_declaration(&mut self, kind: String) -> Result<Statement> {
-        let span = span!(Level::TRACE, "function declaration");
+    fn import_declaration(&mut self) -> Result<Statement> {
+        let span = span!(Level::TRACE, "import declaration");
+        let _enter = span.enter();
+        let mut span = Span::new(&self.previous());
+        trace!(token = %self.peek(), "Import declaration");
+        let path = self.consume(TokenType::StringLit, "Expect module path after 'import'.")?;
+        self.consume(TokenType::AS, "Expect 'as' after module path.")?;
+        let alias = self.consume(TokenType::Ident, "Expect module name after 'as'.")?;
+        self.consume(TokenType::Semicolon, "Expect ';' after import.")?;
+        Ok(Statement::Import {
+            span: span.set_last(&self.previous()).done(),
+            path,
+            alias: Some(alias),
+            names: Vec::new(),
+        })
+    }
+
+    fn import_from_declaration(&mut self) -> Result<Statement> {
+        let span = span!(Level::TRACE, "import from declaration");
+        let _enter = span.enter();
+        let mut span = Span::new(&self.previous());
+        trace!(token = %self.peek(), "From declaration");
+        let path = self.consume(TokenType::StringLit, "Expect module path after 'from'.")?;
+        self.consume(TokenType::IMPORT, "Expect 'import' after module path.")?;
+        let mut names = Vec::new();
+        loop {
+            names.push(self.consume(TokenType::Ident, "Expect name to import.")?);
+            if !self.match_type(vec![TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(TokenType::Semicolon, "Expect ';' after import.")?;
+        Ok(Statement::Import {
+            span: span.set_last(&self.previous()).done(),
+            path,
+            alias: None,
+            names,
+        })
+    }
+
+    fn class_declaration(&mut self) -> Result<Statement> {
+        let span = span!(Level::TRACE, "class declaration");
         let _enter = span.enter();
-        let mut span = Span::new(self.current);
+        let mut span = Span::new(&self.previous());
+        trace!(token = %self.peek(), "Class declaration");
+        let name = self.consume(TokenType::Ident, "Expect class name.")?;
+        let mut superclass = None;
+        if self.match_type(vec![TokenType::Less]) {
+            let mut superclass_span = self.start_span();
+            let superclass_name = self.consume(TokenType::Ident, "Expect superclass name.")?;
+            superclass = Some(Expr::Variable {
+                span: superclass_span.set_last(&self.previous()).done(),
+                name: superclass_name,
+                depth: None,
+            });
+        }
+        self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
+        let mut methods = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_end() {
+            let span = self.start_span();
+            match self.function_declaration("method".into(), span) {
+                Ok(method) => methods.push(method),
+                Err(e) => self.errors.push(e),
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
+        Ok(Statement::Class {
+            span: span.set_last(&self.previous()).done(),
+            name,
+            superclass,
+            methods,
+        })
+    }
+
+    /// Discards the body of a function whose header is broken, through its
+    /// closing `}`, so its statements aren't parsed as if they were outside it.
+    fn skip_function_body(&mut self) {
+        let mut depth = 0;
+        while !self.is_end() {
+            match self.peek().ty {
+                TokenType::LeftBrace => depth += 1,
+                TokenType::RightBrace if depth == 0 => return,
+                TokenType::RightBrace => depth -= 1,
+                _ if depth == 0 && self.starts_statement() => return,
+                _ => {}
+            }
+            self.advance();
+            if depth == 0 && self.previous().ty == TokenType::RightBrace {
+                return;
+            }
+        }
+    }
+
+    /// Parses a named function or method; `span` starts at its `fun` keyword
+    /// or, for a method, at its name.
+    fn function_declaration(&mut self, kind: String, span: Span) -> Result<Statement> {
+        let span_trace = span!(Level::TRACE, "function declaration");
+        let _enter = span_trace.enter();
         event!(Level::TRACE, token = %self.peek(), "Function declaration");
-        let name = self.consume(TokenType::Ident, &format!("Expect {} name.", kind))?;
+        let function = self
+            .consume(TokenType::Ident, &format!("Expect {} name.", kind))
+            .and_then(|name| {
+                let _ = self.consume(
+                    TokenType::LeftParen,
+                    &format!("Expect '(' after {} name.)", kind),
+                )?;
+                self.function(&kind, name, span)
+            });
+        if function.is_err() {
+            self.skip_function_body();
+        }
+        function
+    }
+
+    /// Parses a function's parameters and body, after its '('.
+    fn function(&mut self, kind: &str, name: Token, mut span: Span) -> Result<Statement> {
+        let parameters = self.parameters()?;
         let _ = self.consume(
-            TokenType::LeftParen,
-            &format!("Expect '(' after {} name.)", kind),
-        )?;
-        let mut parameters = Vec::new();
+            TokenType::LeftBrace,
+            &format!("Expect '{{' before {} body", kind),
+        );
+        // Loops outside the function body can't be broken out of from inside it.
+        let loops = std::mem::take(&mut self.loops);
+        let body = self.block_statement();
+        self.loops = loops;
+        let body = body?;
+        Ok(Statement::Function {
+            span: span.set_last(&self.previous()).done(),
+            name,
+            params: parameters,
+            body,
+        })
+    }
 
+    /// Parses a parameter list up to and including its ')'.
+    fn parameters(&mut self) -> Result<Vec<Token>> {
+        let mut parameters = Vec::new();
         if !self.check(TokenType::RightParen) {
             loop {
                 if parameters.len() >= 255 {
@@ -167,24 +399,49 @@ impl Parser {
             }
         }
         let _ = self.consume(TokenType::RightParen, "Expect ')' after parameters");
-        let _ = self.consume(
-            TokenType::LeftBrace,
-            &format!("Expect '{{' before {} body", kind),
-        );
-        let body = self.block_statement()?;
-        Ok(Statement::Function {
-            span: span.set_last(self.current).done(),
-            name,
-            params: parameters,
-            body,
-        })
+        Ok(parameters)
+    }
+
+    /// Whether the upcoming '(' opens an arrow function's parameters rather than a grouping.
+    fn is_arrow(&self) -> bool {
+        if self.in_guard {
+            return false;
+        }
+        let mut tokens = self.tokens[self.current as usize..]
+            .iter()
+            .filter(|token| !self.is_white_space(token.ty.clone()));
+        if tokens.next().map(|token| &token.ty) != Some(&TokenType::LeftParen) {
+            return false;
+        }
+        let mut expect_name = true;
+        for token in tokens.by_ref() {
+            match token.ty {
+                TokenType::RightParen => break,
+                TokenType::Ident if expect_name => expect_name = false,
+                TokenType::Comma if !expect_name => expect_name = true,
+                _ => return false,
+            }
+        }
+        tokens.next().map(|token| &token.ty) == Some(&TokenType::Arrow)
+    }
+
+    /// Function expressions are anonymous function declarations named "lambda".
+    fn lambda_name(keyword: &Token) -> Token {
+        Token::new(
+            TokenType::Ident,
+            "lambda".into(),
+            None,
+            keyword.line,
+            keyword.column,
+            keyword.offset,
+        )
     }
 
     fn var_declaration(&mut self) -> Result<Option<Statement>> {
         let span = span!(Level::TRACE, "variable declaration");
         let _enter = span.enter();
         trace!(token = %self.peek());
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         let name = self.consume(TokenType::Ident, "Expected variable name.")?;
         if self.match_type(vec![TokenType::Equal]) {
             if let Some(expr) = self.expression()? {
@@ -193,7 +450,7 @@ impl Parser {
                     "Expect ';' after variable declaration.",
                 )?;
                 return Ok(Some(Statement::Var {
-                    span: span.set_last(self.current).done(),
+                    span: span.set_last(&self.previous()).done(),
                     name,
                     expression: Some(expr),
                 }));
@@ -204,26 +461,26 @@ impl Parser {
             TokenType::Semicolon,
             "Expected ';' after unintialized variable.",
         )?;
-        return Ok(Some(Statement::Var {
-            span: span.set_last(self.current).done(),
+        Ok(Some(Statement::Var {
+            span: span.set_last(&self.previous()).done(),
             name,
             expression: Some(Expr::Literal {
-                span: span.set_last(self.current).done(),
+                span: span.set_last(&self.previous()).done(),
                 value: Some(LitType::Nil),
             }),
-        }));
+        }))
     }
 
     fn statement(&mut self) -> Result<Option<Statement>> {
         let span = span!(Level::TRACE, "statement");
         let _enter = span.enter();
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         trace!(token = %self.peek(), "Statement processing");
         if self.match_type(vec![TokenType::LeftBrace]) {
-            return Ok(Some(Statement::Block {
-                span: span.set_last(self.current).done(),
+            Ok(Some(Statement::Block {
+                span: span.set_last(&self.previous()).done(),
                 statements: self.block_statement()?,
-            }));
+            }))
         } else if self.match_type(vec![TokenType::IF]) {
             Ok(Some(self.if_statement()?))
         } else if self.match_type(vec![TokenType::PRINT]) {
@@ -231,37 +488,295 @@ impl Parser {
         } else if self.match_type(vec![TokenType::RETURN]) {
             self.return_statement()
         } else if self.match_type(vec![TokenType::WHILE]) {
-            self.while_statement()
+            self.while_statement(None)
         } else if self.match_type(vec![TokenType::FOR]) {
-            self.for_statement()
+            self.for_statement(None)
+        } else if self.match_type(vec![TokenType::BREAK, TokenType::CONTINUE]) {
+            self.jump_statement()
+        } else if self.match_type(vec![TokenType::THROW]) {
+            self.throw_statement()
+        } else if self.match_type(vec![TokenType::TRY]) {
+            self.try_statement()
+        } else if self.match_type(vec![TokenType::MATCH]) {
+            self.match_statement()
+        } else if self.check(TokenType::Ident) && self.peek_next().ty == TokenType::Colon {
+            self.labeled_statement()
         } else {
             self.expression_statement()
         }
     }
 
+    fn match_statement(&mut self) -> Result<Option<Statement>> {
+        let span = span!(Level::TRACE, "match statement");
+        let _enter = span.enter();
+        let keyword = self.previous();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'match'.")?;
+        let Some(subject) = self.expression()? else {
+            return Err(self.unexpected("Expect expression after 'match ('."));
+        };
+        self.consume(TokenType::RightParen, "Expect ')' after match value.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before match arms.")?;
+        let mut arms = Vec::new();
+        loop {
+            self.skip_white_space();
+            if self.check(TokenType::RightBrace) || self.is_end() {
+                break;
+            }
+            arms.push(self.match_arm()?);
+            self.match_type(vec![TokenType::Comma]);
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after match arms.")?;
+        trace!(subject = %subject, arms = arms.len(), "Match statement");
+        Ok(Some(Statement::Match {
+            span: Span::new(&keyword).set_last(&self.previous()).done(),
+            keyword,
+            subject,
+            arms,
+        }))
+    }
+
+    fn match_arm(&mut self) -> Result<MatchArm> {
+        let mut span = self.start_span();
+        let pattern = self.pattern()?;
+        let mut guard = None;
+        if self.match_type(vec![TokenType::IF]) {
+            self.in_guard = true;
+            let condition = self.expression();
+            self.in_guard = false;
+            let Some(condition) = condition? else {
+                return Err(self.unexpected("Expect guard condition after 'if'."));
+            };
+            guard = Some(condition);
+        }
+        self.consume(TokenType::Arrow, "Expect '=>' after pattern.")?;
+        let Some(body) = self.statement()? else {
+            return Err(self.unexpected("Expect statement after '=>'."));
+        };
+        Ok(MatchArm {
+            span: span.set_last(&self.previous()).done(),
+            pattern,
+            guard,
+            body,
+        })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern> {
+        let mut span = self.start_span();
+        let first = self.single_pattern()?;
+        if !self.match_type(vec![TokenType::Pipe]) {
+            return Ok(first);
+        }
+        let mut alternatives = vec![first, self.single_pattern()?];
+        while self.match_type(vec![TokenType::Pipe]) {
+            alternatives.push(self.single_pattern()?);
+        }
+        if let Some(name) = alternatives.iter().find_map(Pattern::binding) {
+            return Err(Report::new(ParserError::ConsumeTokenError {
+                line: name.line,
+                span: Span::new(name),
+                location: name.lexeme.clone(),
+                message: "Can't bind a name in an alternative pattern.".into(),
+            }));
+        }
+        Ok(Pattern::Or(
+            alternatives,
+            span.set_last(&self.previous()).done(),
+        ))
+    }
+
+    fn single_pattern(&mut self) -> Result<Pattern> {
+        let mut span = self.start_span();
+        if self.match_type(vec![TokenType::Ident]) {
+            let name = self.previous();
+            if name.lexeme == "_" {
+                return Ok(Pattern::Wildcard((&name).into()));
+            }
+            return Ok(Pattern::Binding(name));
+        }
+        let literal = if self.match_type(vec![TokenType::TRUE]) {
+            Some(LitType::Bool(true))
+        } else if self.match_type(vec![TokenType::FALSE]) {
+            Some(LitType::Bool(false))
+        } else if self.match_type(vec![TokenType::NIL]) {
+            Some(LitType::Nil)
+        } else if self.match_type(vec![TokenType::StringLit]) {
+            Some(LitType::Str(self.previous().literal.unwrap_or_default()))
+        } else {
+            None
+        };
+        if let Some(literal) = literal {
+            return Ok(Pattern::Literal(
+                literal,
+                span.set_last(&self.previous()).done(),
+            ));
+        }
+        let start = self.pattern_number()?;
+        if self.match_type(vec![TokenType::DotDot, TokenType::DotDotEqual]) {
+            let inclusive = self.previous().ty == TokenType::DotDotEqual;
+            let end = self.pattern_number()?;
+            return Ok(Pattern::Range {
+                span: span.set_last(&self.previous()).done(),
+                start,
+                end,
+                inclusive,
+            });
+        }
+        Ok(Pattern::Literal(
+            start.into(),
+            span.set_last(&self.previous()).done(),
+        ))
+    }
+
+    fn pattern_number(&mut self) -> Result<Number> {
+        let negative = self.match_type(vec![TokenType::Minus]);
+        if !self.match_type(vec![TokenType::NumberLit]) {
+            return Err(self.unexpected("Expect pattern."));
+        }
+        let Some(number) = Self::number_literal(&self.previous()).number() else {
+            return Err(self.unexpected("Expect pattern."));
+        };
+        if !negative {
+            return Ok(number);
+        }
+        number
+            .checked_neg()
+            .map_err(|_| self.unexpected("Pattern number is out of range."))
+    }
+
+    fn number_literal(token: &Token) -> LitType {
+        let literal = token.literal.clone().unwrap();
+        if literal.contains('.') {
+            LitType::Float(literal.parse::<f64>().unwrap())
+        } else {
+            LitType::Int(literal.parse::<i64>().unwrap())
+        }
+    }
+
     fn return_statement(&mut self) -> Result<Option<Statement>> {
         let span = span!(Level::TRACE, "return statement");
         let _enter = span.enter();
-        let mut span = Span::new(self.current);
         trace!(token = %self.peek(), "Return statement");
         let keyword = self.previous();
-        if !self.match_type(vec![TokenType::Semicolon]) {
-            if let Some(value) = self.expression()? {
-                let _ = self.consume(TokenType::Semicolon, "Expect ';' after return value");
-                return Ok(Some(Statement::Return {
-                    span: span.set_last(self.current).done(),
-                    keyword,
-                    value,
-                }));
+        let mut value = None;
+        if !self.check(TokenType::Semicolon) {
+            value = self.expression()?;
+        }
+        self.consume(TokenType::Semicolon, "Expect ';' after return value")?;
+        Ok(Some(Statement::Return {
+            span: Span::new(&keyword).set_last(&self.previous()).done(),
+            keyword,
+            value,
+        }))
+    }
+
+    fn labeled_statement(&mut self) -> Result<Option<Statement>> {
+        let span = span!(Level::TRACE, "labeled statement");
+        let _enter = span.enter();
+        let label = self.advance();
+        trace!(label = %label, "Labeled statement");
+        self.consume(TokenType::Colon, "Expect ':' after loop label.")?;
+        if self.match_type(vec![TokenType::WHILE]) {
+            self.while_statement(Some(label))
+        } else if self.match_type(vec![TokenType::FOR]) {
+            self.for_statement(Some(label))
+        } else {
+            Err(self.unexpected("Expect 'while' or 'for' after loop label."))
+        }
+    }
+
+    fn loop_body(&mut self, label: &Option<Token>) -> Result<Option<Statement>> {
+        self.loops
+            .push(label.as_ref().map(|label| label.lexeme.clone()));
+        let body = self.statement();
+        self.loops.pop();
+        body
+    }
+
+    fn jump_statement(&mut self) -> Result<Option<Statement>> {
+        let span = span!(Level::TRACE, "jump statement");
+        let _enter = span.enter();
+        let keyword = self.previous();
+        trace!(keyword = %keyword, "Jump statement");
+        let mut label = None;
+        if self.match_type(vec![TokenType::Ident]) {
+            label = Some(self.previous());
+        }
+        if self.loops.is_empty() {
+            return Err(Report::new(ParserError::OutsideLoop(keyword)));
+        }
+        if let Some(label) = &label {
+            if !self.loops.contains(&Some(label.lexeme.clone())) {
+                return Err(Report::new(ParserError::UnknownLabel(label.clone())));
             }
         }
-        Ok(None)
+        self.consume(
+            TokenType::Semicolon,
+            &format!("Expect ';' after '{}'.", keyword.lexeme),
+        )?;
+        let span = Span::new(&keyword).set_last(&self.previous()).done();
+        if keyword.ty == TokenType::BREAK {
+            Ok(Some(Statement::Break {
+                span,
+                keyword,
+                label,
+            }))
+        } else {
+            Ok(Some(Statement::Continue {
+                span,
+                keyword,
+                label,
+            }))
+        }
+    }
+
+    fn throw_statement(&mut self) -> Result<Option<Statement>> {
+        let span = span!(Level::TRACE, "throw statement");
+        let _enter = span.enter();
+        let keyword = self.previous();
+        trace!(token = %self.peek(), "Throw statement");
+        let Some(value) = self.expression()? else {
+            return Err(self.unexpected("Expect expression after 'throw'."));
+        };
+        self.consume(TokenType::Semicolon, "Expect ';' after thrown value.")?;
+        Ok(Some(Statement::Throw {
+            span: Span::new(&keyword).set_last(&self.previous()).done(),
+            keyword,
+            value,
+        }))
     }
 
-    fn for_statement(&mut self) -> Result<Option<Statement>> {
+    fn try_statement(&mut self) -> Result<Option<Statement>> {
+        let span = span!(Level::TRACE, "try statement");
+        let _enter = span.enter();
+        let keyword = self.previous();
+        trace!(token = %self.peek(), "Try statement");
+        let body = self.braced_block("Expect '{' after 'try'.")?;
+        let mut catch = None;
+        if self.match_type(vec![TokenType::CATCH]) {
+            self.consume(TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self.consume(TokenType::Ident, "Expect error variable name.")?;
+            self.consume(TokenType::RightParen, "Expect ')' after error variable.")?;
+            catch = Some((name, self.braced_block("Expect '{' before catch body.")?));
+        }
+        let mut finally = None;
+        if self.match_type(vec![TokenType::FINALLY]) {
+            finally = Some(self.braced_block("Expect '{' after 'finally'.")?);
+        }
+        if catch.is_none() && finally.is_none() {
+            return Err(self.unexpected("Expect 'catch' or 'finally' after try block."));
+        }
+        Ok(Some(Statement::Try {
+            span: Span::new(&keyword).set_last(&self.previous()).done(),
+            body,
+            catch,
+            finally,
+        }))
+    }
+
+    fn for_statement(&mut self, label: Option<Token>) -> Result<Option<Statement>> {
         let span = span!(Level::TRACE, "for statement");
         let _enter = span.enter();
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         trace!(token = %self.peek(), "For statement");
         let _ = self.consume(TokenType::LeftParen, "Expected '(' after 'for'.");
         let initializer: Option<Statement>;
@@ -287,82 +802,48 @@ impl Parser {
 
         let _ = self.consume(TokenType::RightParen, "Expect ')' after for clause.");
 
-        let mut body = self.statement()?;
-
-        if let Some(inc) = increment {
-            if let Some(bdy) = body {
-                body = Some(Statement::Block {
-                    span: span.set_last(self.current).done(),
-                    statements: vec![
-                        Box::new(bdy),
-                        Box::new(Statement::Expression {
-                            span: span.set_last(self.current).done(),
-                            expression: inc,
-                        }),
-                    ],
-                });
-            }
-        }
-
-        if let Some(con) = condition {
-            if let Some(bdy) = body {
-                body = Some(Statement::While {
-                    span: span.set_last(self.current).done(),
-                    condition: con,
-                    body: Box::new(bdy),
-                });
-            }
-        } else {
-            if let Some(bdy) = body {
-                body = Some(Statement::While {
-                    span: span.set_last(self.current).done(),
-                    condition: Expr::Literal {
-                        span: span.set_last(self.current).done(),
-                        value: Some(LitType::Bool(true)),
-                    },
-                    body: Box::new(bdy),
-                });
-            }
-        }
-
-        if let Some(init) = initializer {
-            if let Some(bdy) = body {
-                body = Some(Statement::Block {
-                    span: span.set_last(self.current).done(),
-                    statements: vec![Box::new(init), Box::new(bdy)],
-                });
-            }
-        }
+        let body = self.loop_body(&label)?.unwrap_or(Statement::Block {
+            span: span.set_last(&self.previous()).done(),
+            statements: Vec::new(),
+        });
 
-        return Ok(body);
+        Ok(Some(Statement::For {
+            span: span.set_last(&self.previous()).done(),
+            initializer: initializer.map(Box::new),
+            condition,
+            increment,
+            body: Box::new(body),
+            label,
+        }))
     }
 
-    fn while_statement(&mut self) -> Result<Option<Statement>> {
+    fn while_statement(&mut self, label: Option<Token>) -> Result<Option<Statement>> {
         let span = span!(Level::TRACE, "while statement");
         let _enter = span.enter();
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         trace!(token = %self.peek(), "While statement");
         let _ = self.consume(TokenType::LeftParen, "Expect '(' after 'while'.");
         if let Some(condition) = self.expression()? {
             let _ = self.consume(TokenType::RightParen, "Expected ')' after condition.");
-            if let Some(body) = self.statement()? {
+            if let Some(body) = self.loop_body(&label)? {
                 return Ok(Some(Statement::While {
-                    span: span.set_last(self.current).done(),
+                    span: span.set_last(&self.previous()).done(),
                     condition,
                     body: Box::new(body),
+                    label,
                 }));
             }
             return Err(Report::new(ParserError::WhileMissingBody(condition)));
         }
-        return Err(Report::new(ParserError::WhileMissingCondition(
+        Err(Report::new(ParserError::WhileMissingCondition(
             self.previous(),
-        )));
+        )))
     }
 
     fn if_statement(&mut self) -> Result<Statement> {
         let span = span!(Level::TRACE, "if statement");
         let _enter = span.enter();
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         trace!(token = %self.peek(), "If statement");
         let _ = self.consume(TokenType::LeftParen, "Expected '(' after 'if'.");
         if let Some(condition) = self.expression()? {
@@ -371,7 +852,7 @@ impl Parser {
                 if self.match_type(vec![TokenType::ELSE]) {
                     if let Some(els) = self.statement()? {
                         return Ok(Statement::If {
-                            span: span.set_last(self.current).done(),
+                            span: span.set_last(&self.previous()).done(),
                             condition,
                             then_branch: Box::new(then_branch),
                             else_branch: Some(Box::new(els)),
@@ -379,7 +860,7 @@ impl Parser {
                     }
                 } else {
                     return Ok(Statement::If {
-                        span: span.set_last(self.current).done(),
+                        span: span.set_last(&self.previous()).done(),
                         condition,
                         then_branch: Box::new(then_branch),
                         else_branch: None,
@@ -388,19 +869,29 @@ impl Parser {
             }
             return Err(Report::new(ParserError::MissingThenBranch(condition)));
         }
-        return Err(Report::new(ParserError::MissingIfCondition(
+        Err(Report::new(ParserError::MissingIfCondition(
             self.previous(),
-        )));
+        )))
     }
 
-    fn block_statement(&mut self) -> Result<Vec<Box<Statement>>> {
+    /// A block that the grammar requires, rather than any statement.
+    fn braced_block(&mut self, error_message: &str) -> Result<Box<Statement>> {
+        let brace = self.consume(TokenType::LeftBrace, error_message)?;
+        let statements = self.block_statement()?;
+        Ok(Box::new(Statement::Block {
+            span: Span::new(&brace).set_last(&self.previous()).done(),
+            statements,
+        }))
+    }
+
+    fn block_statement(&mut self) -> Result<Vec<Statement>> {
         let span = span!(Level::TRACE, "block statement");
         let _enter = span.enter();
         trace!(token = %self.peek(), "Block statement");
-        let mut statements: Vec<Box<Statement>> = Vec::new();
+        let mut statements: Vec<Statement> = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_end() {
-            if let Some(dec) = self.declaration()? {
-                statements.push(Box::new(dec));
+            if let Some(dec) = self.recovering_declaration() {
+                statements.push(dec);
             }
         }
         let _ = self.consume(TokenType::RightBrace, "Expected '}' after block.")?;
@@ -410,12 +901,12 @@ impl Parser {
     fn print_statement(&mut self) -> Result<Option<Statement>> {
         let span = span!(Level::TRACE, "print statement");
         let _enter = span.enter();
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         trace!(token = %self.peek(), "Print statement");
         if let Some(expr) = self.expression()? {
             let _ = self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
             return Ok(Some(Statement::Print {
-                span: span.set_last(self.current).done(),
+                span: span.set_last(&self.previous()).done(),
                 expression: expr,
             }));
         }
@@ -425,15 +916,18 @@ impl Parser {
     fn expression_statement(&mut self) -> Result<Option<Statement>> {
         let span = span!(Level::TRACE, "expression statement");
         let _enter = span.enter();
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         trace!(token = %self.peek(), "Expression statement");
         if let Some(expr) = self.expression()? {
-            let _ = self.consume(
-                TokenType::Semicolon,
-                "Expected ';' after expression statement.",
-            );
+            self.skip_white_space();
+            if !(self.interactive && self.is_end()) {
+                let _ = self.consume(
+                    TokenType::Semicolon,
+                    "Expected ';' after expression statement.",
+                )?;
+            }
             return Ok(Some(Statement::Expression {
-                span: span.set_last(self.current).done(),
+                span: span.set_last(&self.previous()).done(),
                 expression: expr,
             }));
         }
@@ -451,23 +945,63 @@ impl Parser {
     }
 
     fn assignment(&mut self) -> Result<Option<Expr>> {
-        let mut span = Span::new(self.current);
-        if let Some(expr) = self.logic_or()? {
-            if self.match_type(vec![TokenType::Equal]) {
+        let mut span = self.start_span();
+        if let Some(expr) = self.conditional()? {
+            if self.match_type(vec![
+                TokenType::Equal,
+                TokenType::PlusEqual,
+                TokenType::MinusEqual,
+                TokenType::StarEqual,
+                TokenType::SlashEqual,
+                TokenType::PercentEqual,
+            ]) {
                 let equals = self.previous();
-                if let Some(value) = self.assignment()? {
-                    if let Expr::Variable { span: _, name } = expr {
-                        if matches!(self.peek().ty, TokenType::Semicolon) {
-                            let _ = self.consume(
-                                TokenType::Semicolon,
-                                "Expect ';' after variable assignment.",
-                            )?;
-                        }
+                if let Some(mut value) = self.assignment()? {
+                    if equals.ty != TokenType::Equal {
+                        value = Self::compound(&expr, &equals, value, &mut span, &self.previous());
+                    }
+                    if let Expr::Variable {
+                        span: _,
+                        name,
+                        depth: _,
+                    } = expr
+                    {
                         trace!(name = %name, value = %value, "Assignment");
                         return Ok(Some(Expr::Assign {
-                            span: span.set_last(self.current).done(),
+                            span: span.set_last(&self.previous()).done(),
                             name,
                             value: Box::new(value),
+                            depth: None,
+                        }));
+                    }
+                    if let Expr::Get {
+                        span: _,
+                        object,
+                        name,
+                    } = expr
+                    {
+                        trace!(name = %name, value = %value, "Set");
+                        return Ok(Some(Expr::Set {
+                            span: span.set_last(&self.previous()).done(),
+                            object,
+                            name,
+                            value: Box::new(value),
+                        }));
+                    }
+                    if let Expr::Index {
+                        span: _,
+                        object,
+                        bracket,
+                        index,
+                    } = expr
+                    {
+                        trace!(value = %value, "Set index");
+                        return Ok(Some(Expr::SetIndex {
+                            span: span.set_last(&self.previous()).done(),
+                            object,
+                            bracket,
+                            index,
+                            value: Box::new(value),
                         }));
                     }
                     return Err(Report::new(ParserError::UnexpectedAssignmentTarget(equals)));
@@ -479,21 +1013,65 @@ impl Parser {
         Ok(None)
     }
 
+    /// Desugars the value of `a += b` into `a + b`, reading the target again.
+    fn compound(target: &Expr, equals: &Token, value: Expr, span: &mut Span, last: &Token) -> Expr {
+        let mut operator = equals.clone();
+        (operator.ty, operator.lexeme) = match equals.ty {
+            TokenType::PlusEqual => (TokenType::Plus, "+".into()),
+            TokenType::MinusEqual => (TokenType::Minus, "-".into()),
+            TokenType::StarEqual => (TokenType::Star, "*".into()),
+            TokenType::SlashEqual => (TokenType::Slash, "/".into()),
+            _ => (TokenType::Percent, "%".into()),
+        };
+        Expr::Binary {
+            span: span.set_last(last).done(),
+            left: Box::new(target.clone()),
+            operator,
+            right: Box::new(value),
+        }
+    }
+
+    fn conditional(&mut self) -> Result<Option<Expr>> {
+        let mut span = self.start_span();
+        if let Some(condition) = self.logic_or()? {
+            if self.match_type(vec![TokenType::Question]) {
+                let Some(then_branch) = self.expression()? else {
+                    return Err(self.unexpected("Expect expression after '?'."));
+                };
+                self.consume(
+                    TokenType::Colon,
+                    "Expect ':' after then branch of conditional.",
+                )?;
+                let Some(else_branch) = self.conditional()? else {
+                    return Err(self.unexpected("Expect expression after ':'."));
+                };
+                return Ok(Some(Expr::Conditional {
+                    span: span.set_last(&self.previous()).done(),
+                    condition: Box::new(condition),
+                    then_branch: Box::new(then_branch),
+                    else_branch: Box::new(else_branch),
+                }));
+            }
+            return Ok(Some(condition));
+        }
+        Ok(None)
+    }
+
     fn logic_or(&mut self) -> Result<Option<Expr>> {
-        let mut span = Span::new(self.current);
-        if let Some(expr) = self.logic_and()? {
+        let mut span = self.start_span();
+        if let Some(mut expr) = self.logic_and()? {
             while self.match_type(vec![TokenType::OR]) {
                 let operator = self.previous();
-                if let Some(right) = self.logic_and()? {
-                    trace!(expr = %expr, operator.lexeme, right = %right, "Logic OR");
-                    return Ok(Some(Expr::Logcial {
-                        span: span.set_last(self.current).done(),
-                        left: Box::new(expr),
-                        operator,
-                        right: Box::new(right),
-                    }));
-                }
-                return Err(Report::new(ParserError::LogicOrMissingRight(expr)));
+                let Some(right) = self.logic_and()? else {
+                    return Err(Report::new(ParserError::LogicOrMissingRight(expr)));
+                };
+                trace!(expr = %expr, operator.lexeme, right = %right, "Logic OR");
+                expr = Expr::Logcial {
+                    span: span.set_last(&self.previous()).done(),
+                    left: Box::new(expr),
+                    operator,
+                    right: Box::new(right),
+                };
             }
             return Ok(Some(expr));
         }
@@ -501,20 +1079,20 @@ impl Parser {
     }
 
     fn logic_and(&mut self) -> Result<Option<Expr>> {
-        let mut span = Span::new(self.current);
-        if let Some(expr) = self.equality()? {
+        let mut span = self.start_span();
+        if let Some(mut expr) = self.equality()? {
             while self.match_type(vec![TokenType::AND]) {
                 let operator = self.previous();
-                if let Some(right) = self.equality()? {
-                    trace!(expr = %expr, operator.lexeme, right = %right, "Logic AND");
-                    return Ok(Some(Expr::Logcial {
-                        span: span.set_last(self.current).done(),
-                        left: Box::new(expr),
-                        operator,
-                        right: Box::new(right),
-                    }));
-                }
-                return Err(Report::new(ParserError::LogicAndMissingRight(expr)));
+                let Some(right) = self.equality()? else {
+                    return Err(Report::new(ParserError::LogicAndMissingRight(expr)));
+                };
+                trace!(expr = %expr, operator.lexeme, right = %right, "Logic AND");
+                expr = Expr::Logcial {
+                    span: span.set_last(&self.previous()).done(),
+                    left: Box::new(expr),
+                    operator,
+                    right: Box::new(right),
+                };
             }
             return Ok(Some(expr));
         }
@@ -522,14 +1100,14 @@ impl Parser {
     }
 
     fn equality(&mut self) -> Result<Option<Expr>> {
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         if let Some(expr) = self.comparison()? {
             while self.match_type(vec![TokenType::BangEqual, TokenType::EqualEqual]) {
                 let operator = self.previous();
                 if let Some(right) = self.comparison()? {
                     trace!(expr = %expr, operator.lexeme, right = %right, "Binary");
                     return Ok(Some(Expr::Binary {
-                        span: span.set_last(self.current).done(),
+                        span: span.set_last(&self.previous()).done(),
                         left: Box::new(expr),
                         operator,
                         right: Box::new(right),
@@ -542,19 +1120,19 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Result<Option<Expr>> {
-        let mut span = Span::new(self.current);
-        if let Some(mut expr) = self.term()? {
+        let mut span = self.start_span();
+        if let Some(mut expr) = self.bit_or()? {
             while self.match_type(vec![
                 TokenType::Greater,
-                TokenType::LessEqual,
+                TokenType::GreaterEqual,
                 TokenType::Less,
                 TokenType::LessEqual,
             ]) {
                 let operator = self.previous();
-                if let Some(right) = self.term()? {
+                if let Some(right) = self.bit_or()? {
                     trace!(expr = %expr, operator.lexeme, right = %right, "Comparison");
                     expr = Expr::Binary {
-                        span: span.set_last(self.current).done(),
+                        span: span.set_last(&self.previous()).done(),
                         left: Box::new(expr),
                         operator,
                         right: Box::new(right),
@@ -566,15 +1144,59 @@ impl Parser {
         Ok(None)
     }
 
+    fn bit_or(&mut self) -> Result<Option<Expr>> {
+        self.left_associative(vec![TokenType::Pipe], Self::bit_xor)
+    }
+
+    fn bit_xor(&mut self) -> Result<Option<Expr>> {
+        self.left_associative(vec![TokenType::Caret], Self::bit_and)
+    }
+
+    fn bit_and(&mut self) -> Result<Option<Expr>> {
+        self.left_associative(vec![TokenType::Ampersand], Self::shift)
+    }
+
+    fn shift(&mut self) -> Result<Option<Expr>> {
+        self.left_associative(
+            vec![TokenType::LessLess, TokenType::GreaterGreater],
+            Self::term,
+        )
+    }
+
+    /// Parses `operand (op operand)*` for one of the given operators.
+    fn left_associative(
+        &mut self,
+        operators: Vec<TokenType>,
+        operand: fn(&mut Self) -> Result<Option<Expr>>,
+    ) -> Result<Option<Expr>> {
+        let mut span = self.start_span();
+        if let Some(mut expr) = operand(self)? {
+            while self.match_type(operators.clone()) {
+                let operator = self.previous();
+                if let Some(right) = operand(self)? {
+                    trace!(expr = %expr, operator.lexeme, right = %right, "Binary");
+                    expr = Expr::Binary {
+                        span: span.set_last(&self.previous()).done(),
+                        left: Box::new(expr),
+                        operator,
+                        right: Box::new(right),
+                    }
+                }
+            }
+            return Ok(Some(expr));
+        }
+        Ok(None)
+    }
+
     fn term(&mut self) -> Result<Option<Expr>> {
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         if let Some(mut expr) = self.factor()? {
             while self.match_type(vec![TokenType::Minus, TokenType::Plus]) {
                 let operator = self.previous();
                 if let Some(right) = self.factor()? {
                     trace!(expr = %expr, operator.lexeme, right = %right, "Term");
                     expr = Expr::Binary {
-                        span: span.set_last(self.current).done(),
+                        span: span.set_last(&self.previous()).done(),
                         left: Box::new(expr),
                         operator,
                         right: Box::new(right),
@@ -587,14 +1209,19 @@ impl Parser {
     }
 
     fn factor(&mut self) -> Result<Option<Expr>> {
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         if let Some(mut expr) = self.unary()? {
-            while self.match_type(vec![TokenType::Slash, TokenType::Star]) {
+            while self.match_type(vec![
+                TokenType::Slash,
+                TokenType::Star,
+                TokenType::Percent,
+                TokenType::TildeSlash,
+            ]) {
                 let operator = self.previous();
                 if let Some(right) = self.unary()? {
                     trace!(expr = %expr, operator.lexeme, right = %right, "Factor");
                     expr = Expr::Binary {
-                        span: span.set_last(self.current).done(),
+                        span: span.set_last(&self.previous()).done(),
                         left: Box::new(expr),
                         operator,
                         right: Box::new(right),
@@ -608,30 +1235,115 @@ impl Parser {
     }
 
     fn unary(&mut self) -> Result<Option<Expr>> {
-        let mut span = Span::new(self.current);
-        if let Some(mut expr) = self.call()? {
-            if self.match_type(vec![TokenType::Bang, TokenType::Minus]) {
+        let mut span = self.start_span();
+        if self.match_type(vec![TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
+            let operator = self.previous();
+            if let Some(right) = self.unary()? {
+                trace!(operator.lexeme, right = %right, "Unary");
+                return Ok(Some(Expr::Unary {
+                    span: span.set_last(&self.previous()).done(),
+                    operator,
+                    right: Box::new(right),
+                }));
+            }
+        }
+        if self.match_type(vec![TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous();
+            if let Some(target) = self.unary()? {
+                return Ok(Some(self.increment(target, operator, true, &mut span)?));
+            }
+        }
+        self.power()
+    }
+
+    /// `**` binds tighter than a unary operator on its left, and is right associative.
+    fn power(&mut self) -> Result<Option<Expr>> {
+        let mut span = self.start_span();
+        if let Some(base) = self.postfix()? {
+            if self.match_type(vec![TokenType::StarStar]) {
                 let operator = self.previous();
-                if let Some(right) = self.unary()? {
-                    trace!(operator.lexeme, right = %right, "Unary");
-                    expr = Expr::Unary {
-                        span: span.set_last(self.current).done(),
-                        operator,
-                        right: Box::new(right),
-                    }
-                }
+                let Some(exponent) = self.unary()? else {
+                    return Err(self.unexpected("Expect expression after '**'."));
+                };
+                trace!(base = %base, exponent = %exponent, "Power");
+                return Ok(Some(Expr::Binary {
+                    span: span.set_last(&self.previous()).done(),
+                    left: Box::new(base),
+                    operator,
+                    right: Box::new(exponent),
+                }));
+            }
+            return Ok(Some(base));
+        }
+        Ok(None)
+    }
+
+    fn postfix(&mut self) -> Result<Option<Expr>> {
+        let mut span = self.start_span();
+        if let Some(expr) = self.call()? {
+            if self.match_type(vec![TokenType::PlusPlus, TokenType::MinusMinus]) {
+                let operator = self.previous();
+                return Ok(Some(self.increment(expr, operator, false, &mut span)?));
             }
             return Ok(Some(expr));
         }
         Ok(None)
     }
 
+    fn increment(
+        &self,
+        target: Expr,
+        operator: Token,
+        prefix: bool,
+        span: &mut Span,
+    ) -> Result<Expr> {
+        let Expr::Variable {
+            span: _,
+            name,
+            depth: _,
+        } = target
+        else {
+            return Err(Report::new(ParserError::InvalidAssignmentTarget(operator)));
+        };
+        trace!(name = %name, operator = %operator.ty, "Increment");
+        Ok(Expr::Increment {
+            span: span.set_last(&self.previous()).done(),
+            name,
+            operator,
+            prefix,
+            depth: None,
+        })
+    }
+
     fn call(&mut self) -> Result<Option<Expr>> {
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         if let Some(mut expr) = self.primary()? {
             loop {
                 if self.match_type(vec![TokenType::LeftParen]) {
-                    expr = self.finish_call(expr, &mut span)?;
+                    // Arguments are delimited, so arrow functions are fine in a guard's calls.
+                    let in_guard = std::mem::take(&mut self.in_guard);
+                    let call = self.finish_call(expr, &mut span);
+                    self.in_guard = in_guard;
+                    expr = call?;
+                } else if self.match_type(vec![TokenType::Dot]) {
+                    let name = self.consume(TokenType::Ident, "Expect property name after '.'.")?;
+                    expr = Expr::Get {
+                        span: span.set_last(&self.previous()).done(),
+                        object: Box::new(expr),
+                        name,
+                    };
+                } else if self.match_type(vec![TokenType::LeftBracket]) {
+                    let bracket = self.previous();
+                    let Some(index) = self.expression()? else {
+                        return Err(Report::new(ParserError::MissingIndex(bracket)));
+                    };
+                    self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                    expr = Expr::Index {
+                        span: span.set_last(&self.previous()).done(),
+                        object: Box::new(expr),
+                        bracket,
+                        index: Box::new(index),
+                    };
                 } else {
                     break;
                 }
@@ -651,7 +1363,7 @@ impl Parser {
                     if arguments.len() >= 255 {
                         return Err(Report::new(ParserError::MaxArguments(self.peek())));
                     }
-                    arguments.push(Box::new(ex));
+                    arguments.push(ex);
                 }
                 if !self.match_type(vec![TokenType::Comma]) {
                     break;
@@ -659,55 +1371,203 @@ impl Parser {
             }
         }
         let paren = self.consume(TokenType::RightParen, "Expected ')' after arguments.")?;
-        return Ok(Expr::Call {
-            span: span.set_last(self.current).done(),
+        Ok(Expr::Call {
+            span: span.set_last(&self.previous()).done(),
             callee: Box::new(expr),
             paren,
             arguments,
-        });
+        })
+    }
+
+    /// Desugars `"a${x}b"` into `"a" + str(x) + "b"`.
+    fn interpolation(&mut self, mut span: Span) -> Result<Expr> {
+        let mut expr = Self::string_piece(&self.previous());
+        loop {
+            let piece = self.previous();
+            // The string piece after an interpolation starts at its closing brace.
+            if self.peek().lexeme.starts_with('}') {
+                return Err(Report::new(ParserError::EmptyInterpolation(piece)));
+            }
+            let Some(inner) = self.expression()? else {
+                return Err(Report::new(ParserError::EmptyInterpolation(piece)));
+            };
+            let inner = Expr::Stringify {
+                span: inner.span(),
+                expression: Box::new(inner),
+            };
+            expr = Self::concat(expr, inner, &piece, &mut span, &self.previous());
+            let more = self.match_type(vec![TokenType::Interpolation]);
+            if !more {
+                self.consume(TokenType::StringLit, "Expect end of string after '}'.")?;
+            }
+            let piece = self.previous();
+            if piece.literal.as_deref() != Some("") {
+                let text = Self::string_piece(&piece);
+                expr = Self::concat(expr, text, &piece, &mut span, &piece);
+            }
+            if !more {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn string_piece(token: &Token) -> Expr {
+        Expr::Literal {
+            span: token.into(),
+            value: Some(LitType::Str(token.literal.clone().unwrap_or_default())),
+        }
+    }
+
+    fn concat(left: Expr, right: Expr, at: &Token, span: &mut Span, last: &Token) -> Expr {
+        let mut operator = at.clone();
+        operator.ty = TokenType::Plus;
+        operator.lexeme = "+".into();
+        operator.literal = None;
+        Expr::Binary {
+            span: span.set_last(last).done(),
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        }
     }
 
     fn primary(&mut self) -> Result<Option<Expr>> {
-        let mut span = Span::new(self.current);
+        let mut span = self.start_span();
         trace!(token = %self.peek(), "Primary");
         if self.match_type(vec![TokenType::FALSE]) {
             return Ok(Some(Expr::Literal {
-                span: span.set_last(self.current).done(),
+                span: span.set_last(&self.previous()).done(),
                 value: Some(LitType::Bool(false)),
             }));
         }
         if self.match_type(vec![TokenType::TRUE]) {
             return Ok(Some(Expr::Literal {
-                span: span.set_last(self.current).done(),
+                span: span.set_last(&self.previous()).done(),
                 value: Some(LitType::Bool(true)),
             }));
         }
         if self.match_type(vec![TokenType::NIL]) {
             return Ok(Some(Expr::Literal {
-                span: span.set_last(self.current).done(),
+                span: span.set_last(&self.previous()).done(),
                 value: Some(LitType::Nil),
             }));
         }
         if self.match_type(vec![TokenType::NumberLit]) {
+            let value = Self::number_literal(&self.previous());
             return Ok(Some(Expr::Literal {
-                span: span.set_last(self.current).done(),
-                value: Some(LitType::Float(
-                    self.previous().literal.unwrap().parse::<f32>().unwrap(),
-                )),
+                span: span.set_last(&self.previous()).done(),
+                value: Some(value),
             }));
         }
+        if self.match_type(vec![TokenType::Interpolation]) {
+            return Ok(Some(self.interpolation(span)?));
+        }
         if self.match_type(vec![TokenType::StringLit]) {
             return Ok(Some(Expr::Literal {
-                span: span.set_last(self.current).done(),
+                span: span.set_last(&self.previous()).done(),
                 value: Some(LitType::Str(self.previous().literal.unwrap())),
             }));
         }
 
+        if self.match_type(vec![TokenType::THIS]) {
+            return Ok(Some(Expr::This {
+                span: span.set_last(&self.previous()).done(),
+                keyword: self.previous(),
+                depth: None,
+            }));
+        }
+
+        if self.match_type(vec![TokenType::SUPER]) {
+            let keyword = self.previous();
+            self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+            let method = self.consume(TokenType::Ident, "Expect superclass method name.")?;
+            return Ok(Some(Expr::Super {
+                span: span.set_last(&self.previous()).done(),
+                keyword,
+                method,
+                depth: None,
+            }));
+        }
+
+        if self.match_type(vec![TokenType::LeftBracket]) {
+            let mut elements = Vec::new();
+            while !self.check(TokenType::RightBracket) {
+                if let Some(element) = self.expression()? {
+                    elements.push(element);
+                }
+                if !self.match_type(vec![TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+            return Ok(Some(Expr::List {
+                span: span.set_last(&self.previous()).done(),
+                elements,
+            }));
+        }
+
+        if self.match_type(vec![TokenType::LeftBrace]) {
+            let mut entries = Vec::new();
+            while !self.check(TokenType::RightBrace) {
+                let Some(key) = self.expression()? else {
+                    break;
+                };
+                self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+                let colon = self.previous();
+                let Some(value) = self.expression()? else {
+                    return Err(Report::new(ParserError::MissingMapValue(colon)));
+                };
+                entries.push((key, value));
+                if !self.match_type(vec![TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+            return Ok(Some(Expr::Map {
+                span: span.set_last(&self.previous()).done(),
+                entries,
+            }));
+        }
+
+        if self.match_type(vec![TokenType::FUN]) {
+            let keyword = self.previous();
+            self.consume(TokenType::LeftParen, "Expect '(' after 'fun'.")?;
+            let declaration = self.function("function", Self::lambda_name(&keyword), span)?;
+            return Ok(Some(Expr::Lambda {
+                span: span.set_last(&self.previous()).done(),
+                declaration: Box::new(declaration),
+            }));
+        }
+
+        if self.is_arrow() {
+            self.advance();
+            let params = self.parameters()?;
+            let arrow = self.consume(TokenType::Arrow, "Expect '=>' after parameters.")?;
+            let Some(value) = self.expression()? else {
+                return Err(self.unexpected("Expect expression after '=>'."));
+            };
+            let span = span.set_last(&self.previous()).done();
+            let body = vec![Statement::Return {
+                span: value.span(),
+                keyword: arrow.clone(),
+                value: Some(value),
+            }];
+            return Ok(Some(Expr::Lambda {
+                span,
+                declaration: Box::new(Statement::Function {
+                    span,
+                    name: Self::lambda_name(&arrow),
+                    params,
+                    body,
+                }),
+            }));
+        }
+
         if self.match_type(vec![TokenType::LeftParen]) {
             if let Some(expr) = self.expression()? {
                 self.consume(TokenType::RightParen, "Expected ')' after expression.")?;
                 return Ok(Some(Expr::Grouping {
-                    span: span.set_last(self.current).done(),
+                    span: span.set_last(&self.previous()).done(),
                     expression: Box::new(expr),
                 }));
             }
@@ -715,8 +1575,9 @@ impl Parser {
 
         if self.match_type(vec![TokenType::Ident]) {
             return Ok(Some(Expr::Variable {
-                span: span.set_last(self.current).done(),
+                span: span.set_last(&self.previous()).done(),
                 name: self.previous(),
+                depth: None,
             }));
         }
 
@@ -726,6 +1587,7 @@ impl Parser {
         } else {
             Err(Report::new(ParserError::PrimaryTokenError {
                 line: token.line,
+                span: Span::new(&token),
                 location: token.lexeme,
                 message: "Expected expression.".into(),
             }))
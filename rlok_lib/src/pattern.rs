@@ -0,0 +1,150 @@
+use super::expression::Expr;
+use super::lit::LitType;
+use super::numeric::Number;
+use super::span::Span;
+use super::statement::Statement;
+use super::tokens::Token;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub span: Span,
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Statement,
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// `_`, which matches anything.
+    Wildcard(Span),
+    /// A name, which matches anything and binds the value to it.
+    Binding(Token),
+    Literal(LitType, Span),
+    /// `start..end`, or `start..=end` when `inclusive`.
+    Range {
+        span: Span,
+        start: Number,
+        end: Number,
+        inclusive: bool,
+    },
+    /// `a | b`, which matches when any alternative does.
+    Or(Vec<Pattern>, Span),
+}
+
+impl Pattern {
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Binding(name) => name.into(),
+            Pattern::Wildcard(span)
+            | Pattern::Literal(_, span)
+            | Pattern::Range { span, .. }
+            | Pattern::Or(_, span) => *span,
+        }
+    }
+
+    pub fn binding(&self) -> Option<&Token> {
+        match self {
+            Pattern::Binding(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, value: &LitType) -> bool {
+        match self {
+            Pattern::Wildcard(_) | Pattern::Binding(_) => true,
+            Pattern::Literal(literal, _) => literal == value,
+            Pattern::Range {
+                span: _,
+                start,
+                end,
+                inclusive,
+            } => value
+                .number()
+                .is_some_and(|number| Self::in_range(number, *start, *end, *inclusive)),
+            Pattern::Or(alternatives, _) => {
+                alternatives.iter().any(|pattern| pattern.matches(value))
+            }
+        }
+    }
+
+    pub fn in_range(number: Number, start: Number, end: Number, inclusive: bool) -> bool {
+        let above = matches!(
+            number.compare(start),
+            Some(Ordering::Greater | Ordering::Equal)
+        );
+        let below = match number.compare(end) {
+            Some(Ordering::Less) => true,
+            Some(Ordering::Equal) => inclusive,
+            _ => false,
+        };
+        above && below
+    }
+
+    /// Whether every value `other` matches is also matched by this pattern, which
+    /// makes an arm with `other` after an unguarded arm with this one unreachable.
+    pub fn covers(&self, other: &Pattern) -> bool {
+        match (self, other) {
+            (Pattern::Wildcard(_) | Pattern::Binding(_), _) => true,
+            (_, Pattern::Or(alternatives, _)) => {
+                alternatives.iter().all(|pattern| self.covers(pattern))
+            }
+            (Pattern::Or(alternatives, _), _) => {
+                alternatives.iter().any(|pattern| pattern.covers(other))
+            }
+            (Pattern::Literal(literal, _), Pattern::Literal(other, _)) => literal == other,
+            (Pattern::Range { .. }, Pattern::Literal(literal, _)) => self.matches(literal),
+            (
+                Pattern::Range {
+                    span: _,
+                    start,
+                    end,
+                    inclusive,
+                },
+                Pattern::Range {
+                    span: _,
+                    start: other_start,
+                    end: other_end,
+                    inclusive: other_inclusive,
+                },
+            ) => {
+                let starts_after = matches!(
+                    other_start.compare(*start),
+                    Some(Ordering::Greater | Ordering::Equal)
+                );
+                let ends_before = match other_end.compare(*end) {
+                    Some(Ordering::Less) => true,
+                    Some(Ordering::Equal) => *inclusive || !other_inclusive,
+                    _ => false,
+                };
+                starts_after && ends_before
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Pattern::Wildcard(_) => write!(f, "_"),
+            Pattern::Binding(name) => write!(f, "{}", name.lexeme),
+            Pattern::Literal(literal, _) => write!(f, "{}", literal.quoted()),
+            Pattern::Range {
+                span: _,
+                start,
+                end,
+                inclusive,
+            } => write!(f, "{}..{}{}", start, if *inclusive { "=" } else { "" }, end),
+            Pattern::Or(alternatives, _) => {
+                for (i, pattern) in alternatives.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{}", pattern)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
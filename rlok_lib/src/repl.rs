@@ -0,0 +1,166 @@
+use super::ast_printer::AstPrinter;
+use super::interpreter::Interpreter;
+use super::scanner::Scanner;
+use super::tokens::TokenType;
+use color_eyre::eyre::Result;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::env;
+use std::path::PathBuf;
+use std::time::Instant;
+use tracing::trace;
+
+const PROMPT: &str = "> ";
+const CONTINUATION: &str = "... ";
+
+pub struct Repl {
+    editor: DefaultEditor,
+    history: Option<PathBuf>,
+}
+
+impl Repl {
+    pub fn build() -> Result<Self> {
+        let mut editor = DefaultEditor::new()?;
+        let history = env::var_os("RLOK_HISTORY").map(PathBuf::from).or_else(|| {
+            env::var_os("HOME")
+                .or_else(|| env::var_os("USERPROFILE"))
+                .map(|home| PathBuf::from(home).join(".rlok_history"))
+        });
+        if let Some(path) = &history {
+            // A missing history file just means this is the first session.
+            let _ = editor.load_history(path);
+        }
+        Ok(Repl { editor, history })
+    }
+
+    pub fn run(&mut self, inter: &mut Interpreter) -> Result<()> {
+        let result = self.read_eval_loop(inter);
+        if let Some(path) = &self.history {
+            if let Err(e) = self.editor.save_history(path) {
+                trace!(error = %e, "Unable to save history");
+            }
+        }
+        result
+    }
+
+    fn read_eval_loop(&mut self, inter: &mut Interpreter) -> Result<()> {
+        while let Some(entry) = self.read_entry()? {
+            if entry.trim().is_empty() {
+                continue;
+            }
+            self.editor.add_history_entry(entry.trim_end())?;
+            match entry.trim().strip_prefix(':') {
+                Some(command) => Self::command(inter, command)?,
+                None => inter.run(entry)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn command(inter: &mut Interpreter, command: &str) -> Result<()> {
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+        let file = inter.file().to_string();
+        let source = format!("{}\n", argument);
+        match name {
+            "env" => Self::print_scopes(inter),
+            "tokens" => Self::print_tokens(inter, &file, source),
+            "ast" => {
+                for stmt in inter.parse_source(&file, source).unwrap_or_default() {
+                    println!("{}", AstPrinter::statement(&stmt));
+                }
+            }
+            "load" if !argument.is_empty() => {
+                if let Err(e) = inter.load_file(argument) {
+                    inter.report(&e);
+                }
+            }
+            "time" if !argument.is_empty() => {
+                let start = Instant::now();
+                inter.run(source)?;
+                println!("took {:?}", start.elapsed());
+            }
+            "reset" => inter.reset(),
+            _ => eprintln!(
+                "Unknown command :{}. The commands are :env, :tokens <src>, :ast <src>, \
+                 :load <file>, :time <src> and :reset.",
+                command
+            ),
+        }
+        Ok(())
+    }
+
+    /// Prints every token but whitespace, with its position and any literal value.
+    fn print_tokens(inter: &mut Interpreter, file: &str, source: String) {
+        let Some(tokens) = inter.scan_source(file, source) else {
+            return;
+        };
+        for token in tokens {
+            if matches!(
+                token.ty,
+                TokenType::Space | TokenType::Tab | TokenType::NewLine | TokenType::CarriageReturn
+            ) {
+                continue;
+            }
+            let position = format!("{}:{}", token.line, token.column);
+            match token.literal {
+                Some(literal) => {
+                    println!(
+                        "{} {:?} {:?} {:?}",
+                        position, token.ty, token.lexeme, literal
+                    )
+                }
+                None => println!("{} {:?} {:?}", position, token.ty, token.lexeme),
+            }
+        }
+    }
+
+    /// Prints each scope's variables, innermost first. The builtins are listed
+    /// by name only.
+    pub(crate) fn print_scopes(inter: &Interpreter) {
+        let scopes = inter.scopes();
+        let Some((builtins, scopes)) = scopes.split_last() else {
+            return;
+        };
+        for (depth, scope) in scopes.iter().enumerate() {
+            if depth + 1 == scopes.len() {
+                println!("globals:");
+            } else {
+                println!("scope {}:", depth);
+            }
+            for (name, value) in scope {
+                println!("  {} = {}", name, value);
+            }
+        }
+        let names: Vec<&str> = builtins.iter().map(|(name, _)| name.as_str()).collect();
+        println!("builtins: {}", names.join(", "));
+    }
+
+    /// Reads lines until brackets and strings are closed, or a single line for
+    /// a `:` command. `None` means the
+    /// user pressed Ctrl-D; Ctrl-C throws away the entry being typed.
+    fn read_entry(&mut self) -> Result<Option<String>> {
+        let mut entry = String::new();
+        loop {
+            let prompt = if entry.is_empty() {
+                PROMPT
+            } else {
+                CONTINUATION
+            };
+            match self.editor.readline(prompt) {
+                Ok(line) => {
+                    let command = entry.is_empty() && line.trim_start().starts_with(':');
+                    entry.push_str(&line);
+                    entry.push('\n');
+                    if command || Scanner::is_complete(&entry) {
+                        return Ok(Some(entry));
+                    }
+                }
+                Err(ReadlineError::Interrupted) => entry.clear(),
+                Err(ReadlineError::Eof) => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
@@ -0,0 +1,531 @@
+use super::error_handler::ResolverError;
+use super::expression::Expr;
+use super::statement::Statement;
+use super::tokens::Token;
+use color_eyre::eyre::Report;
+use std::collections::HashMap;
+use tracing::{span, trace, Level};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Initializer,
+    Method,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+pub struct Resolver {
+    scopes: Vec<HashMap<String, bool>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    errors: Vec<Report>,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Resolver {
+            scopes: Vec::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// A resolver for code run inside scopes that already exist, such as an
+    /// expression evaluated where the debugger paused. `scopes` holds each
+    /// scope's names, outermost first.
+    pub fn within(scopes: Vec<Vec<String>>) -> Self {
+        let mut resolver = Resolver::new();
+        for names in scopes {
+            if names.iter().any(|name| name == "super") {
+                resolver.current_class = ClassType::Subclass;
+            } else if names.iter().any(|name| name == "this")
+                && resolver.current_class == ClassType::None
+            {
+                resolver.current_class = ClassType::Class;
+            }
+            resolver
+                .scopes
+                .push(names.into_iter().map(|name| (name, true)).collect());
+        }
+        resolver
+    }
+
+    pub fn resolve(mut self, statements: &mut [Statement]) -> Vec<Report> {
+        let span = span!(Level::TRACE, "resolving");
+        let _enter = span.enter();
+        for stmt in statements.iter_mut() {
+            self.resolve_statement(stmt);
+        }
+        self.errors
+    }
+
+    fn error(&mut self, error: ResolverError) {
+        trace!(error = %error, "Resolver error");
+        self.errors.push(Report::new(error));
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last() {
+            if scope.contains_key(&name.lexeme) {
+                self.error(ResolverError::AlreadyDeclared(
+                    name.lexeme.clone(),
+                    name.into(),
+                ));
+            }
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), false);
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), true);
+        }
+    }
+
+    fn resolve_local(&self, name: &Token) -> Option<usize> {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&name.lexeme) {
+                trace!(name = %name, depth, "Resolved local");
+                return Some(depth);
+            }
+        }
+        trace!(name = %name, "Resolved global");
+        None
+    }
+
+    fn resolve_function(
+        &mut self,
+        params: &[Token],
+        body: &mut [Statement],
+        function_type: FunctionType,
+    ) {
+        let enclosing_function = self.current_function;
+        self.current_function = function_type;
+        self.begin_scope();
+        for param in params {
+            self.declare(param);
+            self.define(param);
+        }
+        for stmt in body.iter_mut() {
+            self.resolve_statement(stmt);
+        }
+        self.end_scope();
+        self.current_function = enclosing_function;
+    }
+
+    fn resolve_statement(&mut self, stmt: &mut Statement) {
+        match stmt {
+            Statement::Expression {
+                span: _,
+                expression,
+            } => self.resolve_expr(expression),
+            Statement::Print {
+                span: _,
+                expression,
+            } => self.resolve_expr(expression),
+            Statement::Var {
+                span: _,
+                name,
+                expression,
+            } => {
+                self.declare(name);
+                if let Some(expr) = expression {
+                    self.resolve_expr(expr);
+                }
+                self.define(name);
+            }
+            Statement::Block {
+                span: _,
+                statements,
+            } => {
+                self.begin_scope();
+                for stmt in statements.iter_mut() {
+                    self.resolve_statement(stmt);
+                }
+                self.end_scope();
+            }
+            Statement::If {
+                span: _,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.resolve_expr(condition);
+                self.resolve_statement(then_branch);
+                if let Some(els) = else_branch {
+                    self.resolve_statement(els);
+                }
+            }
+            Statement::While {
+                span: _,
+                condition,
+                body,
+                label: _,
+            } => {
+                self.resolve_expr(condition);
+                self.resolve_statement(body);
+            }
+            Statement::For {
+                span: _,
+                initializer,
+                condition,
+                increment,
+                body,
+                label: _,
+            } => {
+                self.begin_scope();
+                if let Some(init) = initializer {
+                    self.resolve_statement(init);
+                }
+                if let Some(cond) = condition {
+                    self.resolve_expr(cond);
+                }
+                if let Some(inc) = increment {
+                    self.resolve_expr(inc);
+                }
+                self.resolve_statement(body);
+                self.end_scope();
+            }
+            Statement::Break { .. } | Statement::Continue { .. } => (),
+            Statement::Throw {
+                span: _,
+                keyword: _,
+                value,
+            } => self.resolve_expr(value),
+            Statement::Try {
+                span: _,
+                body,
+                catch,
+                finally,
+            } => {
+                self.resolve_statement(body);
+                if let Some((name, handler)) = catch {
+                    self.begin_scope();
+                    self.declare(name);
+                    self.define(name);
+                    self.resolve_statement(handler);
+                    self.end_scope();
+                }
+                if let Some(finally) = finally {
+                    self.resolve_statement(finally);
+                }
+            }
+            Statement::Match {
+                span: _,
+                keyword: _,
+                subject,
+                arms,
+            } => {
+                self.resolve_expr(subject);
+                for (i, arm) in arms.iter().enumerate() {
+                    let covering = arms[..i].iter().find(|earlier| {
+                        earlier.guard.is_none() && earlier.pattern.covers(&arm.pattern)
+                    });
+                    if let Some(earlier) = covering {
+                        self.error(ResolverError::UnreachableArm(
+                            earlier.pattern.to_string(),
+                            arm.span,
+                        ));
+                    }
+                }
+                for arm in arms.iter_mut() {
+                    self.begin_scope();
+                    if let Some(name) = arm.pattern.binding().cloned() {
+                        self.declare(&name);
+                        self.define(&name);
+                    }
+                    if let Some(guard) = &mut arm.guard {
+                        self.resolve_expr(guard);
+                    }
+                    self.resolve_statement(&mut arm.body);
+                    self.end_scope();
+                }
+            }
+            Statement::Import {
+                span: _,
+                path: _,
+                alias,
+                names,
+            } => {
+                for name in alias.iter().chain(names.iter()) {
+                    self.declare(name);
+                    self.define(name);
+                }
+            }
+            Statement::Function {
+                span: _,
+                name,
+                params,
+                body,
+            } => {
+                self.declare(name);
+                self.define(name);
+                self.resolve_function(params, body, FunctionType::Function);
+            }
+            Statement::Return {
+                span,
+                keyword: _,
+                value,
+            } => {
+                match self.current_function {
+                    FunctionType::None => {
+                        self.error(ResolverError::TopLevelReturn(*span));
+                    }
+                    FunctionType::Initializer if value.is_some() => {
+                        self.error(ResolverError::InitializerReturn(*span));
+                    }
+                    _ => (),
+                }
+                if let Some(value) = value {
+                    self.resolve_expr(value);
+                }
+            }
+            Statement::Class {
+                span: _,
+                name,
+                superclass,
+                methods,
+            } => {
+                let enclosing_class = self.current_class;
+                self.current_class = ClassType::Class;
+                self.declare(name);
+                self.define(name);
+
+                if let Some(superclass) = superclass {
+                    if let Expr::Variable {
+                        span: superclass_span,
+                        name: superclass_name,
+                        depth: _,
+                    } = superclass
+                    {
+                        if superclass_name.lexeme == name.lexeme {
+                            self.error(ResolverError::InheritFromSelf(*superclass_span));
+                        }
+                    }
+                    self.current_class = ClassType::Subclass;
+                    self.resolve_expr(superclass);
+                    self.begin_scope();
+                    if let Some(scope) = self.scopes.last_mut() {
+                        scope.insert("super".into(), true);
+                    }
+                }
+
+                self.begin_scope();
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert("this".into(), true);
+                }
+                for method in methods.iter_mut() {
+                    if let Statement::Function {
+                        span: _,
+                        name,
+                        params,
+                        body,
+                    } = method
+                    {
+                        let function_type = if name.lexeme == "init" {
+                            FunctionType::Initializer
+                        } else {
+                            FunctionType::Method
+                        };
+                        self.resolve_function(params, body, function_type);
+                    }
+                }
+                self.end_scope();
+
+                if superclass.is_some() {
+                    self.end_scope();
+                }
+                self.current_class = enclosing_class;
+            }
+        }
+    }
+
+    fn resolve_expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Binary {
+                span: _,
+                left,
+                operator: _,
+                right,
+            } => {
+                self.resolve_expr(left);
+                self.resolve_expr(right);
+            }
+            Expr::Grouping {
+                span: _,
+                expression,
+            } => self.resolve_expr(expression),
+            Expr::Literal { span: _, value: _ } => (),
+            Expr::Unary {
+                span: _,
+                operator: _,
+                right,
+            } => self.resolve_expr(right),
+            Expr::Variable { span, name, depth } => {
+                if let Some(scope) = self.scopes.last() {
+                    if scope.get(&name.lexeme) == Some(&false) {
+                        self.error(ResolverError::OwnInitializer(name.lexeme.clone(), *span));
+                    }
+                }
+                *depth = self.resolve_local(name);
+            }
+            Expr::Assign {
+                span: _,
+                name,
+                value,
+                depth,
+            } => {
+                self.resolve_expr(value);
+                *depth = self.resolve_local(name);
+            }
+            Expr::Increment {
+                span: _,
+                name,
+                operator: _,
+                prefix: _,
+                depth,
+            } => {
+                *depth = self.resolve_local(name);
+            }
+            Expr::Logcial {
+                span: _,
+                left,
+                operator: _,
+                right,
+            } => {
+                self.resolve_expr(left);
+                self.resolve_expr(right);
+            }
+            Expr::Conditional {
+                span: _,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.resolve_expr(condition);
+                self.resolve_expr(then_branch);
+                self.resolve_expr(else_branch);
+            }
+            Expr::Call {
+                span: _,
+                callee,
+                paren: _,
+                arguments,
+            } => {
+                self.resolve_expr(callee);
+                for arg in arguments.iter_mut() {
+                    self.resolve_expr(arg);
+                }
+            }
+            Expr::Get {
+                span: _,
+                object,
+                name: _,
+            } => self.resolve_expr(object),
+            Expr::Set {
+                span: _,
+                object,
+                name: _,
+                value,
+            } => {
+                self.resolve_expr(value);
+                self.resolve_expr(object);
+            }
+            Expr::List { span: _, elements } => {
+                for element in elements.iter_mut() {
+                    self.resolve_expr(element);
+                }
+            }
+            Expr::Stringify {
+                span: _,
+                expression,
+            } => self.resolve_expr(expression),
+            Expr::Map { span: _, entries } => {
+                for (key, value) in entries.iter_mut() {
+                    self.resolve_expr(key);
+                    self.resolve_expr(value);
+                }
+            }
+            Expr::Index {
+                span: _,
+                object,
+                bracket: _,
+                index,
+            } => {
+                self.resolve_expr(object);
+                self.resolve_expr(index);
+            }
+            Expr::SetIndex {
+                span: _,
+                object,
+                bracket: _,
+                index,
+                value,
+            } => {
+                self.resolve_expr(value);
+                self.resolve_expr(object);
+                self.resolve_expr(index);
+            }
+            Expr::This {
+                span,
+                keyword,
+                depth,
+            } => {
+                if self.current_class == ClassType::None {
+                    self.error(ResolverError::ThisOutsideClass(*span));
+                    return;
+                }
+                *depth = self.resolve_local(keyword);
+            }
+            Expr::Super {
+                span,
+                keyword,
+                method: _,
+                depth,
+            } => {
+                match self.current_class {
+                    ClassType::None => {
+                        self.error(ResolverError::SuperOutsideClass(*span));
+                    }
+                    ClassType::Class => {
+                        self.error(ResolverError::SuperWithoutSuperclass(*span));
+                    }
+                    ClassType::Subclass => (),
+                }
+                *depth = self.resolve_local(keyword);
+            }
+            Expr::Lambda {
+                span: _,
+                declaration,
+            } => {
+                if let Statement::Function {
+                    span: _,
+                    name: _,
+                    params,
+                    body,
+                } = &mut **declaration
+                {
+                    self.resolve_function(params, body, FunctionType::Function);
+                }
+            }
+        }
+    }
+}
@@ -1,13 +1,16 @@
 use super::error_handler::ScannerError;
+use super::span::Span;
 use super::tokens::{Token, TokenType};
 use color_eyre::eyre::{Report, Result};
 use std::collections::HashMap;
 
 pub struct Scanner {
     source: String,
-    start: i32,
-    current: i32,
+    start: usize,
+    current: usize,
     line: i32,
+    line_start: usize,
+    origin: usize,
     tokens: Vec<Token>,
     keywords: HashMap<String, TokenType>,
 }
@@ -16,19 +19,29 @@ impl Scanner {
     pub fn build(source: String) -> Self {
         let mut keywords = HashMap::new();
         keywords.insert("and".into(), TokenType::AND);
+        keywords.insert("as".into(), TokenType::AS);
+        keywords.insert("break".into(), TokenType::BREAK);
+        keywords.insert("catch".into(), TokenType::CATCH);
         keywords.insert("class".into(), TokenType::CLASS);
+        keywords.insert("continue".into(), TokenType::CONTINUE);
         keywords.insert("else".into(), TokenType::ELSE);
         keywords.insert("false".into(), TokenType::FALSE);
+        keywords.insert("finally".into(), TokenType::FINALLY);
         keywords.insert("for".into(), TokenType::FOR);
+        keywords.insert("from".into(), TokenType::FROM);
         keywords.insert("fun".into(), TokenType::FUN);
         keywords.insert("if".into(), TokenType::IF);
+        keywords.insert("import".into(), TokenType::IMPORT);
+        keywords.insert("match".into(), TokenType::MATCH);
         keywords.insert("nil".into(), TokenType::NIL);
         keywords.insert("or".into(), TokenType::OR);
         keywords.insert("print".into(), TokenType::PRINT);
         keywords.insert("return".into(), TokenType::RETURN);
         keywords.insert("super".into(), TokenType::SUPER);
         keywords.insert("this".into(), TokenType::THIS);
+        keywords.insert("throw".into(), TokenType::THROW);
         keywords.insert("true".into(), TokenType::TRUE);
+        keywords.insert("try".into(), TokenType::TRY);
         keywords.insert("var".into(), TokenType::VAR);
         keywords.insert("while".into(), TokenType::WHILE);
         Scanner {
@@ -36,121 +49,348 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            origin: 0,
             tokens: Vec::new(),
             keywords,
         }
     }
 
+    pub fn set_origin(mut self, origin: usize, line: i32) -> Self {
+        self.origin = origin;
+        self.line = line;
+        self
+    }
+
     pub fn scan_tokens(&mut self) -> Result<Vec<Token>> {
         while !self.is_end() {
             self.start = self.current;
             self.scan_token()?;
         }
-        self.tokens
-            .push(Token::new(TokenType::EOF, "".into(), None, self.line));
+        self.start = self.current;
+        self.tokens.push(self.make_token(TokenType::EOF, None));
         Ok(self.tokens.clone())
     }
 
+    /// Whether `source` could be run as it is, rather than ending inside a
+    /// string or with brackets still open. Other errors count as complete so
+    /// that they get reported.
+    pub fn is_complete(source: &str) -> bool {
+        let mut scanner = Scanner::build(source.into());
+        match scanner.scan_tokens() {
+            Ok(tokens) => {
+                let mut depth = 0;
+                for token in tokens {
+                    match token.ty {
+                        TokenType::LeftParen | TokenType::LeftBrace | TokenType::LeftBracket => {
+                            depth += 1
+                        }
+                        TokenType::RightParen | TokenType::RightBrace | TokenType::RightBracket => {
+                            depth -= 1
+                        }
+                        _ => (),
+                    }
+                }
+                depth <= 0
+            }
+            Err(_) => !scanner.is_end(),
+        }
+    }
+
     fn is_end(&self) -> bool {
-        self.current >= self.source.len() as i32
+        self.current >= self.source.len()
     }
 
     fn advance(&mut self) -> char {
-        let c = self.source.chars().nth(self.current as usize).unwrap();
-        self.current += 1;
+        let c = self.peek();
+        self.current += c.len_utf8();
         c
     }
 
-    fn add_token(&mut self, ty: TokenType) {
-        let text = &self.source[self.start as usize..self.current as usize];
-        self.tokens
-            .push(Token::new(ty, text.into(), None, self.line));
+    fn column(&self) -> i32 {
+        self.column_at(self.start)
     }
 
-    fn add_token_val(&mut self, ty: TokenType, value: &str) {
-        let text = &self.source[self.start as usize..self.current as usize];
-        self.tokens.push(Token::new(
+    fn column_at(&self, offset: usize) -> i32 {
+        self.source[self.line_start..offset].chars().count() as i32 + 1
+    }
+
+    fn span(&self) -> Span {
+        Span::at(
+            self.origin + self.start,
+            self.origin + self.current,
+            self.line,
+            self.column(),
+        )
+    }
+
+    /// A span that starts at `offset` on the current line and ends at `current`.
+    fn span_at(&self, offset: usize) -> Span {
+        Span::at(
+            self.origin + offset,
+            self.origin + self.current,
+            self.line,
+            self.column_at(offset),
+        )
+    }
+
+    fn make_token(&self, ty: TokenType, literal: Option<String>) -> Token {
+        let text = &self.source[self.start..self.current];
+        Token::new(
             ty,
             text.into(),
-            Some(value.to_string()),
+            literal,
             self.line,
-        ));
+            self.column(),
+            self.origin + self.start,
+        )
+    }
+
+    fn add_token(&mut self, ty: TokenType) {
+        let token = self.make_token(ty, None);
+        self.tokens.push(token);
+    }
+
+    fn add_token_val(&mut self, ty: TokenType, value: &str) {
+        let token = self.make_token(ty, Some(value.to_string()));
+        self.tokens.push(token);
     }
 
     fn match_char(&mut self, expected: char) -> bool {
-        if self.is_end() {
-            return false;
-        }
-        if self.source.chars().nth(self.current as usize).unwrap() != expected {
+        if self.is_end() || self.peek() != expected {
             return false;
         }
-        self.current += 1;
+        self.current += expected.len_utf8();
         true
     }
 
     fn peek(&self) -> char {
-        if self.is_end() {
-            '\0'
-        } else {
-            self.source.chars().nth(self.current as usize).unwrap()
-        }
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() as i32 {
-            return '\0';
-        }
-        self.source
-            .chars()
-            .nth((self.current + 1) as usize)
-            .unwrap()
+        self.source[self.current..].chars().nth(1).unwrap_or('\0')
+    }
+
+    fn string_error(&self, span: Span, message: &str) -> Report {
+        Report::new(ScannerError::UnterminatedString {
+            line: span.line(),
+            span,
+            message: message.into(),
+        })
+    }
+
+    fn push_string_piece(&mut self, ty: TokenType, value: String, start: Span) {
+        self.tokens.push(Token::new(
+            ty,
+            self.source[start.start() - self.origin..self.current].into(),
+            Some(value),
+            start.line(),
+            start.column(),
+            start.start(),
+        ));
     }
 
     fn string(&mut self) -> Result<()> {
-        while self.peek() != '"' && !self.is_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+        let span = self.span();
+        let mut piece = span;
+        let mut value = String::new();
+        loop {
+            if self.is_end() {
+                return Err(self.string_error(span, "Unterminated string."));
             }
+            match self.peek() {
+                '"' => {
+                    self.advance();
+                    break;
+                }
+                '\\' => {
+                    let c = self.escape()?;
+                    value.push(c);
+                }
+                '$' if self.peek_next() == '{' => {
+                    self.advance();
+                    self.advance();
+                    self.push_string_piece(TokenType::Interpolation, value.split_off(0), piece);
+                    self.interpolation(span)?;
+                    piece = self.span_at(self.current - 1);
+                }
+                c => {
+                    self.advance();
+                    if c == '\n' {
+                        self.line += 1;
+                        self.line_start = self.current;
+                    }
+                    value.push(c);
+                }
+            }
+        }
+        self.push_string_piece(TokenType::StringLit, value, piece);
+        Ok(())
+    }
+
+    fn interpolation(&mut self, string: Span) -> Result<()> {
+        let mut depth = 0;
+        loop {
+            if self.is_end() {
+                return Err(self.string_error(string, "Unterminated interpolation."));
+            }
+            match self.peek() {
+                '{' => depth += 1,
+                '}' if depth == 0 => {
+                    self.advance();
+                    return Ok(());
+                }
+                '}' => depth -= 1,
+                _ => (),
+            }
+            self.start = self.current;
+            self.scan_token()?;
+        }
+    }
+
+    fn escape(&mut self) -> Result<char> {
+        let start = self.current;
+        self.advance();
+        let c = match self.advance() {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            c @ ('\\' | '"' | '$') => c,
+            'u' => return self.unicode_escape(start),
+            _ => return Err(self.escape_error(start, "Unknown escape sequence.")),
+        };
+        Ok(c)
+    }
+
+    fn unicode_escape(&mut self, start: usize) -> Result<char> {
+        if !self.match_char('{') {
+            return Err(self.escape_error(start, "Expect '{' to start a unicode escape."));
+        }
+        let digits = self.current;
+        while self.peek().is_ascii_hexdigit() {
             self.advance();
         }
+        let hex = self.source[digits..self.current].to_string();
+        if !self.match_char('}') || hex.is_empty() || hex.len() > 6 {
+            return Err(self.escape_error(start, "Expect 1 to 6 hex digits in a unicode escape."));
+        }
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.escape_error(start, "Invalid unicode code point."))
+    }
 
-        if self.is_end() {
-            Err(Report::new(ScannerError::StringError {
-                line: self.line,
-                message: "Unterminated string.".into(),
-            }))
-        } else {
+    fn escape_error(&self, start: usize, message: &str) -> Report {
+        Report::new(ScannerError::InvalidEscape {
+            line: self.line,
+            span: self.span_at(start),
+            message: message.into(),
+        })
+    }
+
+    fn raw_string(&mut self) -> Result<()> {
+        let span = self.span();
+        let mut hashes = 0;
+        while self.match_char('#') {
+            hashes += 1;
+        }
+        if !self.match_char('"') {
+            return Err(self.string_error(span, "Expect '\"' to start a raw string."));
+        }
+        let content = self.current;
+        let closing = format!("\"{}", "#".repeat(hashes));
+        loop {
+            if self.is_end() {
+                return Err(self.string_error(span, "Unterminated raw string."));
+            }
+            if self.source[self.current..].starts_with(&closing) {
+                break;
+            }
+            if self.advance() == '\n' {
+                self.line += 1;
+                self.line_start = self.current;
+            }
+        }
+        let value = self.source[content..self.current].to_string();
+        self.current += closing.len();
+        self.push_string_piece(TokenType::StringLit, value, span);
+        Ok(())
+    }
+
+    /// Consumes a run of digits. Underscores are taken too, so that a badly
+    /// placed one is reported rather than starting an identifier.
+    fn digits(&mut self, is_digit: fn(char) -> bool) {
+        while is_digit(self.peek()) || self.peek() == '_' {
             self.advance();
+        }
+    }
+
+    fn number_error(&self, message: &str) -> Report {
+        Report::new(ScannerError::InvalidNumber {
+            line: self.line,
+            span: self.span(),
+            message: message.into(),
+        })
+    }
 
-            let value = &self.source[(self.start + 1) as usize..(self.current - 1) as usize];
-            self.add_token_val(TokenType::StringLit, &value.to_string());
-            Ok(())
+    /// Checks the literal scanned so far, whose digits start at `digits`.
+    /// Letters right after it are taken into the literal and make it invalid.
+    fn check_number(&mut self, digits: usize) -> Result<String> {
+        let letters = Self::is_alpha_numeric(self.peek());
+        while Self::is_alpha_numeric(self.peek()) {
+            self.advance();
         }
+        if letters {
+            return Err(self.number_error("Invalid number literal."));
+        }
+        let text = &self.source[digits..self.current];
+        if text.is_empty() {
+            return Err(self.number_error("Expect digits after '0x'."));
+        }
+        let misplaced = text.starts_with('_')
+            || text.ends_with('_')
+            || text.contains("__")
+            || text.contains("_.");
+        if misplaced {
+            return Err(self.number_error("'_' can only separate two digits."));
+        }
+        Ok(text.replace('_', ""))
     }
 
-    fn number(&mut self) {
-        while Self::is_digit(self.peek()) {
+    fn number(&mut self, first: char) -> Result<()> {
+        if first == '0' && matches!(self.peek(), 'x' | 'X') {
             self.advance();
+            self.digits(|c| c.is_ascii_hexdigit());
+            let digits = self.check_number(self.start + 2)?;
+            let value = i64::from_str_radix(&digits, 16)
+                .map_err(|_| self.number_error("Hex literal does not fit in an integer."))?;
+            self.add_token_val(TokenType::NumberLit, &value.to_string());
+            return Ok(());
         }
+        self.digits(Self::is_digit);
         if self.peek() == '.' && Self::is_digit(self.peek_next()) {
             self.advance();
-            while Self::is_digit(self.peek()) {
-                self.advance();
-            }
+            self.digits(Self::is_digit);
         }
-        let value = &self.source[self.start as usize..self.current as usize];
-        self.add_token_val(TokenType::NumberLit, &value.to_string());
+        let value = self.check_number(self.start)?;
+        if !value.contains('.') && value.parse::<i64>().is_err() {
+            return Err(self.number_error("Integer literal does not fit in an integer."));
+        }
+        self.add_token_val(TokenType::NumberLit, &value);
+        Ok(())
     }
 
     fn is_digit(c: char) -> bool {
-        c >= '0' && c <= '9'
+        c.is_ascii_digit()
     }
 
     fn identifier(&mut self) {
         while Self::is_alpha_numeric(self.peek()) {
             self.advance();
         }
-        let value = &self.source[self.start as usize..self.current as usize];
+        let value = &self.source[self.start..self.current];
         let ty = self.keywords.get(value);
         if let Some(t) = ty {
             self.add_token(t.clone());
@@ -160,7 +400,7 @@ impl Scanner {
     }
 
     fn is_alpha(c: char) -> bool {
-        (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || c == '_'
+        c.is_ascii_lowercase() || c.is_ascii_uppercase() || c == '_'
     }
 
     fn is_alpha_numeric(c: char) -> bool {
@@ -174,12 +414,65 @@ impl Scanner {
             ')' => self.add_token(TokenType::RightParen),
             '{' => self.add_token(TokenType::LeftBrace),
             '}' => self.add_token(TokenType::RightBrace),
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
-            '.' => self.add_token(TokenType::Dot),
-            '-' => self.add_token(TokenType::Minus),
-            '+' => self.add_token(TokenType::Plus),
+            '.' => {
+                if !self.match_char('.') {
+                    self.add_token(TokenType::Dot)
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::DotDotEqual)
+                } else {
+                    self.add_token(TokenType::DotDot)
+                }
+            }
+            '-' => {
+                if self.match_char('-') {
+                    self.add_token(TokenType::MinusMinus)
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::MinusEqual)
+                } else {
+                    self.add_token(TokenType::Minus)
+                }
+            }
+            '+' => {
+                if self.match_char('+') {
+                    self.add_token(TokenType::PlusPlus)
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::PlusEqual)
+                } else {
+                    self.add_token(TokenType::Plus)
+                }
+            }
             ';' => self.add_token(TokenType::Semicolon),
-            '*' => self.add_token(TokenType::Star),
+            ':' => self.add_token(TokenType::Colon),
+            '?' => self.add_token(TokenType::Question),
+            '&' => self.add_token(TokenType::Ampersand),
+            '|' => self.add_token(TokenType::Pipe),
+            '^' => self.add_token(TokenType::Caret),
+            '~' => {
+                if self.match_char('/') {
+                    self.add_token(TokenType::TildeSlash)
+                } else {
+                    self.add_token(TokenType::Tilde)
+                }
+            }
+            '*' => {
+                if self.match_char('*') {
+                    self.add_token(TokenType::StarStar)
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::StarEqual)
+                } else {
+                    self.add_token(TokenType::Star)
+                }
+            }
+            '%' => {
+                if self.match_char('=') {
+                    self.add_token(TokenType::PercentEqual)
+                } else {
+                    self.add_token(TokenType::Percent)
+                }
+            }
             '!' => {
                 if self.match_char('=') {
                     self.add_token(TokenType::BangEqual)
@@ -190,6 +483,8 @@ impl Scanner {
             '=' => {
                 if self.match_char('=') {
                     self.add_token(TokenType::EqualEqual)
+                } else if self.match_char('>') {
+                    self.add_token(TokenType::Arrow)
                 } else {
                     self.add_token(TokenType::Equal)
                 }
@@ -197,6 +492,8 @@ impl Scanner {
             '<' => {
                 if self.match_char('=') {
                     self.add_token(TokenType::LessEqual)
+                } else if self.match_char('<') {
+                    self.add_token(TokenType::LessLess)
                 } else {
                     self.add_token(TokenType::Less)
                 }
@@ -204,6 +501,8 @@ impl Scanner {
             '>' => {
                 if self.match_char('=') {
                     self.add_token(TokenType::GreaterEqual)
+                } else if self.match_char('>') {
+                    self.add_token(TokenType::GreaterGreater)
                 } else {
                     self.add_token(TokenType::Greater)
                 }
@@ -213,6 +512,8 @@ impl Scanner {
                     while self.peek() != '\n' && !self.is_end() {
                         self.advance();
                     }
+                } else if self.match_char('=') {
+                    self.add_token(TokenType::SlashEqual)
                 } else {
                     self.add_token(TokenType::Slash)
                 }
@@ -224,15 +525,19 @@ impl Scanner {
             '\n' => {
                 self.add_token(TokenType::NewLine);
                 self.line += 1;
+                self.line_start = self.current;
             }
             _ => {
                 if Self::is_digit(c) {
-                    self.number();
+                    self.number(c)?;
+                } else if c == 'r' && matches!(self.peek(), '"' | '#') {
+                    self.raw_string()?;
                 } else if Self::is_alpha(c) {
                     self.identifier();
                 } else {
-                    return Err(Report::new(ScannerError::UnexpectedTokenError {
+                    return Err(Report::new(ScannerError::UnexpectedToken {
                         line: self.line,
+                        span: self.span(),
                         message: "Unexpected token".into(),
                     }));
                 }
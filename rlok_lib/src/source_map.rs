@@ -0,0 +1,78 @@
+/// The name REPL entries are scanned under. They read as one source that grows
+/// with each entry, so their line numbers carry on from the entry before.
+pub const REPL: &str = "<repl>";
+
+/// Every file (and REPL entry) the interpreter has scanned, laid end to end so
+/// that a span's byte offset identifies both the file and the position in it.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    len: usize,
+}
+
+#[derive(Debug)]
+pub struct SourceFile {
+    name: String,
+    origin: usize,
+    text: String,
+}
+
+impl SourceMap {
+    /// Adds a source and returns the origin offset and first line to scan it with.
+    /// A file starts at line 1 each time it's added, while REPL entries continue
+    /// the line numbers of the entries before them.
+    pub fn add(&mut self, name: &str, text: &str) -> (usize, i32) {
+        let line = if name == REPL {
+            self.entries(REPL)
+                .map(|file| file.text.matches('\n').count() as i32)
+                .sum::<i32>()
+                + 1
+        } else {
+            1
+        };
+        let origin = self.len;
+        self.files.push(SourceFile {
+            name: name.into(),
+            origin,
+            text: text.into(),
+        });
+        self.len += text.len();
+        (origin, line)
+    }
+
+    /// Line `line` of the source named `name`: of its latest copy for a file, or
+    /// counting across every entry for the REPL.
+    pub fn line(&self, name: &str, line: i32) -> Option<&str> {
+        let index = usize::try_from(line - 1).ok()?;
+        if name == REPL {
+            self.entries(REPL)
+                .flat_map(|file| file.text.lines())
+                .nth(index)
+        } else {
+            let file = self.files.iter().rev().find(|file| file.name == name)?;
+            file.text.lines().nth(index)
+        }
+    }
+
+    fn entries<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a SourceFile> {
+        self.files.iter().filter(move |file| file.name == name)
+    }
+
+    pub fn find(&self, offset: usize) -> Option<&SourceFile> {
+        self.files.iter().rev().find(|file| file.origin <= offset)
+    }
+}
+
+impl SourceFile {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn origin(&self) -> usize {
+        self.origin
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
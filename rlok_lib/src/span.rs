@@ -1,156 +1,66 @@
-use super::tokens::{Token, TokenType};
-use tracing::trace;
+use super::tokens::Token;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct Span {
-    first: i32,
-    last: i32,
+    start: usize,
+    end: usize,
+    line: i32,
+    column: i32,
 }
 
-pub struct SpanParser;
-
-impl SpanParser {
-    fn get_underline(space_before: usize, num_underline: usize) -> String {
-        let mut output = String::from("");
-        for _ in 0..space_before {
-            output = format!("{} ", output);
-        }
-        for _ in 0..num_underline {
-            output = format!("{}^", output);
+impl Span {
+    pub fn new(first: &Token) -> Self {
+        Span {
+            start: first.offset,
+            end: first.offset + first.lexeme.len(),
+            line: first.line,
+            column: first.column,
         }
-        format!("{}--- Here", output)
     }
-    pub fn parse(span: Span, tokens: Vec<Token>) -> String {
-        let mut underline_space_before = 0;
-        let mut num_underline = 0;
-        let mut start = span.first();
-        let mut end = span.last();
-        loop {
-            if let Some(token) = tokens.get((start - 1) as usize) {
-                if matches!(token.ty, TokenType::NewLine) {
-                    break;
-                } else {
-                    start -= 1;
-                }
-            } else {
-                break;
-            }
-        }
-        loop {
-            if let Some(token) = tokens.get((end + 1) as usize) {
-                if matches!(token.ty, TokenType::NewLine) || matches!(token.ty, TokenType::EOF) {
-                    break;
-                } else {
-                    end += 1;
-                }
-            } else {
-                break;
-            }
-        }
 
-        if let Some(span_tokens) = tokens.get((span.first() - 1) as usize..span.last() as usize) {
-            span_tokens
-                .into_iter()
-                .reduce(|_, token| {
-                    num_underline += token.lexeme.len();
-                    token
-                })
-                .unwrap();
+    pub fn at(start: usize, end: usize, line: i32, column: i32) -> Self {
+        Span {
+            start,
+            end,
+            line,
+            column,
         }
+    }
 
-        if let Some(span_tokens) = tokens.get(start as usize..span.first() as usize) {
-            let _output = span_tokens.into_iter().fold(String::new(), |accu, token| {
-                if accu.len() <= 0 {
-                    if let Some(prev_token) = tokens.get(start as usize) {
-                        let prepend = format!("\n{}| {}", token.line, prev_token);
-                        underline_space_before += prepend.len();
-                        format!("\n{}|{}{}", token.line - 1, prepend, token.lexeme)
-                    } else {
-                        let prepend = format!("\n{}| ", token.line);
-                        underline_space_before += prepend.len();
-                        format!("\n{}|{}{}", token.line - 1, prepend, token.lexeme)
-                    }
-                } else {
-                    underline_space_before += token.lexeme.len();
-                    format!("{}{}", accu, token.lexeme)
-                }
-            });
-        }
-        if let Some(span_tokens) = tokens.get(start as usize..end as usize) {
-            let output = span_tokens.into_iter().fold(String::new(), |accu, token| {
-                if accu.len() <= 0 {
-                    if let Some(prev_token) = tokens.get(start as usize) {
-                        let prepend = format!("\n{}| {}", token.line, prev_token);
-                        format!("\n{}|{}{}", token.line - 1, prepend, token.lexeme)
-                    } else {
-                        let prepend = format!("\n{}| ", token.line);
-                        format!("\n{}|{}{}", token.line - 1, prepend, token.lexeme)
-                    }
-                } else {
-                    format!("{}{}", accu, token.lexeme)
-                }
-            });
-            if let Some(last_token) = tokens.get(end as usize) {
-                let prepend = format!("{}|", last_token.line + 1);
-                format!(
-                    "{}{}\n{}{}",
-                    output,
-                    last_token,
-                    prepend,
-                    SpanParser::get_underline(
-                        underline_space_before - prepend.len(),
-                        num_underline
-                    )
-                )
-            } else {
-                format!(
-                    "{}{}",
-                    output,
-                    SpanParser::get_underline(underline_space_before, num_underline)
-                )
-            }
-        } else {
-            format!("TOKEN OUT OF BOUNDS")
-        }
+    pub fn set_last(&mut self, last: &Token) -> &Span {
+        self.end = (last.offset + last.lexeme.len()).max(self.start);
+        self
     }
-}
 
-impl Span {
-    pub fn new(first: i32) -> Self {
-        Span {
-            first,
-            last: Default::default(),
-        }
+    pub fn done(&self) -> Span {
+        *self
     }
 
-    pub fn set_first_and_last(&mut self, first: i32, last: i32) -> &mut Span {
-        self.first = first;
-        self.last = last;
-        self
+    pub fn start(&self) -> usize {
+        self.start
     }
 
-    pub fn set_first(mut self, first: i32) -> Span {
-        self.first = first;
-        self
+    pub fn end(&self) -> usize {
+        self.end
     }
 
-    pub fn set_last(&mut self, last: i32) -> &Span {
-        self.last = last;
-        self
+    pub fn line(&self) -> i32 {
+        self.line
     }
 
-    pub fn done(&self) -> Span {
-        Span {
-            first: self.first,
-            last: self.last,
-        }
+    pub fn column(&self) -> i32 {
+        self.column
     }
+}
 
-    pub fn first(&self) -> i32 {
-        self.first
+impl From<&Token> for Span {
+    fn from(token: &Token) -> Self {
+        Span::new(token)
     }
+}
 
-    pub fn last(&self) -> i32 {
-        self.last
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
     }
 }
@@ -1,4 +1,5 @@
 use super::expression::Expr;
+use super::pattern::MatchArm;
 use super::span::Span;
 use super::tokens::Token;
 
@@ -19,7 +20,7 @@ pub enum Statement {
     },
     Block {
         span: Span,
-        statements: Vec<Box<Statement>>,
+        statements: Vec<Statement>,
     },
     If {
         span: Span,
@@ -31,18 +32,89 @@ pub enum Statement {
         span: Span,
         condition: Expr,
         body: Box<Statement>,
+        label: Option<Token>,
+    },
+    For {
+        span: Span,
+        initializer: Option<Box<Statement>>,
+        condition: Option<Expr>,
+        increment: Option<Expr>,
+        body: Box<Statement>,
+        label: Option<Token>,
+    },
+    Break {
+        span: Span,
+        keyword: Token,
+        label: Option<Token>,
+    },
+    Continue {
+        span: Span,
+        keyword: Token,
+        label: Option<Token>,
     },
     Function {
         span: Span,
         name: Token,
         params: Vec<Token>,
-        body: Vec<Box<Statement>>,
+        body: Vec<Statement>,
     },
     Return {
+        span: Span,
+        keyword: Token,
+        value: Option<Expr>,
+    },
+    Class {
+        span: Span,
+        name: Token,
+        superclass: Option<Expr>,
+        methods: Vec<Statement>,
+    },
+    Import {
+        span: Span,
+        path: Token,
+        alias: Option<Token>,
+        names: Vec<Token>,
+    },
+    Throw {
         span: Span,
         keyword: Token,
         value: Expr,
     },
+    Try {
+        span: Span,
+        body: Box<Statement>,
+        catch: Option<(Token, Box<Statement>)>,
+        finally: Option<Box<Statement>>,
+    },
+    Match {
+        span: Span,
+        keyword: Token,
+        subject: Expr,
+        arms: Vec<MatchArm>,
+    },
+}
+
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Expression { span, .. }
+            | Statement::Print { span, .. }
+            | Statement::Var { span, .. }
+            | Statement::Block { span, .. }
+            | Statement::If { span, .. }
+            | Statement::While { span, .. }
+            | Statement::For { span, .. }
+            | Statement::Break { span, .. }
+            | Statement::Continue { span, .. }
+            | Statement::Function { span, .. }
+            | Statement::Return { span, .. }
+            | Statement::Class { span, .. }
+            | Statement::Import { span, .. }
+            | Statement::Throw { span, .. }
+            | Statement::Try { span, .. }
+            | Statement::Match { span, .. } => *span,
+        }
+    }
 }
 
 impl std::fmt::Display for Statement {
@@ -65,8 +137,8 @@ impl std::fmt::Display for Statement {
                 span: _,
                 statements,
             } => {
-                let statements_out = statements.into_iter().fold(String::new(), |acc, stmt| {
-                    if acc.len() > 0 {
+                let statements_out = statements.iter().fold(String::new(), |acc, stmt| {
+                    if !acc.is_empty() {
                         format!("{:?}, {:?}", acc, stmt)
                     } else {
                         format!("{{ {:?} }}", stmt)
@@ -94,24 +166,55 @@ impl std::fmt::Display for Statement {
                 span: _,
                 condition,
                 body,
+                label: _,
             } => {
                 write!(f, "{{ while {:?} do {:?} }}", condition, body)
             }
+            Statement::For {
+                span: _,
+                initializer,
+                condition,
+                increment,
+                body,
+                label: _,
+            } => {
+                write!(
+                    f,
+                    "{{ for {:?}; {:?}; {:?} do {:?} }}",
+                    initializer, condition, increment, body
+                )
+            }
+            Statement::Break {
+                span: _,
+                keyword: _,
+                label,
+            } => match label {
+                Some(label) => write!(f, "{{ break {} }}", label.lexeme),
+                None => write!(f, "{{ break }}"),
+            },
+            Statement::Continue {
+                span: _,
+                keyword: _,
+                label,
+            } => match label {
+                Some(label) => write!(f, "{{ continue {} }}", label.lexeme),
+                None => write!(f, "{{ continue }}"),
+            },
             Statement::Function {
                 span: _,
                 name,
                 params,
                 body: _,
             } => {
-                let params_output = params.into_iter().fold(String::new(), |acc, token| {
-                    if acc.len() > 0 {
+                let params_output = params.iter().fold(String::new(), |acc, token| {
+                    if !acc.is_empty() {
                         format!("{}, {:?}", acc, token)
                     } else {
                         format!("{:?}", token)
                     }
                 });
-                let body_output = params.into_iter().fold(String::new(), |acc, body| {
-                    if acc.len() > 0 {
+                let body_output = params.iter().fold(String::new(), |acc, body| {
+                    if !acc.is_empty() {
                         format!("{}, {:?}", acc, body)
                     } else {
                         format!("{:?}", body)
@@ -130,6 +233,79 @@ impl std::fmt::Display for Statement {
             } => {
                 write!(f, "{{ keyword {:?} value {:?} }}", keyword, value)
             }
+            Statement::Class {
+                span: _,
+                name,
+                superclass,
+                methods,
+            } => {
+                let methods_output = methods.iter().fold(String::new(), |acc, method| {
+                    if !acc.is_empty() {
+                        format!("{}, {}", acc, method)
+                    } else {
+                        format!("{}", method)
+                    }
+                });
+                if let Some(sup) = superclass {
+                    write!(
+                        f,
+                        "{{ class {:?} < {:?} methods {} }}",
+                        name, sup, methods_output
+                    )
+                } else {
+                    write!(f, "{{ class {:?} methods {} }}", name, methods_output)
+                }
+            }
+            Statement::Import {
+                span: _,
+                path,
+                alias,
+                names,
+            } => {
+                let path = path.literal.clone().unwrap_or_default();
+                if let Some(alias) = alias {
+                    write!(f, "{{ import {:?} as {} }}", path, alias.lexeme)
+                } else {
+                    let names: Vec<&str> = names.iter().map(|name| name.lexeme.as_str()).collect();
+                    write!(f, "{{ from {:?} import {} }}", path, names.join(", "))
+                }
+            }
+            Statement::Throw {
+                span: _,
+                keyword: _,
+                value,
+            } => write!(f, "{{ throw {} }}", value),
+            Statement::Try {
+                span: _,
+                body,
+                catch,
+                finally,
+            } => {
+                write!(f, "{{ try {}", body)?;
+                if let Some((name, handler)) = catch {
+                    write!(f, " catch {} {}", name.lexeme, handler)?;
+                }
+                if let Some(finally) = finally {
+                    write!(f, " finally {}", finally)?;
+                }
+                write!(f, " }}")
+            }
+            Statement::Match {
+                span: _,
+                keyword: _,
+                subject,
+                arms,
+            } => {
+                write!(f, "{{ match {:?}", subject)?;
+                for arm in arms {
+                    write!(f, " {}", arm.pattern)?;
+                    if let Some(guard) = &arm.guard {
+                        write!(f, " if {:?}", guard)?;
+                    }
+                    write!(f, " => {}", arm.body)?;
+                }
+                write!(f, " }}")
+            }
         }
     }
 }
@@ -0,0 +1,254 @@
+use super::error_handler::RuntimeError;
+use super::interpreter::Interpreter;
+use super::lit::LitType;
+use super::lox_callable::LoxCallable;
+use super::lox_list::LoxList;
+use super::lox_map::LoxMap;
+use super::numeric::Number;
+use color_eyre::eyre::{Report, Result};
+use std::cmp::Ordering;
+use std::io;
+use std::time::SystemTime;
+
+pub fn register(inter: &mut Interpreter) {
+    inter.register_native("clock", 0, clock);
+    inter.register_native("len", 1, len);
+    inter.register_native("substr", 3, substr);
+    inter.register_native("str", 1, str);
+    inter.register_native("num", 1, num);
+    inter.register_native("floor", 1, |_, args| round("floor", &args[0], f64::floor));
+    inter.register_native("ceil", 1, |_, args| round("ceil", &args[0], f64::ceil));
+    inter.register_native("sqrt", 1, sqrt);
+    inter.register_native("abs", 1, abs);
+    inter.register_native("pow", 2, pow);
+    inter.register_native("min", 2, |_, args| pick("min", args, Ordering::Less));
+    inter.register_native("max", 2, |_, args| pick("max", args, Ordering::Greater));
+    inter.register_native("type_of", 1, |_, args| {
+        Ok(LitType::Str(type_name(&args[0]).into()))
+    });
+    inter.register_native("input", 0, input);
+    inter.register_native("push", 2, push);
+    inter.register_native("pop", 1, pop);
+    inter.register_native("insert", 3, insert);
+    inter.register_native("remove", 2, remove);
+    inter.register_native("keys", 1, |_, args| {
+        Ok(LitType::List(LoxList::new(map("keys", &args[0])?.keys())))
+    });
+    inter.register_native("values", 1, |_, args| {
+        Ok(LitType::List(LoxList::new(
+            map("values", &args[0])?.values(),
+        )))
+    });
+    inter.register_native("has", 2, |_, args| {
+        Ok(LitType::Bool(map("has", &args[0])?.has(&args[1])))
+    });
+    inter.register_native("delete", 2, |_, args| {
+        Ok(LitType::Bool(
+            map("delete", &args[0])?.delete(&args[1]).is_some(),
+        ))
+    });
+}
+
+pub fn type_name(value: &LitType) -> &'static str {
+    match value {
+        LitType::Int(_) => "int",
+        LitType::Float(_) => "float",
+        LitType::Str(_) => "string",
+        LitType::Bool(_) => "bool",
+        LitType::Callable(LoxCallable::Class(_)) => "class",
+        LitType::Callable(_) => "function",
+        LitType::Instance(_) => "instance",
+        LitType::List(_) => "list",
+        LitType::Map(_) => "map",
+        LitType::Module(_) => "module",
+        LitType::Nil => "nil",
+    }
+}
+
+fn expected(name: &str, expected: &str, got: &LitType) -> Report {
+    Report::new(RuntimeError::native(format!(
+        "{}() expects {}, got {}",
+        name,
+        expected,
+        type_name(got)
+    )))
+}
+
+fn number(name: &str, value: &LitType) -> Result<Number> {
+    value
+        .number()
+        .ok_or_else(|| expected(name, "a number", value))
+}
+
+fn overflow(name: &str) -> Report {
+    Report::new(RuntimeError::native(format!(
+        "{}() overflowed the integer range",
+        name
+    )))
+}
+
+fn index(name: &str, value: &LitType) -> Result<usize> {
+    match value {
+        LitType::Int(i) if *i >= 0 => Ok(*i as usize),
+        LitType::Int(_) => Err(Report::new(RuntimeError::native(format!(
+            "{}() expects a non-negative int, got {}",
+            name, value
+        )))),
+        other => Err(expected(name, "an int", other)),
+    }
+}
+
+fn clock(_inter: &mut Interpreter, _args: Vec<LitType>) -> Result<LitType> {
+    match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(time) => Ok(LitType::Float(time.as_secs_f64())),
+        Err(e) => Err(Report::new(RuntimeError::native(e.to_string()))),
+    }
+}
+
+fn len(_inter: &mut Interpreter, args: Vec<LitType>) -> Result<LitType> {
+    match &args[0] {
+        LitType::Str(s) => Ok(LitType::Int(s.chars().count() as i64)),
+        LitType::List(list) => Ok(LitType::Int(list.len() as i64)),
+        LitType::Map(map) => Ok(LitType::Int(map.len() as i64)),
+        other => Err(expected("len", "a string, list or map", other)),
+    }
+}
+
+fn substr(_inter: &mut Interpreter, args: Vec<LitType>) -> Result<LitType> {
+    let LitType::Str(s) = &args[0] else {
+        return Err(expected("substr", "a string", &args[0]));
+    };
+    let start = index("substr", &args[1])?;
+    let length = index("substr", &args[2])?;
+    Ok(LitType::Str(s.chars().skip(start).take(length).collect()))
+}
+
+fn str(_inter: &mut Interpreter, args: Vec<LitType>) -> Result<LitType> {
+    Ok(LitType::Str(args[0].to_string()))
+}
+
+fn num(_inter: &mut Interpreter, args: Vec<LitType>) -> Result<LitType> {
+    match &args[0] {
+        LitType::Int(_) | LitType::Float(_) => Ok(args[0].clone()),
+        LitType::Str(s) => {
+            let s = s.trim().replace('_', "");
+            if let Ok(i) = s.parse::<i64>() {
+                Ok(LitType::Int(i))
+            } else {
+                Ok(s.parse::<f64>().map_or(LitType::Nil, LitType::Float))
+            }
+        }
+        other => Err(expected("num", "a string or number", other)),
+    }
+}
+
+fn round(name: &str, value: &LitType, op: fn(f64) -> f64) -> Result<LitType> {
+    match number(name, value)? {
+        Number::Int(i) => Ok(LitType::Int(i)),
+        Number::Float(f) => {
+            let rounded = op(f);
+            if rounded.is_finite() && rounded.abs() < i64::MAX as f64 {
+                Ok(LitType::Int(rounded as i64))
+            } else {
+                Ok(LitType::Float(rounded))
+            }
+        }
+    }
+}
+
+fn sqrt(_inter: &mut Interpreter, args: Vec<LitType>) -> Result<LitType> {
+    Ok(LitType::Float(number("sqrt", &args[0])?.as_float().sqrt()))
+}
+
+fn abs(_inter: &mut Interpreter, args: Vec<LitType>) -> Result<LitType> {
+    match number("abs", &args[0])? {
+        Number::Int(i) => i
+            .checked_abs()
+            .map(LitType::Int)
+            .ok_or_else(|| overflow("abs")),
+        Number::Float(f) => Ok(LitType::Float(f.abs())),
+    }
+}
+
+fn pick(name: &str, args: Vec<LitType>, keep: Ordering) -> Result<LitType> {
+    let left = number(name, &args[0])?;
+    let right = number(name, &args[1])?;
+    if left.compare(right) == Some(keep.reverse()) {
+        Ok(right.into())
+    } else {
+        Ok(left.into())
+    }
+}
+
+fn pow(_inter: &mut Interpreter, args: Vec<LitType>) -> Result<LitType> {
+    number("pow", &args[0])?
+        .checked_pow(number("pow", &args[1])?)
+        .map(LitType::from)
+        .map_err(|_| overflow("pow"))
+}
+
+fn input(_inter: &mut Interpreter, _args: Vec<LitType>) -> Result<LitType> {
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        return Ok(LitType::Nil);
+    }
+    Ok(LitType::Str(
+        line.trim_end_matches(['\n', '\r']).to_string(),
+    ))
+}
+
+fn list<'a>(name: &str, value: &'a LitType) -> Result<&'a LoxList> {
+    match value {
+        LitType::List(list) => Ok(list),
+        other => Err(expected(name, "a list", other)),
+    }
+}
+
+fn map<'a>(name: &str, value: &'a LitType) -> Result<&'a LoxMap> {
+    match value {
+        LitType::Map(map) => Ok(map),
+        other => Err(expected(name, "a map", other)),
+    }
+}
+
+fn position(name: &str, value: &LitType) -> Result<i64> {
+    match value {
+        LitType::Int(i) => Ok(*i),
+        other => Err(expected(name, "an int index", other)),
+    }
+}
+
+fn out_of_bounds(name: &str, index: i64, list: &LoxList) -> Report {
+    Report::new(RuntimeError::native(format!(
+        "{}() index {} is out of bounds for a list of length {}",
+        name,
+        index,
+        list.len()
+    )))
+}
+
+fn push(_inter: &mut Interpreter, args: Vec<LitType>) -> Result<LitType> {
+    list("push", &args[0])?.push(args[1].clone());
+    Ok(LitType::Nil)
+}
+
+fn pop(_inter: &mut Interpreter, args: Vec<LitType>) -> Result<LitType> {
+    list("pop", &args[0])?
+        .pop()
+        .ok_or_else(|| Report::new(RuntimeError::native("pop() called on an empty list")))
+}
+
+fn insert(_inter: &mut Interpreter, args: Vec<LitType>) -> Result<LitType> {
+    let list = list("insert", &args[0])?;
+    let index = position("insert", &args[1])?;
+    list.insert(index, args[2].clone())
+        .ok_or_else(|| out_of_bounds("insert", index, list))?;
+    Ok(LitType::Nil)
+}
+
+fn remove(_inter: &mut Interpreter, args: Vec<LitType>) -> Result<LitType> {
+    let list = list("remove", &args[0])?;
+    let index = position("remove", &args[1])?;
+    list.remove(index)
+        .ok_or_else(|| out_of_bounds("remove", index, list))
+}
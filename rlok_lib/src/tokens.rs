@@ -1,5 +1,4 @@
-use std::fmt::{self, Write};
-
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     // Signel-character tokens
@@ -11,21 +10,42 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
+    MinusMinus,
+    MinusEqual,
     Plus,
+    PlusPlus,
+    PlusEqual,
     Semicolon,
     Slash,
+    SlashEqual,
     Star,
-    // Colon,
-    // Question,
+    StarStar,
+    StarEqual,
+    Percent,
+    PercentEqual,
+    Colon,
+    DotDot,
+    DotDotEqual,
+    Question,
+    TildeSlash,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
+    LessLess,
+    GreaterGreater,
 
     // One or two character tokens
     Bang,
     BangEqual,
     Equal,
     EqualEqual,
+    Arrow,
     Greater,
     GreaterEqual,
     Less,
@@ -34,23 +54,34 @@ pub enum TokenType {
     // Literals
     Ident,
     StringLit,
+    Interpolation,
     NumberLit,
 
     // Keywords
     AND,
+    AS,
+    BREAK,
+    CATCH,
     CLASS,
+    CONTINUE,
     ELSE,
     FALSE,
+    FINALLY,
+    FROM,
     FUN,
     FOR,
     IF,
+    IMPORT,
+    MATCH,
     NIL,
     OR,
     PRINT,
     RETURN,
     SUPER,
     THIS,
+    THROW,
     TRUE,
+    TRY,
     VAR,
     WHILE,
 
@@ -63,15 +94,26 @@ pub struct Token {
     pub lexeme: String,
     pub literal: Option<String>,
     pub line: i32,
+    pub column: i32,
+    pub offset: usize,
 }
 
 impl Token {
-    pub fn new(ty: TokenType, lexeme: String, literal: Option<String>, line: i32) -> Self {
+    pub fn new(
+        ty: TokenType,
+        lexeme: String,
+        literal: Option<String>,
+        line: i32,
+        column: i32,
+        offset: usize,
+    ) -> Self {
         Token {
             ty,
             lexeme,
             literal,
             line,
+            column,
+            offset,
         }
     }
 }
@@ -87,38 +129,72 @@ impl std::fmt::Display for TokenType {
             TokenType::RightParen => write!(f, "("),
             TokenType::LeftBrace => write!(f, "{{"),
             TokenType::RightBrace => write!(f, "}}"),
+            TokenType::LeftBracket => write!(f, "["),
+            TokenType::RightBracket => write!(f, "]"),
             TokenType::Comma => write!(f, ","),
             TokenType::Dot => write!(f, "."),
             TokenType::Minus => write!(f, "-"),
+            TokenType::MinusMinus => write!(f, "--"),
+            TokenType::MinusEqual => write!(f, "-="),
             TokenType::Plus => write!(f, "+"),
+            TokenType::PlusPlus => write!(f, "++"),
+            TokenType::PlusEqual => write!(f, "+="),
             TokenType::Semicolon => write!(f, ";"),
             TokenType::Slash => write!(f, "/"),
+            TokenType::SlashEqual => write!(f, "/="),
             TokenType::Star => write!(f, "*"),
+            TokenType::StarStar => write!(f, "**"),
+            TokenType::StarEqual => write!(f, "*="),
+            TokenType::Percent => write!(f, "%"),
+            TokenType::PercentEqual => write!(f, "%="),
+            TokenType::Colon => write!(f, ":"),
+            TokenType::DotDot => write!(f, ".."),
+            TokenType::DotDotEqual => write!(f, "..="),
+            TokenType::Question => write!(f, "?"),
+            TokenType::TildeSlash => write!(f, "~/"),
+            TokenType::Ampersand => write!(f, "&"),
+            TokenType::Pipe => write!(f, "|"),
+            TokenType::Caret => write!(f, "^"),
+            TokenType::Tilde => write!(f, "~"),
+            TokenType::LessLess => write!(f, "<<"),
+            TokenType::GreaterGreater => write!(f, ">>"),
             TokenType::Bang => write!(f, "!"),
             TokenType::BangEqual => write!(f, "!="),
             TokenType::Equal => write!(f, "="),
             TokenType::EqualEqual => write!(f, "=="),
+            TokenType::Arrow => write!(f, "=>"),
             TokenType::Greater => write!(f, ">"),
             TokenType::GreaterEqual => write!(f, ">="),
             TokenType::Less => write!(f, "<"),
             TokenType::LessEqual => write!(f, "<="),
             TokenType::Ident => write!(f, "Ident"),
             TokenType::StringLit => write!(f, "StringLit"),
+            TokenType::Interpolation => write!(f, "Interpolation"),
             TokenType::NumberLit => write!(f, "NumberLit"),
             TokenType::AND => write!(f, "AND"),
+            TokenType::AS => write!(f, "AS"),
+            TokenType::BREAK => write!(f, "BREAK"),
+            TokenType::CATCH => write!(f, "CATCH"),
             TokenType::CLASS => write!(f, "CLASS"),
+            TokenType::CONTINUE => write!(f, "CONTINUE"),
             TokenType::ELSE => write!(f, "ELSE"),
             TokenType::FALSE => write!(f, "FALSE"),
+            TokenType::FINALLY => write!(f, "FINALLY"),
+            TokenType::FROM => write!(f, "FROM"),
             TokenType::FUN => write!(f, "FUN"),
             TokenType::FOR => write!(f, "FOR"),
             TokenType::IF => write!(f, "IF"),
+            TokenType::IMPORT => write!(f, "IMPORT"),
+            TokenType::MATCH => write!(f, "MATCH"),
             TokenType::NIL => write!(f, "NIL"),
             TokenType::OR => write!(f, "OR"),
             TokenType::PRINT => write!(f, "PRINT"),
             TokenType::RETURN => write!(f, "RETURN"),
             TokenType::SUPER => write!(f, "SUPER"),
             TokenType::THIS => write!(f, "THIS"),
+            TokenType::THROW => write!(f, "THROW"),
             TokenType::TRUE => write!(f, "TRUE"),
+            TokenType::TRY => write!(f, "TRY"),
             TokenType::VAR => write!(f, "VAR"),
             TokenType::WHILE => write!(f, "WHILE"),
             TokenType::EOF => write!(f, "EOF"),
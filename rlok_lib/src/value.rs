@@ -0,0 +1,255 @@
+use super::chunk::Chunk;
+use super::environment::Environment;
+use super::error_handler::RuntimeError;
+use super::lit::LitType;
+use super::lox_callable::{Callable, LoxCallable, LoxFunction, NativeFunction};
+use super::lox_class::{LoxClass, LoxInstance};
+use super::lox_list::LoxList;
+use super::lox_map::LoxMap;
+use super::lox_module::LoxModule;
+use super::numeric::Number;
+use super::span::Span;
+use color_eyre::eyre::{Report, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(Arc<str>),
+    Function(Arc<Function>),
+    Native(NativeFunction),
+    Closure(Arc<Closure>),
+    Class(Arc<Mutex<Class>>),
+    Instance(Arc<Mutex<Instance>>),
+    BoundMethod(Arc<BoundMethod>),
+    List(LoxList),
+    Map(LoxMap),
+    Module(Arc<Module>),
+}
+
+pub type Globals = Arc<Mutex<HashMap<String, Value>>>;
+
+#[derive(Debug, Default)]
+pub struct Function {
+    pub name: String,
+    pub arity: usize,
+    pub upvalue_count: usize,
+    pub chunk: Chunk,
+}
+
+#[derive(Debug)]
+pub enum Upvalue {
+    Open(usize),
+    Closed(Value),
+}
+
+#[derive(Debug)]
+pub struct Closure {
+    pub function: Arc<Function>,
+    pub upvalues: Vec<Arc<Mutex<Upvalue>>>,
+    pub globals: Globals,
+}
+
+#[derive(Debug)]
+pub struct Class {
+    pub name: String,
+    pub methods: HashMap<String, Arc<Closure>>,
+}
+
+#[derive(Debug)]
+pub struct Instance {
+    pub class: Arc<Mutex<Class>>,
+    pub fields: HashMap<String, Value>,
+}
+
+#[derive(Debug)]
+pub struct Module {
+    pub name: String,
+    pub globals: Globals,
+}
+
+#[derive(Debug)]
+pub struct BoundMethod {
+    pub receiver: Value,
+    pub method: Arc<Closure>,
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(l), Value::Bool(r)) => l == r,
+            (Value::Str(l), Value::Str(r)) => l == r,
+            (l, r) => match (l.number(), r.number()) {
+                (Some(l), Some(r)) => l.compare(r) == Some(std::cmp::Ordering::Equal),
+                _ => l.ptr_eq(r),
+            },
+        }
+    }
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Int(i) => *i > 0,
+            Value::Float(n) => *n > 0.0,
+            Value::Str(s) => !s.is_empty(),
+            Value::Bool(b) => *b,
+            Value::List(list) => !list.is_empty(),
+            Value::Map(map) => !map.is_empty(),
+            _ => false,
+        }
+    }
+
+    pub fn quoted(&self) -> String {
+        match self {
+            Value::Str(s) => format!("{:?}", s),
+            other => other.to_string(),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Str(_) => "string",
+            Value::Class(_) => "class",
+            Value::Instance(_) => "instance",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+            Value::Module(_) => "module",
+            _ => "function",
+        }
+    }
+
+    /// Whether both are the same object; only lists, maps and objects compare this way.
+    pub fn ptr_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Function(l), Value::Function(r)) => Arc::ptr_eq(l, r),
+            (Value::Closure(l), Value::Closure(r)) => Arc::ptr_eq(l, r),
+            (Value::Class(l), Value::Class(r)) => Arc::ptr_eq(l, r),
+            (Value::Instance(l), Value::Instance(r)) => Arc::ptr_eq(l, r),
+            (Value::BoundMethod(l), Value::BoundMethod(r)) => Arc::ptr_eq(l, r),
+            (Value::Module(l), Value::Module(r)) => Arc::ptr_eq(l, r),
+            (Value::List(l), Value::List(r)) => l.ptr_eq(r),
+            (Value::Map(l), Value::Map(r)) => l.ptr_eq(r),
+            _ => false,
+        }
+    }
+
+    pub fn number(&self) -> Option<Number> {
+        match self {
+            Value::Int(i) => Some(Number::Int(*i)),
+            Value::Float(n) => Some(Number::Float(*n)),
+            _ => None,
+        }
+    }
+
+    /// The value as natives and collections see it. Functions, classes,
+    /// instances and modules become their tree-walker counterparts, which
+    /// keep hold of the VM object so `from_lit` can give it back.
+    pub fn to_lit(&self) -> LitType {
+        match self {
+            Value::Nil => LitType::Nil,
+            Value::Bool(b) => LitType::Bool(*b),
+            Value::Int(i) => LitType::Int(*i),
+            Value::Float(n) => LitType::Float(*n),
+            Value::Str(s) => LitType::Str(s.to_string()),
+            Value::Native(native) => LitType::Callable(LoxCallable::Native(native.clone())),
+            Value::List(list) => LitType::List(list.clone()),
+            Value::Map(map) => LitType::Map(map.clone()),
+            Value::Function(function) => Self::function(&function.name, self),
+            Value::Closure(closure) => Self::function(&closure.function.name, self),
+            Value::BoundMethod(bound) => Self::function(&bound.method.function.name, self),
+            Value::Class(class) => LitType::Callable(LoxCallable::Class(Self::class(class))),
+            Value::Instance(instance) => {
+                let class = Self::class(&instance.lock().unwrap().class);
+                LitType::Instance(LoxInstance::new(class).set_compiled(self.clone()))
+            }
+            Value::Module(module) => LitType::Module(
+                LoxModule::new(module.name.clone(), Environment::new(None))
+                    .set_compiled(self.clone()),
+            ),
+        }
+    }
+
+    fn function(name: &str, value: &Value) -> LitType {
+        LitType::Callable(LoxCallable::Function(
+            LoxFunction::new(name.into(), None).set_compiled(value.clone()),
+        ))
+    }
+
+    fn class(class: &Arc<Mutex<Class>>) -> LoxClass {
+        let name = class.lock().unwrap().name.clone();
+        LoxClass::new(name, None).set_compiled(Value::Class(class.clone()))
+    }
+
+    pub fn from_lit(lit: LitType, span: Span) -> Result<Value> {
+        let compiled = match &lit {
+            LitType::Callable(LoxCallable::Function(function)) => function.compiled(),
+            LitType::Callable(LoxCallable::Class(class)) => class.compiled(),
+            LitType::Instance(instance) => instance.compiled(),
+            LitType::Module(module) => module.compiled(),
+            _ => None,
+        };
+        if let Some(value) = compiled {
+            return Ok(value.clone());
+        }
+        match lit {
+            LitType::Nil => Ok(Value::Nil),
+            LitType::Bool(b) => Ok(Value::Bool(b)),
+            LitType::Int(i) => Ok(Value::Int(i)),
+            LitType::Float(n) => Ok(Value::Float(n)),
+            LitType::Str(s) => Ok(Value::Str(s.into())),
+            LitType::Callable(LoxCallable::Native(native)) => Ok(Value::Native(native)),
+            LitType::List(list) => Ok(Value::List(list)),
+            LitType::Map(map) => Ok(Value::Map(map)),
+            other => Err(Report::new(RuntimeError::UnsupportedNativeValue(
+                other.to_string(),
+                span,
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(n) => write!(f, "{}", n),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Function(function) => write!(f, "<fun {}>", function.name),
+            Value::Native(native) => write!(f, "<fun {}>", native.callee()),
+            Value::Closure(closure) => write!(f, "<fun {}>", closure.function.name),
+            Value::Class(class) => write!(f, "<class {}>", class.lock().unwrap().name),
+            Value::Instance(instance) => {
+                write!(
+                    f,
+                    "<instance {}>",
+                    instance.lock().unwrap().class.lock().unwrap().name
+                )
+            }
+            Value::BoundMethod(bound) => write!(f, "<fun {}>", bound.method.function.name),
+            Value::List(list) => write!(f, "{}", list),
+            Value::Map(map) => write!(f, "{}", map),
+            Value::Module(module) => write!(f, "<module {}>", module.name),
+        }
+    }
+}
+
+impl From<Number> for Value {
+    fn from(number: Number) -> Self {
+        match number {
+            Number::Int(i) => Value::Int(i),
+            Number::Float(n) => Value::Float(n),
+        }
+    }
+}
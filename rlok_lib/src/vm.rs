@@ -0,0 +1,866 @@
+use super::chunk::OpCode;
+use super::compiler::Compiler;
+use super::error_handler::{ModuleError, RuntimeError};
+use super::interpreter::Interpreter;
+use super::lit::LitType;
+use super::lox_callable::{Callable, NativeFunction};
+use super::lox_list::LoxList;
+use super::lox_map::LoxMap;
+use super::numeric::{Number, NumberError};
+use super::pattern::Pattern;
+use super::span::Span;
+use super::value::{
+    BoundMethod, Class, Closure, Function, Globals, Instance, Module, Upvalue, Value,
+};
+use color_eyre::eyre::{Report, Result};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::{span, trace, Level};
+
+pub(crate) const FRAMES_MAX: usize = 1024;
+
+#[derive(Debug)]
+struct CallFrame {
+    closure: Arc<Closure>,
+    ip: usize,
+    slots: usize,
+}
+
+impl CallFrame {
+    fn read_byte(&mut self) -> u8 {
+        let byte = self.closure.function.chunk.code[self.ip];
+        self.ip += 1;
+        byte
+    }
+
+    fn read_u16(&mut self) -> u16 {
+        let value = self.closure.function.chunk.read_u16(self.ip);
+        self.ip += 2;
+        value
+    }
+
+    fn read_constant(&mut self) -> Value {
+        let index = self.read_u16() as usize;
+        self.closure.function.chunk.constants[index].clone()
+    }
+
+    fn read_string(&mut self) -> Arc<str> {
+        match self.read_constant() {
+            Value::Str(string) => string,
+            other => other.to_string().into(),
+        }
+    }
+
+    fn span(&self) -> Span {
+        self.closure.function.chunk.spans[self.ip.saturating_sub(1)]
+    }
+}
+
+/// Where execution resumes when an error is raised inside a try block.
+#[derive(Debug)]
+struct Handler {
+    frames: usize,
+    stack: usize,
+    ip: usize,
+}
+
+#[derive(Debug)]
+pub struct VM {
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+    handlers: Vec<Handler>,
+    thrown: Option<Value>,
+    error_class: Arc<Mutex<Class>>,
+    globals: Globals,
+    builtins: HashMap<String, Value>,
+    modules: HashMap<PathBuf, Arc<Module>>,
+    open_upvalues: Vec<Arc<Mutex<Upvalue>>>,
+}
+
+impl Default for VM {
+    fn default() -> Self {
+        VM::new()
+    }
+}
+
+impl VM {
+    pub fn new() -> Self {
+        VM {
+            stack: Vec::new(),
+            frames: Vec::new(),
+            handlers: Vec::new(),
+            thrown: None,
+            error_class: Arc::new(Mutex::new(Class {
+                name: "Error".into(),
+                methods: HashMap::new(),
+            })),
+            globals: Globals::default(),
+            builtins: HashMap::new(),
+            modules: HashMap::new(),
+            open_upvalues: Vec::new(),
+        }
+    }
+
+    pub fn define_native(&mut self, native: NativeFunction) {
+        self.builtins.insert(native.callee(), Value::Native(native));
+    }
+
+    /// The globals and then the builtins, the same way `Environment::scopes` shows them.
+    pub fn scopes(&self) -> Vec<Vec<(String, String)>> {
+        [&*self.globals.lock().unwrap(), &self.builtins]
+            .into_iter()
+            .map(|scope| {
+                let mut values: Vec<(String, String)> = scope
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.quoted()))
+                    .collect();
+                values.sort();
+                values
+            })
+            .collect()
+    }
+
+    pub fn interpret(&mut self, function: Arc<Function>, inter: &mut Interpreter) -> Result<()> {
+        let span = span!(Level::TRACE, "vm");
+        let _enter = span.enter();
+        let closure = Arc::new(Closure {
+            function,
+            upvalues: Vec::new(),
+            globals: self.globals.clone(),
+        });
+        self.stack.push(Value::Closure(closure.clone()));
+        let mut frame = CallFrame {
+            closure,
+            ip: 0,
+            slots: 0,
+        };
+        match self.run(&mut frame, inter) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                trace!(error = %e, "Runtime error");
+                self.stack.clear();
+                self.frames.clear();
+                self.handlers.clear();
+                self.thrown = None;
+                self.open_upvalues.clear();
+                Err(e)
+            }
+        }
+    }
+
+    fn import(&mut self, path: &str, span: Span, inter: &mut Interpreter) -> Result<Arc<Module>> {
+        let error = |e| Report::new(RuntimeError::ImportError(e, span));
+        let file = inter.find_module(path).map_err(error)?;
+        if let Some(module) = self.modules.get(&file) {
+            return Ok(module.clone());
+        }
+        let ast = inter.parse_module(path, &file).map_err(error)?;
+        let mut compiler = Compiler::new(false);
+        let functions = compiler.compile(&ast).map_err(|e| {
+            inter.report(&e);
+            error(ModuleError::Invalid(path.into()))
+        })?;
+        trace!(module = path, "Running module");
+        let module = Arc::new(Module {
+            name: path.into(),
+            globals: Globals::default(),
+        });
+        inter.enter_module(file.clone());
+        let mut result = Ok(());
+        for function in functions {
+            result = self.run_module(function, module.globals.clone(), inter);
+            if result.is_err() {
+                break;
+            }
+        }
+        inter.leave_module();
+        result?;
+        self.modules.insert(file, module.clone());
+        Ok(module)
+    }
+
+    /// Runs a module's top-level code to completion on top of the current stack.
+    fn run_module(
+        &mut self,
+        function: Arc<Function>,
+        globals: Globals,
+        inter: &mut Interpreter,
+    ) -> Result<()> {
+        let closure = Arc::new(Closure {
+            function,
+            upvalues: Vec::new(),
+            globals,
+        });
+        let mut frame = CallFrame {
+            closure: closure.clone(),
+            ip: 0,
+            slots: self.stack.len(),
+        };
+        self.stack.push(Value::Closure(closure));
+        let frames = std::mem::take(&mut self.frames);
+        let handlers = std::mem::take(&mut self.handlers);
+        let result = self.run(&mut frame, inter);
+        self.frames = frames;
+        self.handlers = handlers;
+        result
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap_or(Value::Nil)
+    }
+
+    fn peek(&self, distance: usize) -> &Value {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+
+    /// Runs until the outermost frame returns, resuming at the nearest handler
+    /// whenever an error is raised inside a try block.
+    fn run(&mut self, frame: &mut CallFrame, inter: &mut Interpreter) -> Result<()> {
+        loop {
+            let Err(error) = self.execute(frame, inter) else {
+                return Ok(());
+            };
+            let Some(handler) = self.handlers.pop() else {
+                return Err(error);
+            };
+            let value = self.caught(error, inter)?;
+            trace!(error = %value, "Caught error");
+            while self.frames.len() > handler.frames {
+                *frame = self.frames.pop().unwrap();
+            }
+            self.close_upvalues(handler.stack);
+            self.stack.truncate(handler.stack);
+            self.stack.push(value);
+            frame.ip = handler.ip;
+        }
+    }
+
+    /// `Interpreter::caught` for the VM: the thrown value kept aside by `throw`,
+    /// or an `Error` instance with the interpreter's fields for the error.
+    fn caught(&mut self, error: Report, inter: &Interpreter) -> Result<Value> {
+        let error = match error.downcast::<RuntimeError>() {
+            Ok(RuntimeError::Throw(..)) if self.thrown.is_some() => {
+                return Ok(self.thrown.take().unwrap());
+            }
+            Ok(error) => error,
+            Err(error) => return Err(error),
+        };
+        let span = error.span();
+        let mut fields = HashMap::new();
+        for (name, value) in inter.error_fields(span, error.details()) {
+            fields.insert(
+                name.to_string(),
+                Value::from_lit(value, span.unwrap_or_default())?,
+            );
+        }
+        Ok(Value::Instance(Arc::new(Mutex::new(Instance {
+            class: self.error_class.clone(),
+            fields,
+        }))))
+    }
+
+    /// Raises `value` as an error, keeping it aside for whichever handler catches it.
+    fn throw(&mut self, value: Value, span: Span) -> Report {
+        let error = Report::new(RuntimeError::Throw(value.to_lit(), span));
+        self.thrown = Some(value);
+        error
+    }
+
+    fn execute(&mut self, frame: &mut CallFrame, inter: &mut Interpreter) -> Result<()> {
+        loop {
+            let byte = frame.read_byte();
+            let Some(op) = OpCode::from_byte(byte) else {
+                return Err(Report::new(RuntimeError::InvalidOpCode(byte)));
+            };
+            match op {
+                OpCode::Constant => {
+                    let constant = frame.read_constant();
+                    self.stack.push(constant);
+                }
+                OpCode::Nil => self.stack.push(Value::Nil),
+                OpCode::True => self.stack.push(Value::Bool(true)),
+                OpCode::False => self.stack.push(Value::Bool(false)),
+                OpCode::Pop => {
+                    self.pop();
+                }
+                OpCode::GetLocal => {
+                    let slot = frame.read_byte() as usize;
+                    self.stack.push(self.stack[frame.slots + slot].clone());
+                }
+                OpCode::SetLocal => {
+                    let slot = frame.read_byte() as usize;
+                    self.stack[frame.slots + slot] = self.peek(0).clone();
+                }
+                OpCode::GetGlobal => {
+                    let name = frame.read_string();
+                    let value = frame.closure.globals.lock().unwrap().get(&*name).cloned();
+                    match value.or_else(|| self.builtins.get(&*name).cloned()) {
+                        Some(value) => self.stack.push(value),
+                        None => {
+                            return Err(Report::new(RuntimeError::UndefinedVariable(
+                                name.to_string(),
+                                frame.span(),
+                            )))
+                        }
+                    }
+                }
+                OpCode::DefineGlobal => {
+                    let name = frame.read_string();
+                    let value = self.pop();
+                    frame
+                        .closure
+                        .globals
+                        .lock()
+                        .unwrap()
+                        .insert(name.to_string(), value);
+                }
+                OpCode::SetGlobal => {
+                    let name = frame.read_string();
+                    let value = self.peek(0).clone();
+                    let mut globals = frame.closure.globals.lock().unwrap();
+                    match globals
+                        .get_mut(&*name)
+                        .or_else(|| self.builtins.get_mut(&*name))
+                    {
+                        Some(slot) => *slot = value,
+                        None => {
+                            return Err(Report::new(RuntimeError::UndefinedVariable(
+                                name.to_string(),
+                                frame.span(),
+                            )))
+                        }
+                    }
+                }
+                OpCode::GetUpvalue => {
+                    let slot = frame.read_byte() as usize;
+                    let value = match &*frame.closure.upvalues[slot].lock().unwrap() {
+                        Upvalue::Open(index) => self.stack[*index].clone(),
+                        Upvalue::Closed(value) => value.clone(),
+                    };
+                    self.stack.push(value);
+                }
+                OpCode::SetUpvalue => {
+                    let slot = frame.read_byte() as usize;
+                    let value = self.peek(0).clone();
+                    match &mut *frame.closure.upvalues[slot].lock().unwrap() {
+                        Upvalue::Open(index) => self.stack[*index] = value,
+                        Upvalue::Closed(closed) => *closed = value,
+                    }
+                }
+                OpCode::GetProperty => {
+                    let name = frame.read_string();
+                    if let Value::Module(module) = self.peek(0).clone() {
+                        let Some(value) = module.globals.lock().unwrap().get(&*name).cloned()
+                        else {
+                            return Err(Report::new(RuntimeError::MissingExport(
+                                module.name.clone(),
+                                name.to_string(),
+                                frame.span(),
+                            )));
+                        };
+                        self.pop();
+                        self.stack.push(value);
+                        continue;
+                    }
+                    let Value::Instance(instance) = self.peek(0).clone() else {
+                        return Err(Report::new(RuntimeError::OnlyInstancesHaveProperties(
+                            frame.span(),
+                        )));
+                    };
+                    let field = instance.lock().unwrap().fields.get(&*name).cloned();
+                    if let Some(value) = field {
+                        self.pop();
+                        self.stack.push(value);
+                    } else {
+                        let class = instance.lock().unwrap().class.clone();
+                        let bound = self.bind_method(&class, &name, frame.span())?;
+                        self.pop();
+                        self.stack.push(bound);
+                    }
+                }
+                OpCode::SetProperty => {
+                    let name = frame.read_string();
+                    let Value::Instance(instance) = self.peek(1).clone() else {
+                        return Err(Report::new(RuntimeError::OnlyInstancesHaveFields(
+                            frame.span(),
+                        )));
+                    };
+                    let value = self.pop();
+                    instance
+                        .lock()
+                        .unwrap()
+                        .fields
+                        .insert(name.to_string(), value.clone());
+                    self.pop();
+                    self.stack.push(value);
+                }
+                OpCode::GetSuper => {
+                    let name = frame.read_string();
+                    let Value::Class(superclass) = self.pop() else {
+                        return Err(Report::new(RuntimeError::SuperclassMustBeClass(
+                            frame.span(),
+                        )));
+                    };
+                    let bound = self.bind_method(&superclass, &name, frame.span())?;
+                    self.pop();
+                    self.stack.push(bound);
+                }
+                OpCode::BuildList => {
+                    let count = frame.read_u16() as usize;
+                    let mut items = Vec::with_capacity(count);
+                    for value in self.stack.split_off(self.stack.len() - count) {
+                        items.push(value.to_lit());
+                    }
+                    self.stack.push(Value::List(LoxList::new(items)));
+                }
+                OpCode::BuildMap => {
+                    let count = frame.read_u16() as usize;
+                    let map = LoxMap::new();
+                    let entries = self.stack.split_off(self.stack.len() - count * 2);
+                    for pair in entries.chunks(2) {
+                        let key = Self::key(pair[0].clone(), frame.span())?;
+                        map.set(key, pair[1].to_lit());
+                    }
+                    self.stack.push(Value::Map(map));
+                }
+                OpCode::GetIndex => {
+                    let index = self.pop();
+                    let item = match self.pop() {
+                        Value::List(list) => {
+                            let i = Self::list_position(index, frame.span())?;
+                            list.get(i).ok_or_else(|| {
+                                Report::new(RuntimeError::IndexOutOfBounds(
+                                    i,
+                                    list.len(),
+                                    frame.span(),
+                                ))
+                            })?
+                        }
+                        Value::Map(map) => {
+                            let key = Self::key(index, frame.span())?;
+                            map.get(&key).ok_or_else(|| {
+                                Report::new(RuntimeError::MissingKey(key.quoted(), frame.span()))
+                            })?
+                        }
+                        other => {
+                            return Err(Report::new(RuntimeError::NotIndexable(
+                                other.type_name().into(),
+                                frame.span(),
+                            )))
+                        }
+                    };
+                    self.stack.push(Value::from_lit(item, frame.span())?);
+                }
+                OpCode::SetIndex => {
+                    let value = self.pop();
+                    let index = self.pop();
+                    let item = value.to_lit();
+                    match self.pop() {
+                        Value::List(list) => {
+                            let i = Self::list_position(index, frame.span())?;
+                            if list.set(i, item).is_none() {
+                                return Err(Report::new(RuntimeError::IndexOutOfBounds(
+                                    i,
+                                    list.len(),
+                                    frame.span(),
+                                )));
+                            }
+                        }
+                        Value::Map(map) => map.set(Self::key(index, frame.span())?, item),
+                        other => {
+                            return Err(Report::new(RuntimeError::NotIndexable(
+                                other.type_name().into(),
+                                frame.span(),
+                            )))
+                        }
+                    }
+                    self.stack.push(value);
+                }
+                OpCode::Import => {
+                    let path = frame.read_string();
+                    let module = self.import(&path, frame.span(), inter)?;
+                    self.stack.push(Value::Module(module));
+                }
+                OpCode::Stringify => {
+                    let value = self.pop();
+                    self.stack.push(Value::Str(value.to_string().into()));
+                }
+                OpCode::Equal => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.stack.push(Value::Bool(left == right));
+                }
+                OpCode::Greater | OpCode::Less => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let (l, r) = Self::number_operands(left, right, frame.span())?;
+                    let expected = if op == OpCode::Greater {
+                        Ordering::Greater
+                    } else {
+                        Ordering::Less
+                    };
+                    self.stack.push(Value::Bool(l.compare(r) == Some(expected)));
+                }
+                OpCode::Subtract
+                | OpCode::Multiply
+                | OpCode::Divide
+                | OpCode::Modulo
+                | OpCode::FloorDivide
+                | OpCode::Power
+                | OpCode::BitAnd
+                | OpCode::BitOr
+                | OpCode::BitXor
+                | OpCode::ShiftLeft
+                | OpCode::ShiftRight => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let (l, r) = Self::number_operands(left, right, frame.span())?;
+                    let result = match op {
+                        OpCode::Subtract => l.checked_sub(r),
+                        OpCode::Multiply => l.checked_mul(r),
+                        OpCode::Divide => l.checked_div(r),
+                        OpCode::Modulo => l.checked_rem(r),
+                        OpCode::FloorDivide => l.checked_floor_div(r),
+                        OpCode::Power => l.checked_pow(r),
+                        OpCode::BitAnd => l.bit_and(r),
+                        OpCode::BitOr => l.bit_or(r),
+                        OpCode::BitXor => l.bit_xor(r),
+                        OpCode::ShiftLeft => l.shift_left(r),
+                        _ => l.shift_right(r),
+                    };
+                    self.stack.push(Self::arithmetic(result, frame.span())?);
+                }
+                OpCode::Add => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    match (left, right) {
+                        (Value::Str(l), Value::Str(r)) => {
+                            self.stack.push(Value::Str(format!("{}{}", l, r).into()))
+                        }
+                        (left, right) => {
+                            let (l, r) = Self::number_operands(left, right, frame.span())?;
+                            let sum = Self::arithmetic(l.checked_add(r), frame.span())?;
+                            self.stack.push(sum);
+                        }
+                    }
+                }
+                OpCode::Not => match self.pop() {
+                    Value::Bool(b) => self.stack.push(Value::Bool(!b)),
+                    Value::Nil => self.stack.push(Value::Bool(true)),
+                    _ => return Err(Report::new(RuntimeError::RighthandBoolorNil(frame.span()))),
+                },
+                OpCode::Negate => {
+                    let Some(n) = self.pop().number() else {
+                        return Err(Report::new(RuntimeError::InvalidUnary(frame.span())));
+                    };
+                    let negated = Self::arithmetic(n.checked_neg(), frame.span())?;
+                    self.stack.push(negated);
+                }
+                OpCode::BitNot => {
+                    let Some(n) = self.pop().number() else {
+                        return Err(Report::new(RuntimeError::NonIntegralOperand(frame.span())));
+                    };
+                    let inverted = Self::arithmetic(n.bit_not(), frame.span())?;
+                    self.stack.push(inverted);
+                }
+                OpCode::Print => {
+                    let value = self.pop();
+                    println!("{}", value);
+                }
+                OpCode::Jump => {
+                    let offset = frame.read_u16() as usize;
+                    frame.ip += offset;
+                }
+                OpCode::JumpIfFalse => {
+                    let offset = frame.read_u16() as usize;
+                    if !self.peek(0).is_truthy() {
+                        frame.ip += offset;
+                    }
+                }
+                OpCode::Loop => {
+                    let offset = frame.read_u16() as usize;
+                    frame.ip -= offset;
+                }
+                OpCode::MatchLiteral => {
+                    let literal = self.pop();
+                    let value = self.pop();
+                    self.stack.push(Value::Bool(value == literal));
+                }
+                OpCode::MatchRange => {
+                    let inclusive = frame.read_byte() == 1;
+                    let end = self.pop();
+                    let start = self.pop();
+                    let value = self.pop();
+                    let matched = match (value.number(), start.number(), end.number()) {
+                        (Some(n), Some(start), Some(end)) => {
+                            Pattern::in_range(n, start, end, inclusive)
+                        }
+                        _ => false,
+                    };
+                    self.stack.push(Value::Bool(matched));
+                }
+                OpCode::Call => {
+                    let argc = frame.read_byte() as usize;
+                    self.call_value(frame, argc, inter)?;
+                }
+                OpCode::Closure => {
+                    let Value::Function(function) = frame.read_constant() else {
+                        return Err(Report::new(RuntimeError::NotCallable(frame.span())));
+                    };
+                    let mut upvalues = Vec::with_capacity(function.upvalue_count);
+                    for _ in 0..function.upvalue_count {
+                        let is_local = frame.read_byte() == 1;
+                        let index = frame.read_byte() as usize;
+                        if is_local {
+                            upvalues.push(self.capture_upvalue(frame.slots + index));
+                        } else {
+                            upvalues.push(frame.closure.upvalues[index].clone());
+                        }
+                    }
+                    self.stack.push(Value::Closure(Arc::new(Closure {
+                        function,
+                        upvalues,
+                        globals: frame.closure.globals.clone(),
+                    })));
+                }
+                OpCode::CloseUpvalue => {
+                    self.close_upvalues(self.stack.len() - 1);
+                    self.pop();
+                }
+                OpCode::Return => {
+                    let result = self.pop();
+                    while self
+                        .handlers
+                        .last()
+                        .is_some_and(|handler| handler.frames >= self.frames.len())
+                    {
+                        self.handlers.pop();
+                    }
+                    self.close_upvalues(frame.slots);
+                    self.stack.truncate(frame.slots);
+                    match self.frames.pop() {
+                        Some(previous) => {
+                            self.stack.push(result);
+                            *frame = previous;
+                        }
+                        None => return Ok(()),
+                    }
+                }
+                OpCode::PushHandler => {
+                    let offset = frame.read_u16() as usize;
+                    self.handlers.push(Handler {
+                        frames: self.frames.len(),
+                        stack: self.stack.len(),
+                        ip: frame.ip + offset,
+                    });
+                }
+                OpCode::PopHandler => {
+                    self.handlers.pop();
+                }
+                OpCode::Throw => {
+                    let value = self.pop();
+                    return Err(self.throw(value, frame.span()));
+                }
+                OpCode::Class => {
+                    let name = frame.read_string();
+                    self.stack.push(Value::Class(Arc::new(Mutex::new(Class {
+                        name: name.to_string(),
+                        methods: HashMap::new(),
+                    }))));
+                }
+                OpCode::Inherit => {
+                    let Value::Class(superclass) = self.peek(1).clone() else {
+                        return Err(Report::new(RuntimeError::SuperclassMustBeClass(
+                            frame.span(),
+                        )));
+                    };
+                    if let Value::Class(subclass) = self.pop() {
+                        let methods = superclass.lock().unwrap().methods.clone();
+                        subclass.lock().unwrap().methods.extend(methods);
+                    }
+                }
+                OpCode::Method => {
+                    let name = frame.read_string();
+                    let method = self.pop();
+                    if let (Value::Closure(method), Value::Class(class)) = (method, self.peek(0)) {
+                        class
+                            .lock()
+                            .unwrap()
+                            .methods
+                            .insert(name.to_string(), method);
+                    }
+                }
+            }
+        }
+    }
+
+    fn number_operands(left: Value, right: Value, span: Span) -> Result<(Number, Number)> {
+        match (left.number(), right.number()) {
+            (Some(l), Some(r)) => Ok((l, r)),
+            _ => match (left, right) {
+                (Value::Str(_), Value::Str(_)) => {
+                    Err(Report::new(RuntimeError::InvalidStringConcat(span)))
+                }
+                _ => Err(Report::new(RuntimeError::BinaryTypeMismatch(span))),
+            },
+        }
+    }
+
+    fn list_position(index: Value, span: Span) -> Result<i64> {
+        match index {
+            Value::Int(i) => Ok(i),
+            other => Err(Report::new(RuntimeError::InvalidIndex(
+                other.type_name().into(),
+                span,
+            ))),
+        }
+    }
+
+    fn key(value: Value, span: Span) -> Result<LitType> {
+        let key = value.to_lit();
+        if key.is_hashable() {
+            Ok(key)
+        } else {
+            Err(Report::new(RuntimeError::InvalidKey(
+                value.type_name().into(),
+                span,
+            )))
+        }
+    }
+
+    fn arithmetic(result: Result<Number, NumberError>, span: Span) -> Result<Value> {
+        match result {
+            Ok(number) => Ok(number.into()),
+            Err(NumberError::DivideByZero) => Err(Report::new(RuntimeError::DivideByZero(span))),
+            Err(NumberError::Overflow) => Err(Report::new(RuntimeError::IntegerOverflow(span))),
+            Err(NumberError::NotIntegral) => {
+                Err(Report::new(RuntimeError::NonIntegralOperand(span)))
+            }
+            Err(NumberError::InvalidShift) => Err(Report::new(RuntimeError::InvalidShift(span))),
+        }
+    }
+
+    fn bind_method(&mut self, class: &Arc<Mutex<Class>>, name: &str, span: Span) -> Result<Value> {
+        let Some(method) = class.lock().unwrap().methods.get(name).cloned() else {
+            return Err(Report::new(RuntimeError::UndefinedProperty(
+                name.into(),
+                span,
+            )));
+        };
+        Ok(Value::BoundMethod(Arc::new(BoundMethod {
+            receiver: self.peek(0).clone(),
+            method,
+        })))
+    }
+
+    fn call_value(
+        &mut self,
+        frame: &mut CallFrame,
+        argc: usize,
+        inter: &mut Interpreter,
+    ) -> Result<()> {
+        let slot = self.stack.len() - 1 - argc;
+        match self.stack[slot].clone() {
+            Value::Closure(closure) => self.call_closure(frame, closure, argc),
+            Value::Native(native) => {
+                let span = frame.span();
+                if argc != native.arity() {
+                    return Err(Report::new(RuntimeError::IncorrectArgumentCount(
+                        native.arity(),
+                        argc,
+                        span,
+                    )));
+                }
+                let args = self
+                    .stack
+                    .split_off(slot + 1)
+                    .iter()
+                    .map(Value::to_lit)
+                    .collect();
+                self.stack.pop();
+                let result = native
+                    .call(inter, args)
+                    .map_err(|error| Report::new(RuntimeError::from_native(error, span)))?;
+                self.stack.push(Value::from_lit(result, span)?);
+                Ok(())
+            }
+            Value::Class(class) => {
+                self.stack[slot] = Value::Instance(Arc::new(Mutex::new(Instance {
+                    class: class.clone(),
+                    fields: HashMap::new(),
+                })));
+                let initializer = class.lock().unwrap().methods.get("init").cloned();
+                match initializer {
+                    Some(initializer) => self.call_closure(frame, initializer, argc),
+                    None if argc != 0 => Err(Report::new(RuntimeError::IncorrectArgumentCount(
+                        0,
+                        argc,
+                        frame.span(),
+                    ))),
+                    None => Ok(()),
+                }
+            }
+            Value::BoundMethod(bound) => {
+                self.stack[slot] = bound.receiver.clone();
+                self.call_closure(frame, bound.method.clone(), argc)
+            }
+            _ => Err(Report::new(RuntimeError::NotCallable(frame.span()))),
+        }
+    }
+
+    fn call_closure(
+        &mut self,
+        frame: &mut CallFrame,
+        closure: Arc<Closure>,
+        argc: usize,
+    ) -> Result<()> {
+        if argc != closure.function.arity {
+            return Err(Report::new(RuntimeError::IncorrectArgumentCount(
+                closure.function.arity,
+                argc,
+                frame.span(),
+            )));
+        }
+        if self.frames.len() >= FRAMES_MAX {
+            return Err(Report::new(RuntimeError::StackOverflow(frame.span())));
+        }
+        let callee = CallFrame {
+            closure,
+            ip: 0,
+            slots: self.stack.len() - argc - 1,
+        };
+        self.frames.push(std::mem::replace(frame, callee));
+        Ok(())
+    }
+
+    fn capture_upvalue(&mut self, index: usize) -> Arc<Mutex<Upvalue>> {
+        for upvalue in &self.open_upvalues {
+            if let Upvalue::Open(open) = &*upvalue.lock().unwrap() {
+                if *open == index {
+                    return upvalue.clone();
+                }
+            }
+        }
+        let upvalue = Arc::new(Mutex::new(Upvalue::Open(index)));
+        self.open_upvalues.push(upvalue.clone());
+        upvalue
+    }
+
+    fn close_upvalues(&mut self, last: usize) {
+        let stack = &self.stack;
+        self.open_upvalues.retain(|upvalue| {
+            let index = match &*upvalue.lock().unwrap() {
+                Upvalue::Open(index) => *index,
+                Upvalue::Closed(_) => return false,
+            };
+            if index >= last {
+                *upvalue.lock().unwrap() = Upvalue::Closed(stack[index].clone());
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
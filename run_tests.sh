@@ -18,3 +18,68 @@ printf "\n=== For Loop (Fibonacci) Test ===\n"
 ./target/release/rlok ./lang_tests/test_for_loop_fib.lox
 printf "\n=== Function Test ===\n"
 ./target/release/rlok ./lang_tests/test_functions.lox
+printf "\n=== Classes Test ===\n"
+./target/release/rlok ./lang_tests/test_classes.lox
+printf "\n=== Return Function (Fibonacci) Test ===\n"
+./target/release/rlok ./lang_tests/test_return_function.lox
+printf "\n=== Closures Test ===\n"
+./target/release/rlok ./lang_tests/test_closures.lox
+printf "\n=== Resolver Test ===\n"
+./target/release/rlok ./lang_tests/test_resolver.lox
+printf "\n=== Resolver Errors Test ===\n"
+./target/release/rlok ./lang_tests/test_resolver_errors.lox
+printf "\n=== Comparison Test ===\n"
+./target/release/rlok ./lang_tests/test_comparison.lox
+printf "\n=== Parser Errors Test ===\n"
+./target/release/rlok ./lang_tests/test_parser_errors.lox
+printf "\n=== Diagnostics Test ===\n"
+./target/release/rlok --no-color ./lang_tests/test_diagnostics.lox
+printf "\n=== Standard Library Test ===\n"
+./target/release/rlok ./lang_tests/test_stdlib.lox
+printf "\n=== Numbers Test ===\n"
+./target/release/rlok ./lang_tests/test_numbers.lox
+printf "\n=== Lists Test ===\n"
+./target/release/rlok ./lang_tests/test_lists.lox
+printf "\n=== Maps Test ===\n"
+./target/release/rlok ./lang_tests/test_maps.lox
+printf "\n=== Strings Test ===\n"
+./target/release/rlok ./lang_tests/test_strings.lox
+printf "\n=== Escape Errors Test ===\n"
+./target/release/rlok --no-color ./lang_tests/test_escape_errors.lox
+printf "\n=== Number Errors Test ===\n"
+RLOK_HISTORY=/dev/null ./target/release/rlok --no-color < ./lang_tests/test_number_errors.lox
+printf "\n=== Break and Continue Test ===\n"
+./target/release/rlok ./lang_tests/test_break_continue.lox
+printf "\n=== Loop Errors Test ===\n"
+./target/release/rlok --no-color ./lang_tests/test_loop_errors.lox
+printf "\n=== Modules Test ===\n"
+./target/release/rlok --no-color ./lang_tests/test_modules.lox
+printf "\n=== Exceptions Test ===\n"
+./target/release/rlok --no-color ./lang_tests/test_exceptions.lox
+printf "\n=== Return Test ===\n"
+./target/release/rlok ./lang_tests/test_return.lox
+printf "\n=== Lambdas Test ===\n"
+./target/release/rlok ./lang_tests/test_lambdas.lox
+printf "\n=== Operators Test ===\n"
+./target/release/rlok ./lang_tests/test_operators.lox
+printf "\n=== Match Test ===\n"
+./target/release/rlok ./lang_tests/test_match.lox
+printf "\n=== REPL Test ===\n"
+RLOK_HISTORY=/dev/null ./target/release/rlok < ./lang_tests/test_repl.lox
+printf "\n=== REPL Commands Test ===\n"
+RLOK_HISTORY=/dev/null ./target/release/rlok < ./lang_tests/repl_commands.txt
+printf "\n=== Debugger Test ===\n"
+./target/release/rlok debug ./lang_tests/test_debugger.lox < ./lang_tests/debug_session.txt
+printf "\n=== Debug Adapter Test ===\n"
+while IFS= read -r request; do
+	printf 'Content-Length: %d\r\n\r\n%s' "${#request}" "$request"
+done < ./lang_tests/dap_session.jsonl | ./target/release/rlok dap
+printf "\n=== Language Server Test ===\n"
+while IFS= read -r request; do
+	printf 'Content-Length: %d\r\n\r\n%s' "${#request}" "$request"
+done < ./lang_tests/lsp_session.jsonl | ./target/release/rlok lsp
+printf "\n=== Bytecode VM Test ===\n"
+for test in ./lang_tests/*.lox; do
+	printf "\n--- %s (vm) ---\n" "$test"
+	./target/release/rlok --vm "$test"
+done